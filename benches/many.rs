@@ -0,0 +1,44 @@
+//! Compares formatting a column of numbers into a `GArena` and into a
+//! `String` each: `cargo bench --bench many`.
+
+use gpoint::{GArena, GPoint, Spec};
+use std::time::{Duration, Instant};
+
+fn time(name: &str, count: usize, format: impl FnOnce() -> usize) -> Duration {
+    let start = Instant::now();
+    let len = format();
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {:.0} ns/value ({} bytes)",
+        name,
+        elapsed.as_nanos() as f64 / count as f64,
+        len
+    );
+    elapsed
+}
+
+fn main() {
+    let values: Vec<f64> = (1..=1_000_000).map(|i| (i as f64).sqrt() * 1e-3).collect();
+    let spec = Spec::parse("%.6g").unwrap();
+    let mut arena = GArena::new();
+    time("GArena::format_many()", values.len(), || {
+        arena.format_many(&values, &spec).unwrap();
+        arena.as_str().len()
+    });
+    // mostly small integers, as in counters, and zeros, as in sparse data
+    let integers: Vec<f64> = (0..1_000_000)
+        .map(|i| if i % 3 == 0 { 0. } else { (i % 5000) as f64 })
+        .collect();
+    arena.clear();
+    time("GArena::format_many() of integers", integers.len(), || {
+        arena.format_many(&integers, &spec).unwrap();
+        arena.as_str().len()
+    });
+    time("GPoint::try_to_string_with()", values.len(), || {
+        let strings: Vec<String> = values
+            .iter()
+            .map(|&value| GPoint(value).try_to_string_with(&spec).unwrap())
+            .collect();
+        strings.iter().map(String::len).sum()
+    });
+}
@@ -0,0 +1,50 @@
+//! Compares formatting rows of coordinates with one `snprintf()` call per
+//! row and with one per value: `cargo bench --bench rows`.
+
+use gpoint::{Buffer, Options, Spec};
+use std::time::{Duration, Instant};
+
+fn time(name: &str, rows: &[[f64; 3]], mut format: impl FnMut(&[f64; 3]) -> usize) -> Duration {
+    let start = Instant::now();
+    let mut len = 0;
+    for row in rows {
+        len += format(row);
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {:.0} ns/row ({} bytes)",
+        name,
+        elapsed.as_nanos() as f64 / rows.len() as f64,
+        len
+    );
+    elapsed
+}
+
+fn main() {
+    let rows: Vec<[f64; 3]> = (1..=1_000_000)
+        .map(|i| {
+            let x = (i as f64).sqrt();
+            [x, x * 1e-3, -x * 1e5]
+        })
+        .collect();
+    let spec = Spec::parse("%.6g").unwrap();
+    let mut buffer = Buffer::new();
+    time("format_row()", &rows, |row| {
+        buffer.format_row(row, " ", &spec).unwrap().len()
+    });
+    // any option disables the single call
+    let options = Options::new().unsigned_nan(true);
+    time("format_row_with()", &rows, |row| {
+        buffer
+            .format_row_with(row, " ", &spec, options)
+            .unwrap()
+            .len()
+    });
+    time("format() per value", &rows, |row| {
+        let mut len = 2;
+        for &value in row {
+            len += buffer.format(value, &spec).unwrap().len();
+        }
+        len
+    });
+}
@@ -0,0 +1,28 @@
+//! Compares the algorithms finding the shortest digits of numbers:
+//! `cargo bench --bench shortest`.
+
+use gpoint::{GPoint, Notation, Options, Shortest};
+use std::fmt::Write;
+use std::time::Instant;
+
+fn main() {
+    let values: Vec<f64> = (1..=100_000)
+        .map(|i| (i as f64).sqrt() * 10f64.powi(i % 40 - 20))
+        .collect();
+    let go = Options::new().notation(Notation::Go);
+    let mut out = String::new();
+    for algorithm in [Shortest::Grisu, Shortest::Dragon4] {
+        let options = go.shortest(algorithm);
+        let start = Instant::now();
+        for &value in &values {
+            out.clear();
+            write!(out, "{}", GPoint(value).with(options)).unwrap();
+        }
+        let elapsed = start.elapsed();
+        println!(
+            "{:?}: {:.0} ns/number",
+            algorithm,
+            elapsed.as_nanos() as f64 / values.len() as f64
+        );
+    }
+}
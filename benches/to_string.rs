@@ -0,0 +1,37 @@
+//! Compares the ways of formatting numbers into strings:
+//! `cargo bench --bench to_string`.
+
+use gpoint::GPoint;
+use std::time::{Duration, Instant};
+
+fn time(name: &str, values: &[f64], format: impl Fn(f64) -> String) -> Duration {
+    let start = Instant::now();
+    let mut len = 0;
+    for &value in values {
+        len += format(value).len();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {:.0} ns/number ({} bytes)",
+        name,
+        elapsed.as_nanos() as f64 / values.len() as f64,
+        len
+    );
+    elapsed
+}
+
+fn main() {
+    let values: Vec<f64> = (1..=1_000_000)
+        .map(|i| (i as f64).sqrt() * 10f64.powi(i % 40 - 20))
+        .collect();
+    time("format!(\"{}\")", &values, |value| {
+        format!("{}", GPoint(value))
+    });
+    time("to_string()", &values, |value| GPoint(value).to_string());
+    time("to_string_fast()", &values, |value| {
+        GPoint(value).to_string_fast()
+    });
+    time("to_string_prec(3)", &values, |value| {
+        GPoint(value).to_string_prec(3)
+    });
+}
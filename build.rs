@@ -0,0 +1,155 @@
+//! Probes the target C library with its C compiler, for the cfgs behind
+//! `BUILD_CAPABILITIES`: without a C compiler nothing is probed, and the
+//! crate relies on its runtime checks alone. Targets without any C library,
+//! e.g. `wasm32-unknown-unknown`, get the `gpoint_no_libc` cfg instead.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Compiles, links or runs tiny C programs for the target.
+struct Probe {
+    compiler: String,
+    out_dir: PathBuf,
+}
+
+impl Probe {
+    fn new() -> Option<Self> {
+        let target = env::var("TARGET").ok()?;
+        // `cl` takes other arguments: MSVC targets aren't probed
+        if env::var("CARGO_CFG_TARGET_ENV").ok()? == "msvc" {
+            return None;
+        }
+        let compiler = [
+            format!("CC_{}", target),
+            format!("CC_{}", target.replace('-', "_")),
+            "TARGET_CC".to_string(),
+            "CC".to_string(),
+        ]
+        .iter()
+        .find_map(|name| env::var(name).ok())
+        .unwrap_or_else(|| "cc".to_string());
+        let probe = Probe {
+            compiler,
+            out_dir: PathBuf::from(env::var_os("OUT_DIR")?),
+        };
+        probe
+            .build("compiler", "int main(void) { return 0; }", true)
+            .map(|_| probe)
+    }
+
+    /// Compiles `source`, linking it if `link`, and returns the executable.
+    fn build(&self, name: &str, source: &str, link: bool) -> Option<PathBuf> {
+        let source_path = self.out_dir.join(format!("probe_{}.c", name));
+        let output = self.out_dir.join(format!("probe_{}", name));
+        fs::write(&source_path, source).ok()?;
+        let mut command = Command::new(&self.compiler);
+        if !link {
+            command.arg("-c");
+        }
+        let status = command
+            .arg(&source_path)
+            .arg("-o")
+            .arg(&output)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .ok()?;
+        if status.success() {
+            Some(output)
+        } else {
+            None
+        }
+    }
+
+    /// Whether `source` compiles, and links if `link`.
+    fn compiles(&self, name: &str, source: &str, link: bool) -> bool {
+        self.build(name, source, link).is_some()
+    }
+
+    /// Whether `source` builds and exits successfully, or `None` if it
+    /// can't be run, since it is cross-compiled.
+    fn runs(&self, name: &str, source: &str) -> Option<bool> {
+        if env::var("HOST").ok()? != env::var("TARGET").ok()? {
+            return None;
+        }
+        let executable = self.build(name, source, true)?;
+        Some(Command::new(executable).status().ok()?.success())
+    }
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=CC");
+    println!("cargo:rerun-if-env-changed=TARGET_CC");
+    for cfg in [
+        "gpoint_probed",
+        "gpoint_hex_float",
+        "gpoint_snprintf_l",
+        "gpoint_strfromd",
+        "gpoint_no_libc",
+    ] {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+    println!("cargo:rustc-check-cfg=cfg(gpoint_long_double, values(\"8\", \"12\", \"16\"))");
+
+    // the `libc` crate is empty there, and `cc` would probe the host
+    if env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("wasm32")
+        && env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("unknown")
+    {
+        println!("cargo:rustc-cfg=gpoint_no_libc");
+        return;
+    }
+
+    let probe = match Probe::new() {
+        Some(probe) => probe,
+        None => return,
+    };
+    println!("cargo:rustc-cfg=gpoint_probed");
+
+    // C99 requires `%a`: only check it when the probe can run
+    let hex_float = probe.runs(
+        "hex_float",
+        "#include <stdio.h>\n#include <string.h>\n\
+         int main(void) { char buf[32]; snprintf(buf, sizeof buf, \"%a\", 1.0);\n\
+         return strncmp(buf, \"0x1\", 3) != 0; }\n",
+    );
+    if hex_float.unwrap_or(true) {
+        println!("cargo:rustc-cfg=gpoint_hex_float");
+    }
+    // declared here since the headers declaring them differ, only linking
+    // tells whether they exist
+    if probe.compiles(
+        "snprintf_l",
+        "#include <stddef.h>\n\
+         extern int snprintf_l(char *, size_t, void *, const char *, ...);\n\
+         int main(void) { char buf[32]; return snprintf_l(buf, sizeof buf, NULL, \"%g\", 1.0) < 0; }\n",
+        true,
+    ) {
+        println!("cargo:rustc-cfg=gpoint_snprintf_l");
+    }
+    if probe.compiles(
+        "strfromd",
+        "#include <stddef.h>\n\
+         extern int strfromd(char *, size_t, const char *, double);\n\
+         int main(void) { char buf[32]; return strfromd(buf, sizeof buf, \"%g\", 1.0) < 0; }\n",
+        true,
+    ) {
+        println!("cargo:rustc-cfg=gpoint_strfromd");
+    }
+    for size in [8, 12, 16] {
+        if probe.compiles(
+            &format!("long_double_{}", size),
+            &format!(
+                "char probe[sizeof(long double) == {} ? 1 : -1];\n\
+                 int main(void) {{ return probe[0]; }}\n",
+                size
+            ),
+            false,
+        ) {
+            println!("cargo:rustc-cfg=gpoint_long_double=\"{}\"", size);
+            break;
+        }
+    }
+}
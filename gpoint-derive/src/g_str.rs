@@ -0,0 +1,147 @@
+//! `g_str!`, formatting literals at compile time
+
+use proc_macro::{TokenStream, TokenTree};
+
+pub(crate) fn g_str(input: TokenStream) -> Result<TokenStream, String> {
+    let mut tokens: Vec<TokenTree> = input.into_iter().collect();
+    let spec = match tokens.first() {
+        Some(TokenTree::Literal(literal)) if literal.to_string().starts_with('"') => {
+            let spec = literal.to_string();
+            match tokens.get(1) {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => (),
+                _ => return Err("expected a comma after the specification".to_owned()),
+            }
+            tokens.drain(..2);
+            Spec::parse(spec.trim_matches('"'))?
+        }
+        _ => Spec::default(),
+    };
+    let (negative, literal) = match tokens.as_slice() {
+        [TokenTree::Literal(literal)] => (false, literal),
+        [TokenTree::Punct(minus), TokenTree::Literal(literal)] if minus.as_char() == '-' => {
+            (true, literal)
+        }
+        _ => return Err("expected a number literal".to_owned()),
+    };
+    let value = number(&literal.to_string())?;
+    let output = spec.format(if negative { -value } else { value });
+    Ok(TokenTree::Literal(proc_macro::Literal::string(&output)).into())
+}
+
+/// Parses a decimal integer or float literal, with an optional type suffix.
+fn number(literal: &str) -> Result<f64, String> {
+    let digits = literal.replace('_', "");
+    let invalid = || format!("`{}` isn't a decimal number literal", literal);
+    if let Some(digits) = digits.strip_suffix("f32") {
+        return digits.parse::<f32>().map(f64::from).map_err(|_| invalid());
+    }
+    let digits = digits.strip_suffix("f64").unwrap_or(&digits);
+    let integer = [
+        "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128",
+    ]
+    .iter()
+    .find_map(|suffix| digits.strip_suffix(suffix))
+    .filter(|digits| digits.bytes().all(|c| c.is_ascii_digit()));
+    integer.unwrap_or(digits).parse().map_err(|_| invalid())
+}
+
+/// A `%g`, `%e` or `%f` specification, with the `+` and `#` flags and a
+/// precision, formatted as glibc does.
+#[derive(Default)]
+struct Spec {
+    plus: bool,
+    alternate: bool,
+    precision: Option<usize>,
+    conversion: char,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Spec, String> {
+        let invalid = || format!("unsupported specification `{}`", spec);
+        let mut rest = spec.strip_prefix('%').ok_or_else(invalid)?;
+        let mut parsed = Spec::default();
+        loop {
+            match rest.chars().next() {
+                Some('+') => parsed.plus = true,
+                Some('#') => parsed.alternate = true,
+                _ => break,
+            }
+            rest = &rest[1..];
+        }
+        if let Some(precision) = rest.strip_prefix('.') {
+            let end = precision
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(precision.len());
+            parsed.precision = Some(precision[..end].parse().unwrap_or(0));
+            rest = &precision[end..];
+        }
+        match rest {
+            "g" | "G" | "e" | "E" | "f" | "F" => parsed.conversion = rest.chars().next().unwrap(),
+            _ => return Err(invalid()),
+        }
+        Ok(parsed)
+    }
+
+    fn format(&self, value: f64) -> String {
+        let mut output = String::new();
+        if value.is_sign_negative() {
+            output.push('-');
+        } else if self.plus {
+            output.push('+');
+        }
+        let value = value.abs();
+        let precision = self.precision.unwrap_or(6);
+        match self.conversion.to_ascii_lowercase() {
+            'f' => {
+                output.push_str(&format!("{:.*}", precision, value));
+                if self.alternate && precision == 0 {
+                    output.push('.');
+                }
+            }
+            'e' => {
+                let (mantissa, exponent) = scientific(precision, value);
+                output.push_str(&mantissa);
+                if self.alternate && precision == 0 {
+                    output.push('.');
+                }
+                output.push_str(&exponent_str(exponent));
+            }
+            _ => {
+                let precision = precision.max(1);
+                let (mut mantissa, exponent) = scientific(precision - 1, value);
+                let fixed = exponent >= -4 && exponent < precision as i32;
+                if fixed {
+                    mantissa = format!("{:.*}", (precision as i32 - 1 - exponent) as usize, value);
+                }
+                if self.alternate {
+                    if !mantissa.contains('.') {
+                        mantissa.push('.');
+                    }
+                } else if mantissa.contains('.') {
+                    let trimmed = mantissa.trim_end_matches('0').trim_end_matches('.');
+                    mantissa.truncate(trimmed.len());
+                }
+                output.push_str(&mantissa);
+                if !fixed {
+                    output.push_str(&exponent_str(exponent));
+                }
+            }
+        }
+        if self.conversion.is_ascii_uppercase() {
+            output.make_ascii_uppercase();
+        }
+        output
+    }
+}
+
+/// Rust's `{:e}` mantissa and exponent.
+fn scientific(precision: usize, value: f64) -> (String, i32) {
+    let text = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = text.split_at(text.find('e').unwrap_or(text.len()));
+    (mantissa.to_owned(), exponent[1..].parse().unwrap_or(0))
+}
+
+fn exponent_str(exponent: i32) -> String {
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("e{}{:02}", sign, exponent.abs())
+}
@@ -0,0 +1,211 @@
+//! `#[derive(GDisplay)]` and `g_str!`, re-exported by `gpoint` with its
+//! `derive` feature: see the documentation there.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+use std::fmt::Write;
+
+mod g_str;
+
+/// Formats a number literal at compile time as glibc's `printf()` would,
+/// with `%g` or the given `%g`, `%e` or `%f` specification, expanding to a
+/// string literal.
+#[proc_macro]
+pub fn g_str(input: TokenStream) -> TokenStream {
+    match g_str::g_str(input) {
+        Ok(output) => output,
+        Err(message) => format!("::core::compile_error!({:?})", message)
+            .parse()
+            .unwrap(),
+    }
+}
+
+/// Implements `Display` for a struct with named fields, formatting each
+/// float field with `%g` as `name=value`, separated by spaces.
+///
+/// Attributes: `#[gpoint(separator = ", ")]` on the struct, and
+/// `#[gpoint(precision = 3)]`, `#[gpoint(rename = "x")]` or
+/// `#[gpoint(skip)]` on fields. Fields which aren't `f64` nor `f32` are
+/// displayed as they are.
+#[proc_macro_derive(GDisplay, attributes(gpoint))]
+pub fn derive_gdisplay(input: TokenStream) -> TokenStream {
+    match derive(input) {
+        Ok(output) => output,
+        Err(message) => format!("::core::compile_error!({:?});", message)
+            .parse()
+            .unwrap(),
+    }
+}
+
+/// A `#[gpoint(...)]` setting, with its value if any.
+type Setting = (String, Option<TokenTree>);
+
+struct Field {
+    name: String,
+    ty: String,
+    settings: Vec<Setting>,
+}
+
+fn derive(input: TokenStream) -> Result<TokenStream, String> {
+    let mut tokens = input.into_iter().peekable();
+    let mut settings = Vec::new();
+    let name = loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => {
+                settings.extend(attribute(tokens.next())?);
+            }
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => match tokens.next() {
+                Some(TokenTree::Ident(name)) => break name.to_string(),
+                _ => return Err("expected a struct name".to_owned()),
+            },
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "enum" => {
+                return Err("GDisplay can only be derived for structs".to_owned())
+            }
+            Some(_) => (),
+            None => return Err("expected a struct".to_owned()),
+        }
+    };
+    let fields = match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            fields(group.stream())?
+        }
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '<' => {
+            return Err("GDisplay can't be derived for generic structs".to_owned())
+        }
+        _ => return Err("GDisplay can only be derived for structs with named fields".to_owned()),
+    };
+
+    let separator = setting(&settings, "separator")?.unwrap_or_else(|| "\" \"".to_owned());
+    let mut body = String::new();
+    let mut first = true;
+    for field in fields {
+        if field.settings.iter().any(|(name, _)| name == "skip") {
+            continue;
+        }
+        if !first {
+            writeln!(body, "f.write_str({})?;", separator).unwrap();
+        }
+        first = false;
+        let label = match setting(&field.settings, "rename")? {
+            Some(rename) => rename.trim_matches('"').to_owned(),
+            None => field.name.trim_start_matches("r#").to_owned(),
+        };
+        writeln!(body, "f.write_str({:?})?;", format!("{}=", label)).unwrap();
+        let spec = match setting(&field.settings, "precision")? {
+            Some(precision) => format!("{{:.{}}}", precision),
+            None => "{}".to_owned(),
+        };
+        let value = match field.ty.as_str() {
+            "f64" | "f32" => format!("::gpoint::GPoint(self.{})", field.name),
+            _ => format!("&self.{}", field.name),
+        };
+        writeln!(body, "::core::write!(f, {:?}, {})?;", spec, value).unwrap();
+    }
+    format!(
+        "impl ::core::fmt::Display for {} {{
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {{
+                {}
+                ::core::result::Result::Ok(())
+            }}
+        }}",
+        name, body
+    )
+    .parse()
+    .map_err(|_| "GDisplay generated invalid code".to_owned())
+}
+
+/// Parses the named fields of a struct.
+fn fields(stream: TokenStream) -> Result<Vec<Field>, String> {
+    let mut fields = Vec::new();
+    let mut tokens = stream.into_iter().peekable();
+    while tokens.peek().is_some() {
+        let mut settings = Vec::new();
+        let name = loop {
+            match tokens.next() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => {
+                    settings.extend(attribute(tokens.next())?);
+                }
+                Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => {
+                    // `pub(crate)` and the like
+                    if let Some(TokenTree::Group(group)) = tokens.peek() {
+                        if group.delimiter() == Delimiter::Parenthesis {
+                            tokens.next();
+                        }
+                    }
+                }
+                Some(TokenTree::Ident(ident)) => break ident.to_string(),
+                _ => return Err("expected a field name".to_owned()),
+            }
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ':' => (),
+            _ => return Err(format!("expected the type of `{}`", name)),
+        }
+        // the type ends at the first comma outside of angle brackets
+        let mut ty = String::new();
+        let mut depth = 0;
+        let mut arrow = false;
+        for token in tokens.by_ref() {
+            if let TokenTree::Punct(punct) = &token {
+                match punct.as_char() {
+                    ',' if depth == 0 => break,
+                    '<' => depth += 1,
+                    '>' if !arrow && depth > 0 => depth -= 1,
+                    _ => (),
+                }
+                arrow = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+            } else {
+                arrow = false;
+            }
+            ty.push_str(&token.to_string());
+        }
+        fields.push(Field { name, ty, settings });
+    }
+    Ok(fields)
+}
+
+/// Parses the settings of an attribute, i.e. the bracketed part of
+/// `#[gpoint(precision = 3, rename = "x")]`. Other attributes have none.
+fn attribute(token: Option<TokenTree>) -> Result<Vec<Setting>, String> {
+    let group = match token {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => group,
+        _ => return Err("expected an attribute".to_owned()),
+    };
+    let mut tokens = group.stream().into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(group)))
+            if ident.to_string() == "gpoint" && group.delimiter() == Delimiter::Parenthesis =>
+        {
+            let mut settings = Vec::new();
+            let mut tokens = group.stream().into_iter();
+            while let Some(token) = tokens.next() {
+                let name = match token {
+                    TokenTree::Ident(ident) => ident.to_string(),
+                    _ => return Err("expected a gpoint setting".to_owned()),
+                };
+                let value = match tokens.next() {
+                    Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {
+                        let value = tokens.next();
+                        tokens.next(); // the comma
+                        value
+                    }
+                    _ => None,
+                };
+                if !["separator", "precision", "rename", "skip"].contains(&name.as_str()) {
+                    return Err(format!("unknown gpoint setting `{}`", name));
+                }
+                settings.push((name, value));
+            }
+            Ok(settings)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Returns the value of a setting, if given.
+fn setting(settings: &[Setting], name: &str) -> Result<Option<String>, String> {
+    match settings.iter().find(|(setting, _)| setting == name) {
+        Some((_, Some(value))) => Ok(Some(value.to_string())),
+        Some((_, None)) => Err(format!("gpoint setting `{}` needs a value", name)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,62 @@
+// C's `%g` formatting for web frontends, with the pure Rust backend of the
+// `gpoint` crate, so that browsers render numbers exactly as servers do.
+//
+// Build the WebAssembly module with
+// `cargo rustc --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib`
+// then:
+//
+//     import { load } from "./gpoint.js";
+//     const { gformat } = await load(fetch("gpoint.wasm"));
+//     gformat(1234567.125); // "1.23457e+06"
+//     gformat(0.5, "%+.3e"); // "+5.000e-01"
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+// Loads the module from a `Response`, a promise of one, or its bytes, and
+// returns its formatting function.
+export async function load(source) {
+  source = await source;
+  const { instance } =
+    typeof Response !== "undefined" && source instanceof Response
+      ? await WebAssembly.instantiateStreaming(source)
+      : await WebAssembly.instantiate(source);
+  return bind(instance.exports);
+}
+
+// Wraps the exports of an instantiated module.
+export function bind(exports) {
+  const { memory, gpoint_alloc, gpoint_free, gpoint_format_js } = exports;
+
+  // Formats `value` as `printf(spec, value)` would, throwing a `RangeError`
+  // for invalid specifications.
+  function gformat(value, spec = "%g") {
+    const specBytes = encoder.encode(spec);
+    const specPtr = gpoint_alloc(specBytes.length);
+    try {
+      // the memory may grow, detaching previous views of it
+      new Uint8Array(memory.buffer, specPtr, specBytes.length).set(specBytes);
+      let size = 64;
+      for (;;) {
+        const bufSize = size;
+        const buf = gpoint_alloc(bufSize);
+        try {
+          const len = gpoint_format_js(value, specPtr, specBytes.length, buf, size);
+          if (len < 0) {
+            throw new RangeError(`invalid specification: ${spec}`);
+          }
+          if (len <= size) {
+            return decoder.decode(new Uint8Array(memory.buffer, buf, len));
+          }
+          size = len;
+        } finally {
+          gpoint_free(buf, bufSize);
+        }
+      }
+    } finally {
+      gpoint_free(specPtr, specBytes.length);
+    }
+  }
+
+  return { gformat };
+}
@@ -0,0 +1,70 @@
+"""C's ``%g`` formatting from Python, backed by the ``gpoint`` Rust crate.
+
+Build the shared library with
+``cargo rustc --release --features python --crate-type cdylib`` and either
+copy it next to this file or point ``GPOINT_LIB`` at it.
+
+>>> gformat(1234567.125)
+'1.23457e+06'
+>>> gformat(1234567.125, "%g", "go")
+'1.234567125e+06'
+"""
+
+import ctypes
+import os
+import sys
+
+__all__ = ["gformat", "MODES"]
+
+#: The compatibility modes :func:`gformat` accepts: the output of C programs,
+#: of glibc on any platform, every digit, Go, .NET, Excel and G-code.
+MODES = ("printf", "pure", "exact", "go", "dotnet", "excel", "gcode")
+
+
+def _load():
+    path = os.environ.get("GPOINT_LIB")
+    if path is None:
+        if sys.platform == "win32":
+            name = "gpoint.dll"
+        elif sys.platform == "darwin":
+            name = "libgpoint.dylib"
+        else:
+            name = "libgpoint.so"
+        path = os.path.join(os.path.dirname(os.path.abspath(__file__)), name)
+    lib = ctypes.CDLL(path)
+    lib.gpoint_gformat.argtypes = [
+        ctypes.c_double,
+        ctypes.c_char_p,
+        ctypes.c_char_p,
+        ctypes.c_char_p,
+        ctypes.c_size_t,
+    ]
+    lib.gpoint_gformat.restype = ctypes.c_int
+    return lib
+
+
+_lib = _load()
+
+
+def gformat(value, spec="%g", mode="printf"):
+    """Formats ``value`` as ``printf(spec, value)`` would, in one of
+    :data:`MODES`.
+
+    Raises ``ValueError`` for invalid specifications and unknown modes.
+    """
+    spec, mode = spec.encode(), mode.encode()
+    size = 64
+    while True:
+        buf = ctypes.create_string_buffer(size)
+        length = _lib.gpoint_gformat(float(value), spec, mode, buf, size)
+        if length < 0:
+            raise ValueError("invalid specification or mode: %r, %r" % (spec, mode))
+        if length < size:
+            return buf.value.decode()
+        size = length + 1
+
+
+if __name__ == "__main__":
+    import doctest
+
+    doctest.testmod()
@@ -0,0 +1,382 @@
+//! Formatting many numbers into a single growable buffer
+
+use crate::{formatted, Backend, Flags, GConfig, GError, Options, Spec};
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// A growable buffer many numbers are formatted into, one after the other,
+/// remembering where each of them is: thousands of numbers cost a few
+/// reallocations instead of one `String` each, and can be written out with a
+/// single `write_all()`, e.g. for column-oriented exports.
+///
+/// ```
+/// use gpoint::{GArena, Spec};
+///
+/// let mut arena = GArena::new();
+/// assert!(arena.push(42.)? == (0..2));
+/// arena.push_str(",");
+/// arena.format_many(&[1. / 3., 1e-10], &Spec::parse("%.3f").unwrap())?;
+/// assert!(arena.as_str() == "42,0.3330.000");
+/// assert!(arena.get(1) == Some("0.333") && arena.span(2) == Some(8..13));
+/// let mut out = Vec::new();
+/// arena.write_to(&mut out)?;
+/// assert!(out == b"42,0.3330.000");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct GArena {
+    buf: String,
+    spans: Vec<Range<usize>>,
+    options: Options,
+}
+
+impl GArena {
+    /// An empty arena, formatting numbers with the default [`Options`].
+    pub fn new() -> Self {
+        GArena::with_capacity(0, 0)
+    }
+
+    /// An empty arena with room for `values` numbers taking `bytes` bytes in
+    /// total without reallocating.
+    pub fn with_capacity(values: usize, bytes: usize) -> Self {
+        GArena {
+            buf: String::with_capacity(bytes),
+            spans: Vec::with_capacity(values),
+            options: GConfig::get(),
+        }
+    }
+
+    /// Sets the [`Options`] numbers are formatted with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Appends `value` as `printf("%g")` would, returning where its output
+    /// is in the buffer.
+    pub fn push(&mut self, value: impl Into<f64>) -> Result<Range<usize>, GError> {
+        let options = self.options;
+        let flags = Flags {
+            precision: options.precision,
+            ..Flags::default()
+        };
+        self.push_flags(value.into(), &flags, &options)
+    }
+
+    /// Appends `value` as `printf()` would with the given specification,
+    /// returning where its output is in the buffer.
+    pub fn push_spec(
+        &mut self,
+        value: impl Into<f64>,
+        spec: &Spec,
+    ) -> Result<Range<usize>, GError> {
+        let options = spec.apply(self.options);
+        self.push_flags(value.into(), &spec.flags_with(&options), &options)
+    }
+
+    /// Appends all of `values` as `printf()` would with the given
+    /// specification, without anything in between. On error, none of them
+    /// is kept.
+    ///
+    /// With plain `%g` specifications and the default [`Options`], values are
+    /// first classified by chunks, without branching per value so that the
+    /// compiler vectorizes it, and chunks of zeros or small integers are
+    /// written directly instead of calling the backend for each value.
+    pub fn format_many(&mut self, values: &[f64], spec: &Spec) -> Result<(), GError> {
+        let (len, count) = (self.buf.len(), self.spans.len());
+        let options = spec.apply(self.options);
+        self.spans.reserve(values.len());
+        if let Err(e) = self.format_chunks(values, &spec.flags_with(&options), &options) {
+            self.buf.truncate(len);
+            self.spans.truncate(count);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Appends text which isn't a number, e.g. a separator.
+    pub fn push_str(&mut self, text: &str) {
+        self.buf.push_str(text);
+    }
+
+    /// The number of numbers formatted.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Whether no number was formatted.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// The output of the `index`th number formatted.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.span(index).map(|span| &self.buf[span])
+    }
+
+    /// Where the output of the `index`th number formatted is in the buffer.
+    pub fn span(&self, index: usize) -> Option<Range<usize>> {
+        self.spans.get(index).cloned()
+    }
+
+    /// Where the outputs of all numbers formatted are in the buffer, in order.
+    pub fn spans(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+
+    /// The whole buffer.
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    /// Writes the whole buffer into `out` at once.
+    pub fn write_to(&self, mut out: impl Write) -> io::Result<()> {
+        out.write_all(self.buf.as_bytes())
+    }
+
+    /// Empties the arena, keeping its memory for the next numbers.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.spans.clear();
+    }
+
+    fn push_flags(
+        &mut self,
+        value: f64,
+        flags: &Flags,
+        options: &Options,
+    ) -> Result<Range<usize>, GError> {
+        let start = self.buf.len();
+        let buf = &mut self.buf;
+        formatted(value, flags, options, |numstr| buf.push_str(numstr))?;
+        let span = start..self.buf.len();
+        self.spans.push(span.clone());
+        Ok(span)
+    }
+
+    fn format_chunks(
+        &mut self,
+        values: &[f64],
+        flags: &Flags,
+        options: &Options,
+    ) -> Result<(), GError> {
+        let limit = match integer_limit(flags, options) {
+            Some(limit) => limit,
+            None => {
+                for &value in values {
+                    self.push_flags(value, flags, options)?;
+                }
+                return Ok(());
+            }
+        };
+        let mut chunks = values.chunks_exact(LANES);
+        for chunk in &mut chunks {
+            match classify(chunk, limit) {
+                Class::Zero => {
+                    for &value in chunk {
+                        let start = self.buf.len();
+                        self.buf
+                            .push_str(if value.is_sign_negative() { "-0" } else { "0" });
+                        self.spans.push(start..self.buf.len());
+                    }
+                }
+                Class::SmallInteger => {
+                    for &value in chunk {
+                        self.push_integer(value);
+                    }
+                }
+                Class::Normal | Class::NonFinite => {
+                    for &value in chunk {
+                        self.push_flags(value, flags, options)?;
+                    }
+                }
+            }
+        }
+        for &value in chunks.remainder() {
+            self.push_flags(value, flags, options)?;
+        }
+        Ok(())
+    }
+
+    /// Appends an integer below 2⁵², maybe a negative zero, as `%g` writes it
+    /// when it has enough significant digits for it.
+    fn push_integer(&mut self, value: f64) {
+        let start = self.buf.len();
+        if value.is_sign_negative() {
+            self.buf.push('-');
+        }
+        let mut integer = value.abs() as u64;
+        let mut digits = [0u8; 16];
+        let mut i = digits.len();
+        loop {
+            i -= 1;
+            digits[i] = b'0' + (integer % 10) as u8;
+            integer /= 10;
+            if integer == 0 {
+                break;
+            }
+        }
+        self.buf
+            .push_str(unsafe { std::str::from_utf8_unchecked(&digits[i..]) });
+        self.spans.push(start..self.buf.len());
+    }
+}
+
+/// The number of values classified at once by [`classify()`].
+const LANES: usize = 8;
+
+/// The kinds of chunks of values [`GArena::format_many()`] has paths for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    /// Only zeros, of either sign.
+    Zero,
+    /// Only integers below the limit, zeros included.
+    SmallInteger,
+    /// Finite values, some of them neither.
+    Normal,
+    /// Some infinities or NaNs.
+    NonFinite,
+}
+
+/// Classifies a chunk of [`LANES`] values with the same operations on each
+/// lane, `limit` being at most 2⁵²: the bounds and the integer test take no
+/// branch, so that the loop compiles to vector instructions.
+fn classify(chunk: &[f64], limit: f64) -> Class {
+    // 2⁵² + x ends with x's integer part whatever the rounding mode, so the
+    // difference is x only for integers
+    const INTEGER: f64 = 4_503_599_627_370_496.;
+    let (mut zero, mut small, mut finite) = (true, true, true);
+    for &value in &chunk[..LANES] {
+        let magnitude = value.abs();
+        zero &= magnitude == 0.;
+        small &= (magnitude < limit) & ((magnitude + INTEGER) - INTEGER == magnitude);
+        finite &= magnitude <= f64::MAX;
+    }
+    match (zero, small, finite) {
+        (true, _, _) => Class::Zero,
+        (_, true, _) => Class::SmallInteger,
+        (_, _, true) => Class::Normal,
+        _ => Class::NonFinite,
+    }
+}
+
+/// The integers below which `%g` writes integers as plain digits with these
+/// `flags` and `options`, like any backend does: `10^precision`, when
+/// nothing else than the precision is set.
+fn integer_limit(flags: &Flags, options: &Options) -> Option<f64> {
+    let plain = Flags {
+        precision: None,
+        ..*flags
+    } == Flags::default()
+        && options.backend(Backend::Libc) == Options::new()
+        && !matches!(options.backend, Backend::Custom(_));
+    // beyond 15 digits, integers aren't all below 2⁵²
+    let precision = flags.precision.unwrap_or(6).clamp(1, 15);
+    plain.then(|| 10f64.powi(precision as i32))
+}
+
+impl Default for GArena {
+    fn default() -> Self {
+        GArena::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn arena() {
+        let options = Options::new().unsigned_nan(true);
+        let mut arena = GArena::with_capacity(4, 64).with(options);
+        let spec = Spec::parse("%8.3G").unwrap();
+        for num in [42., -1.01, 1e-10, -f64::NAN] {
+            arena.push(num).unwrap();
+            arena.push_str("|");
+            arena.push_spec(num as f32, &spec).unwrap();
+            arena.push_str("\n");
+        }
+        assert_eq!(
+            arena.as_str(),
+            "42|      42\n-1.01|   -1.01\n1e-10|   1E-10\nnan|     NAN\n"
+        );
+        assert_eq!(arena.len(), 8);
+        assert_eq!(arena.get(3), Some("   -1.01"));
+        assert_eq!(arena.get(8), None);
+        // the same output as one number at a time, whatever the precision
+        arena.clear();
+        assert!(arena.is_empty());
+        let values: Vec<f64> = crate::testing::corpus().to_vec();
+        for spec in ["%g", "%.17g", "%#.600g"] {
+            let spec = Spec::parse(spec).unwrap();
+            arena.format_many(&values, &spec).unwrap();
+        }
+        assert_eq!(arena.len(), 3 * values.len());
+        for (i, span) in arena.spans().iter().enumerate() {
+            let spec = ["%g", "%.17g", "%#.600g"][i / values.len()];
+            let expected = crate::GPoint(values[i % values.len()])
+                .with(options)
+                .try_to_string_with(&Spec::parse(spec).unwrap())
+                .unwrap();
+            assert_eq!(arena.as_str()[span.clone()], expected);
+        }
+        // failing numbers leave the arena as it was
+        let len = arena.as_str().len();
+        let overflow = Spec::parse("%.99999999f").unwrap();
+        assert!(arena.format_many(&[1., 2.], &overflow).is_err());
+        assert_eq!((arena.len(), arena.as_str().len()), (3 * values.len(), len));
+        // the precision of the options, given or from the configuration
+        let options = Options::new().precision(3);
+        let _guard = crate::ConfigGuard::new(options);
+        for mut arena in [GArena::new().with(options), GArena::new()] {
+            let values = [1. / 3., 42., 1234., -0.];
+            for &num in &values {
+                arena.push(num).unwrap();
+            }
+            arena.format_many(&values, &Spec::default()).unwrap();
+            for (i, span) in arena.spans().iter().enumerate() {
+                let expected = crate::GPoint(values[i % 4]).with(options).to_string();
+                assert_eq!(arena.as_str()[span.clone()], expected);
+            }
+        }
+    }
+    #[test]
+    fn classified() {
+        assert_eq!(
+            classify(&[0., -0., 0., 0., 0., 0., 0., 0.], 1e6),
+            Class::Zero
+        );
+        let integers = [1., -2., 0., 999_999., 42., -0., 7., 3.];
+        assert_eq!(classify(&integers, 1e6), Class::SmallInteger);
+        assert_eq!(classify(&integers, 1e5), Class::Normal);
+        let normal = [1., 0.5, 2., 3., 4., 5., 6., 7.];
+        assert_eq!(classify(&normal, 1e6), Class::Normal);
+        let infinite = [1., 0.5, 2., f64::INFINITY, 4., 5., 6., 7.];
+        assert_eq!(classify(&infinite, 1e6), Class::NonFinite);
+        let nan = [0., 0., 0., 0., 0., 0., 0., f64::NAN];
+        assert_eq!(classify(&nan, 1e6), Class::NonFinite);
+        // every path, and chunks cut anywhere, give the same output as one
+        // number at a time
+        let mut values = vec![0.; 11];
+        values.extend((0..40).map(|i| (i * i - 500) as f64));
+        values.extend([1e6, 999_999., 123_456.5, -0., 1e15, 4503599627370497.]);
+        values.extend([f64::NAN, -f64::INFINITY, 1. / 3., 5e-324]);
+        values.extend((0..20).map(|i| 1e5 * i as f64));
+        values.extend(crate::testing::corpus());
+        for options in [Options::new(), Options::new().unsigned_nan(true)] {
+            for spec in ["%g", "%.3g", "%.0g", "%.20g", "%G", "%#g", "%5g", "%f"] {
+                let spec = Spec::parse(spec).unwrap();
+                for skip in 0..LANES {
+                    let mut arena = GArena::new().with(options);
+                    arena.format_many(&values[skip..], &spec).unwrap();
+                    for (i, &value) in values[skip..].iter().enumerate() {
+                        let expected = crate::GPoint(value)
+                            .with(options)
+                            .try_to_string_with(&spec)
+                            .unwrap();
+                        assert_eq!(arena.get(i).unwrap(), expected, "{:?} {}", spec, value);
+                    }
+                }
+            }
+        }
+    }
+}
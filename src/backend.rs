@@ -0,0 +1,691 @@
+//! The engines actually formatting numbers
+
+use crate::buffer::{self, ByteWriter};
+use crate::error::clear_errno;
+use crate::locale::LocaleGuard;
+use crate::{exact, pure, significant_digits, Conversion, Flags, GError, NumericLocale, Spec};
+use std::fmt::{self, Write as _};
+use std::os::raw::{c_char, c_int};
+use std::sync::OnceLock;
+
+const FORMAT_SIZE: usize = 20;
+
+/// The engine formatting numbers, see [`Options::backend()`](crate::Options::backend)
+/// and [`Spec::backend()`].
+#[derive(Debug, Default, Clone, Copy)]
+#[non_exhaustive]
+pub enum Backend {
+    /// The platform `libc`'s `snprintf()`, or [`Backend::Pure`] on targets
+    /// without a C library, e.g. `wasm32-unknown-unknown`.
+    #[default]
+    Libc,
+    /// A pure-Rust emulation of glibc's `printf("%g")`, giving the same output
+    /// on every platform.
+    Pure,
+    /// C23's `strfromd()`, called in the `C` locale: glibc's follows
+    /// `LC_NUMERIC` like `snprintf()` does, so
+    /// [`Options::locale()`](crate::Options::locale) is ignored and the
+    /// decimal point is always `.`. Falls back to [`Backend::Pure`] when the
+    /// C library doesn't provide it.
+    Strfromd,
+    /// Like [`Backend::Pure`], for any precision: the exact decimal expansion
+    /// of every `f64` is finite, and completed with zeros. Selected
+    /// automatically for precisions beyond [`MAX_PRECISION`].
+    Exact,
+    /// Any other engine, e.g. one calling quadmath's `quadmath_snprintf()`,
+    /// or a wrapper around another backend checking its output.
+    Custom(&'static dyn FormatBackend),
+}
+
+impl PartialEq for Backend {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Backend::Custom(a), Backend::Custom(b)) => std::ptr::addr_eq(*a, *b),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for Backend {}
+
+/// An engine formatting numbers, selected with [`Backend::Custom`].
+///
+/// ```
+/// use gpoint::{Backend, FormatBackend, GError, GPoint, Options, Spec};
+///
+/// /// Formats every number as zero.
+/// #[derive(Debug)]
+/// struct Zero;
+///
+/// impl FormatBackend for Zero {
+///     fn format(&self, buf: &mut [u8], _value: f64, _spec: &Spec) -> Result<usize, GError> {
+///         buf.get_mut(..1).ok_or(GError::Overflow)?.copy_from_slice(b"0");
+///         Ok(1)
+///     }
+/// }
+///
+/// let zero = Options::new().backend(Backend::Custom(&Zero));
+/// assert!(format!("{:+4}", GPoint(42.).with(zero)) == "   0");
+/// ```
+pub trait FormatBackend: fmt::Debug + Sync {
+    /// Formats `value` into `buf` as `printf()` would with `spec`, returning
+    /// the output length.
+    ///
+    /// The specification has no width: padding, like the ` ` flag and the
+    /// [`Options`](crate::Options), is applied to the output afterwards. It
+    /// must be spelled the way glibc does in the `C` locale, e.g. `-1.5e+10`,
+    /// `inf` or `-nan`, in lowercase whatever the conversion.
+    fn format(&self, buf: &mut [u8], value: f64, spec: &Spec) -> Result<usize, GError>;
+}
+
+/// The largest precision given to the other backends: C libraries fail
+/// much sooner than they run out of memory (e.g. glibc needs precisions to
+/// fit in an `int`), and Rust's own formatting panics above `u16::MAX`.
+pub const MAX_PRECISION: usize = u16::MAX as usize;
+
+impl Backend {
+    /// Whether this backend can be used on the running system, i.e. without
+    /// falling back to another one.
+    ///
+    /// ```
+    /// use gpoint::Backend;
+    ///
+    /// assert!(Backend::Libc.is_available());
+    /// assert!(Backend::Pure.is_available());
+    /// ```
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::Libc => !cfg!(gpoint_no_libc),
+            Backend::Pure | Backend::Exact | Backend::Custom(_) => true,
+            Backend::Strfromd => strfromd().is_some(),
+        }
+    }
+}
+
+/// Formats `value` into `numstr` with the given backend, returning the output
+/// length. The width is ignored: padding is left to the caller.
+pub(crate) fn format_g(
+    backend: Backend,
+    numstr: &mut [u8],
+    value: f64,
+    flags: &Flags,
+) -> Result<usize, GError> {
+    let backend =
+        if flags.precision.unwrap_or(0) > MAX_PRECISION && !matches!(backend, Backend::Custom(_)) {
+            Backend::Exact
+        } else {
+            backend
+        };
+    match (backend, strfromd()) {
+        (Backend::Libc, _) => libc_g(numstr, value, flags),
+        (Backend::Strfromd, Some(strfromd)) => strfromd_g(strfromd, numstr, value, flags),
+        // keep the same limit as snprintf(), which needs room for its final 0
+        (Backend::Pure, _) | (Backend::Strfromd, None) => {
+            let size = numstr.len();
+            Ok(pure::format_g(&mut numstr[..size - 1], value, flags)?)
+        }
+        (Backend::Exact, _) => {
+            let size = numstr.len();
+            Ok(exact::format_g(&mut numstr[..size - 1], value, flags)?)
+        }
+        (Backend::Custom(backend), _) => {
+            let size = numstr.len();
+            let spec = Spec::new(*flags);
+            let len = backend.format(&mut numstr[..size - 1], value, &spec)?;
+            if len >= size {
+                return Err(GError::Overflow);
+            }
+            if !numstr[..len].is_ascii() {
+                return Err(GError::Backend);
+            }
+            Ok(len)
+        }
+    }
+}
+
+/// The format strings without precision, by conversion then flags, which
+/// needn't be built for each number.
+#[cfg(not(gpoint_no_libc))]
+const STATIC_FORMATS: [[&[u8]; 4]; 3] = [
+    [b"%g\0", b"%#g\0", b"%+g\0", b"%#+g\0"],
+    [b"%e\0", b"%#e\0", b"%+e\0", b"%#+e\0"],
+    [b"%f\0", b"%#f\0", b"%+f\0", b"%#+f\0"],
+];
+
+/// Returns the `snprintf()` format string for `flags`, built into `format`
+/// when it has a precision.
+#[cfg(not(gpoint_no_libc))]
+fn libc_format<'a>(
+    format: &'a mut [u8; FORMAT_SIZE],
+    flags: &Flags,
+    finite: bool,
+) -> Result<&'a [u8], GError> {
+    // non-finite values ignore precision
+    let precision = match flags.precision.filter(|_| finite) {
+        Some(precision) => precision,
+        None => {
+            let conversion = match flags.conversion {
+                Conversion::General => 0,
+                Conversion::Scientific => 1,
+                Conversion::Fixed => 2,
+            };
+            return Ok(
+                STATIC_FORMATS[conversion][flags.alternate as usize + 2 * flags.plus as usize]
+            );
+        }
+    };
+    let alternate = if flags.alternate { "#" } else { "" };
+    let plus = if flags.plus { "+" } else { "" };
+    let conversion = flags.conversion.letter();
+    // keep the final 0
+    write!(
+        ByteWriter::new(&mut format[..FORMAT_SIZE - 1]),
+        "%{}{}.{}{}",
+        alternate,
+        plus,
+        precision,
+        conversion
+    )
+    .map_err(|_| GError::Overflow)?;
+    Ok(format)
+}
+
+#[cfg(not(gpoint_no_libc))]
+fn libc_g(numstr: &mut [u8], value: f64, flags: &Flags) -> Result<usize, GError> {
+    let finite = value.is_finite();
+    let mut format = [0u8; FORMAT_SIZE];
+    let format = libc_format(&mut format, flags, finite)?;
+    clear_errno();
+    let nbchars = unsafe {
+        libc::snprintf(
+            numstr.as_mut_ptr() as *mut c_char,
+            numstr.len(),
+            format.as_ptr() as *const c_char,
+            value,
+        )
+    };
+    if nbchars < 0 {
+        return Err(GError::libc());
+    }
+    // check if we (virtually) overflowed our buffer
+    if nbchars as usize >= numstr.len() {
+        return Err(GError::Overflow);
+    }
+    let len = nbchars as usize;
+
+    if !finite {
+        return Ok(rewrite_non_finite(numstr, len, value.is_infinite()));
+    }
+
+    Ok(len)
+}
+
+/// Formats `value` as [`Backend::Pure`] does, without a C library.
+#[cfg(gpoint_no_libc)]
+fn libc_g(numstr: &mut [u8], value: f64, flags: &Flags) -> Result<usize, GError> {
+    let size = numstr.len();
+    Ok(pure::format_g(&mut numstr[..size - 1], value, flags)?)
+}
+
+/// The most values [`libc_row()`] formats with a single `snprintf()` call.
+#[cfg(not(gpoint_no_libc))]
+pub(crate) const MAX_ROW: usize = 4;
+
+/// Room for [`MAX_ROW`] conversions with their flags, width and precision,
+/// and the separators between them.
+#[cfg(not(gpoint_no_libc))]
+const ROW_FORMAT_SIZE: usize = 128;
+
+/// Formats `values` into `numstr` with a single `snprintf()` call, separated
+/// by `separator` and padded to the width, returning the output length, or
+/// `None` if the C library's output would need rewriting: with non-finite
+/// values, exponents of more than 2 digits, or extreme precisions. Rows of
+/// more than [`MAX_ROW`] values or whose format string doesn't fit aren't
+/// formatted either.
+#[cfg(not(gpoint_no_libc))]
+pub(crate) fn libc_row(
+    numstr: &mut [u8],
+    values: &[f64],
+    separator: &str,
+    flags: &Flags,
+) -> Result<Option<usize>, GError> {
+    if values.len() > MAX_ROW
+        || !values.iter().all(|value| value.is_finite())
+        || flags.precision.unwrap_or(0) > MAX_PRECISION
+        || flags.center
+        || flags.fill != ' '
+        || separator.contains('\0')
+        || crate::capabilities().exponent_digits != 2
+    {
+        return Ok(None);
+    }
+    let mut format = [0u8; ROW_FORMAT_SIZE];
+    let mut out = ByteWriter::new(&mut format[..ROW_FORMAT_SIZE - 1]); // keep final 0
+    let written = (0..values.len()).try_for_each(|i| {
+        if i > 0 {
+            for (j, part) in separator.split('%').enumerate() {
+                if j > 0 {
+                    out.write_str("%%")?;
+                }
+                out.write_str(part)?;
+            }
+        }
+        out.write_char('%')?;
+        for (set, flag) in [
+            (flags.left, '-'),
+            (flags.plus, '+'),
+            (flags.space, ' '),
+            (flags.alternate, '#'),
+            (flags.zero, '0'),
+        ] {
+            if set {
+                out.write_char(flag)?;
+            }
+        }
+        if let Some(width) = flags.width {
+            write!(out, "{}", width)?;
+        }
+        if let Some(precision) = flags.precision {
+            write!(out, ".{}", precision)?;
+        }
+        out.write_char(flags.conversion.letter())
+    });
+    if written.is_err() {
+        return Ok(None);
+    }
+    let buf = numstr.as_mut_ptr() as *mut c_char;
+    let size = numstr.len();
+    let format = format.as_ptr() as *const c_char;
+    clear_errno();
+    // one shim per arity, since variadic calls can't be built at runtime
+    let nbchars = unsafe {
+        match *values {
+            [] => return Ok(Some(0)),
+            [a] => libc::snprintf(buf, size, format, a),
+            [a, b] => libc::snprintf(buf, size, format, a, b),
+            [a, b, c] => libc::snprintf(buf, size, format, a, b, c),
+            [a, b, c, d] => libc::snprintf(buf, size, format, a, b, c, d),
+            _ => return Ok(None),
+        }
+    };
+    if nbchars < 0 {
+        return Err(GError::libc());
+    }
+    // check if we (virtually) overflowed our buffer
+    if nbchars as usize >= size {
+        return Err(GError::Overflow);
+    }
+    Ok(Some(nbchars as usize))
+}
+
+/// Formats no row, without a C library: values are formatted one at a time.
+#[cfg(gpoint_no_libc)]
+pub(crate) fn libc_row(
+    _numstr: &mut [u8],
+    _values: &[f64],
+    _separator: &str,
+    _flags: &Flags,
+) -> Result<Option<usize>, GError> {
+    Ok(None)
+}
+
+/// Maps the various C runtimes' spellings of non-finite values (`1.#INF`,
+/// `-1.#IND`, `nan(ind)`, `1.#QNAN`, ...) to the portable `inf` and `nan`,
+/// keeping the sign the C runtime chose to print.
+fn normalize_non_finite(numstr: &[u8], infinite: bool) -> (&'static str, &'static str) {
+    let sign = match numstr.iter().find(|c| !c.is_ascii_whitespace()) {
+        Some(b'-') => "-",
+        Some(b'+') => "+",
+        _ => "",
+    };
+    (sign, if infinite { "inf" } else { "nan" })
+}
+
+/// Normalizes the non-finite value spelled in the `len` first bytes of
+/// `numstr`, returning its new length.
+fn rewrite_non_finite(numstr: &mut [u8], len: usize, infinite: bool) -> usize {
+    let (sign, word) = normalize_non_finite(&numstr[..len], infinite);
+    numstr[..sign.len()].copy_from_slice(sign.as_bytes());
+    numstr[sign.len()..sign.len() + word.len()].copy_from_slice(word.as_bytes());
+    sign.len() + word.len()
+}
+
+type Strfromd = unsafe extern "C" fn(*mut c_char, usize, *const c_char, f64) -> c_int;
+
+/// Looks up `strfromd()` at runtime, since older C libraries don't have it.
+fn strfromd() -> Option<Strfromd> {
+    static STRFROMD: OnceLock<Option<Strfromd>> = OnceLock::new();
+    *STRFROMD.get_or_init(|| {
+        #[cfg(unix)]
+        {
+            let symbol =
+                unsafe { libc::dlsym(libc::RTLD_DEFAULT, b"strfromd\0".as_ptr() as *const c_char) };
+            if !symbol.is_null() {
+                return Some(unsafe { std::mem::transmute::<*mut libc::c_void, Strfromd>(symbol) });
+            }
+        }
+        None
+    })
+}
+
+fn strfromd_g(
+    strfromd: Strfromd,
+    numstr: &mut [u8],
+    value: f64,
+    flags: &Flags,
+) -> Result<usize, GError> {
+    // glibc's strfromd() follows LC_NUMERIC too
+    let _locale = LocaleGuard::new(NumericLocale::C)?;
+    // strfromd() only accepts a precision: flags are applied here
+    let mut len = if !value.is_finite() {
+        let len = call_strfromd(strfromd, numstr, format_args!("%g"), value)?;
+        rewrite_non_finite(numstr, len, value.is_infinite())
+    } else if flags.alternate && flags.conversion == Conversion::General {
+        // no `#` flag either: make %g's choice between fixed and scientific
+        // notations, keeping trailing zeros
+        let precision = significant_digits(flags.precision);
+        let mut len = call_strfromd(
+            strfromd,
+            numstr,
+            format_args!("%.{}e", precision - 1),
+            value,
+        )?;
+        let exponent: i32 = std::str::from_utf8(&numstr[..len])
+            .ok()
+            .and_then(|s| s.split_once('e'))
+            .and_then(|(_, exponent)| exponent.parse().ok())
+            .ok_or(GError::Libc(None))?;
+        if exponent >= -4 && exponent < precision as i32 {
+            let decimals = (precision as i32 - 1 - exponent) as usize;
+            len = call_strfromd(strfromd, numstr, format_args!("%.{}f", decimals), value)?;
+        }
+        len
+    } else {
+        let conversion = flags.conversion.letter();
+        match flags.precision {
+            None => call_strfromd(strfromd, numstr, format_args!("%{}", conversion), value)?,
+            Some(p) => call_strfromd(
+                strfromd,
+                numstr,
+                format_args!("%.{}{}", p, conversion),
+                value,
+            )?,
+        }
+    };
+    let size = numstr.len();
+    // the `#` flag always keeps the decimal point
+    if flags.alternate && value.is_finite() && !numstr[..len].contains(&b'.') {
+        let at = numstr[..len].iter().position(|&c| c == b'e').unwrap_or(len);
+        len = buffer::insert(&mut numstr[..size - 1], len, at, b".")?;
+    }
+    if flags.plus && numstr[0] != b'-' {
+        len = buffer::insert(&mut numstr[..size - 1], len, 0, b"+")?;
+    }
+    Ok(len)
+}
+
+fn call_strfromd(
+    strfromd: Strfromd,
+    numstr: &mut [u8],
+    format: fmt::Arguments<'_>,
+    value: f64,
+) -> Result<usize, GError> {
+    let mut format_buf = [0u8; FORMAT_SIZE];
+    ByteWriter::new(&mut format_buf[..FORMAT_SIZE - 1]).write_fmt(format)?; // keep final 0
+    clear_errno();
+    let nbchars = unsafe {
+        strfromd(
+            numstr.as_mut_ptr() as *mut c_char,
+            numstr.len(),
+            format_buf.as_ptr() as *const c_char,
+            value,
+        )
+    };
+    if nbchars < 0 {
+        return Err(GError::libc());
+    }
+    // check if we (virtually) overflowed our buffer
+    if nbchars as usize >= numstr.len() {
+        return Err(GError::Overflow);
+    }
+    Ok(nbchars as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GPoint, Options};
+    #[test]
+    fn crt_quirks() {
+        for (numstr, infinite, res) in [
+            ("inf", true, "inf"),
+            ("-inf", true, "-inf"),
+            ("+inf", true, "+inf"),
+            ("1.#INF", true, "inf"),
+            ("-1.#INF", true, "-inf"),
+            ("+1.#INF", true, "+inf"),
+            ("nan", false, "nan"),
+            ("-nan", false, "-nan"),
+            ("nan(ind)", false, "nan"),
+            ("-nan(ind)", false, "-nan"),
+            ("nan(snan)", false, "nan"),
+            ("1.#QNAN", false, "nan"),
+            ("-1.#IND", false, "-nan"),
+        ] {
+            let (sign, word) = normalize_non_finite(numstr.as_bytes(), infinite);
+            assert_eq!(format!("{}{}", sign, word), res);
+        }
+    }
+    #[test]
+    fn format_string() {
+        let mut flags = Flags {
+            alternate: true,
+            plus: true,
+            precision: Some(12),
+            ..Flags::default()
+        };
+        let mut format = [0; FORMAT_SIZE];
+        assert!(libc_format(&mut format, &flags, true)
+            .unwrap()
+            .starts_with(b"%#+.12g\0"));
+        assert_eq!(libc_format(&mut format, &flags, false), Ok(&b"%#+g\0"[..]));
+        let mut numstr = [0; 200];
+        let len = libc_g(&mut numstr, 0.1, &flags).unwrap();
+        assert_eq!(&numstr[..len], b"+0.100000000000");
+        // `%#+.18446744073709551615g` doesn't fit with its final 0
+        flags.precision = Some(usize::MAX);
+        assert_eq!(libc_g(&mut numstr, 0.1, &flags), Err(GError::Overflow));
+        for (alternate, plus, conversion, res) in [
+            (false, false, Conversion::General, "%g"),
+            (true, false, Conversion::General, "%#g"),
+            (false, true, Conversion::Scientific, "%+e"),
+            (true, true, Conversion::Fixed, "%#+f"),
+        ] {
+            let flags = Flags {
+                alternate,
+                plus,
+                conversion,
+                ..Flags::default()
+            };
+            let format = libc_format(&mut format, &flags, true).unwrap();
+            assert_eq!(format, format!("{}\0", res).as_bytes());
+        }
+    }
+    #[test]
+    fn row() {
+        let mut numstr = [0; 200];
+        let mut flags = Flags::default();
+        let len = libc_row(&mut numstr, &[1., -0.5, 1e100], " % ", &flags).unwrap();
+        assert_eq!(&numstr[..len.unwrap()], b"1 % -0.5 % 1e+100");
+        for values in [&[0.; 5][..], &[1., f64::NAN]] {
+            assert_eq!(libc_row(&mut numstr, values, " ", &flags), Ok(None));
+        }
+        flags.center = true;
+        assert_eq!(libc_row(&mut numstr, &[1.], " ", &flags), Ok(None));
+        assert_eq!(
+            libc_row(&mut [0; 8], &[1., 2.], "       ", &Flags::default()),
+            Err(GError::Overflow)
+        );
+        // the same output as one value at a time
+        let mut row = crate::Buffer::new();
+        let mut single = crate::Buffer::new();
+        for spec in ["%g", "%-+8.3e", "% 012.5f", "%#.0g", "%.17g", "%8G"] {
+            let spec = Spec::parse(spec).unwrap();
+            for values in crate::testing::corpus()
+                .chunks(3)
+                // small enough for `%f` in a buffer
+                .filter(|values| values.iter().all(|value| value.abs() < 1e100))
+            {
+                let res = values
+                    .iter()
+                    .map(|&value| single.format(value, &spec).unwrap().to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let mut numstr = [0; 200];
+                let len = libc_row(&mut numstr, values, ",", &spec.flags).unwrap();
+                if !spec.uppercase {
+                    assert_eq!(&numstr[..len.unwrap()], res.as_bytes(), "{:?}", spec);
+                }
+                assert_eq!(
+                    row.format_row(values, ",", &spec),
+                    Ok(&res[..]),
+                    "{:?}",
+                    spec
+                );
+            }
+        }
+    }
+    #[test]
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    fn errno() {
+        const EOVERFLOW: i32 = 75;
+        let flags = Flags {
+            precision: Some(3_000_000_000),
+            ..Flags::default()
+        };
+        let error = libc_g(&mut [0; 200], 1., &flags).unwrap_err();
+        assert_eq!(error, GError::Libc(Some(EOVERFLOW)));
+        assert_eq!(error.errno(), Some(EOVERFLOW));
+        assert_eq!(
+            error.to_string(),
+            "the C library failed formatting a number: \
+             Value too large for defined data type (os error 75)"
+        );
+        // such precisions are left to the exact backend
+        assert_eq!(format_g(Backend::Libc, &mut [0; 2000], 1., &flags), Ok(1));
+    }
+    #[test]
+    fn strfromd_same_as_libc() {
+        let strfromd = Options::new().backend(Backend::Strfromd);
+        if cfg!(all(target_os = "linux", target_env = "gnu")) {
+            assert!(Backend::Strfromd.is_available());
+        }
+        for num in [
+            0.,
+            -0.,
+            42.,
+            -1.01,
+            1e-5,
+            123456.,
+            1234567.,
+            -1e100,
+            std::f64::consts::PI,
+            f64::NAN,
+            -f64::INFINITY,
+        ] {
+            macro_rules! check {
+                ($($fmt:literal),*) => {$(
+                    assert_eq!(
+                        format!($fmt, GPoint(num).with(strfromd)),
+                        format!($fmt, GPoint(num)),
+                        "{} with {}", num, $fmt
+                    );
+                )*};
+            }
+            check!(
+                "{}",
+                "{:.0}",
+                "{:.3}",
+                "{:.17}",
+                "{:#}",
+                "{:#.0}",
+                "{:#.3}",
+                "{:+}",
+                "{:12}",
+                "{:<12}",
+                "{:012}",
+                "{:+012.4}",
+                "{:<#12.2}"
+            );
+        }
+    }
+    #[test]
+    fn strfromd_locale() {
+        let french = Options::new().locale(NumericLocale::Named("fr_FR.UTF-8"));
+        // only where that locale is installed
+        if GPoint(1.5).with(french).try_to_string() != Ok("1,5".into()) {
+            return;
+        }
+        let strfromd = french.backend(Backend::Strfromd);
+        assert_eq!(format!("{}", GPoint(1.5).with(strfromd)), "1.5");
+        assert_eq!(format!("{:#.3}", GPoint(-2.).with(strfromd)), "-2.00");
+        let engineering = strfromd.notation(crate::Notation::Engineering);
+        assert_eq!(format!("{}", GPoint(1.25e7).with(engineering)), "12.5e+06");
+        // and the locale of the thread is restored
+        assert_eq!(format!("{}", GPoint(1.5).with(french)), "1,5");
+    }
+
+    #[test]
+    fn custom() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Counts its calls, then formats through the pure backend.
+        #[derive(Debug)]
+        struct Counting(AtomicUsize);
+
+        impl FormatBackend for Counting {
+            fn format(&self, buf: &mut [u8], value: f64, spec: &Spec) -> Result<usize, GError> {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                format_g(Backend::Pure, buf, value, &spec.flags)
+            }
+        }
+
+        #[derive(Debug)]
+        struct Broken;
+
+        impl FormatBackend for Broken {
+            fn format(&self, buf: &mut [u8], _value: f64, _spec: &Spec) -> Result<usize, GError> {
+                buf[..2].copy_from_slice("\u{b5}".as_bytes());
+                Ok(2)
+            }
+        }
+
+        /// Writes an exponent beyond any `i32`.
+        #[derive(Debug)]
+        struct Huge;
+
+        impl FormatBackend for Huge {
+            fn format(&self, buf: &mut [u8], _value: f64, _spec: &Spec) -> Result<usize, GError> {
+                let output = b"1e+99999999999";
+                buf[..output.len()].copy_from_slice(output);
+                Ok(output.len())
+            }
+        }
+
+        static COUNTING: Counting = Counting(AtomicUsize::new(0));
+        let counting = Options::new().backend(Backend::Custom(&COUNTING));
+        assert_eq!(format!("{:+.3}", GPoint(1.23456).with(counting)), "+1.23");
+        let spec = Spec::parse("%10.2E")
+            .unwrap()
+            .backend(Backend::Custom(&COUNTING));
+        assert_eq!(spec.format(-1234.5), " -1.23E+03");
+        assert_eq!(format!("{}", GPoint(1.).with(counting)), "1");
+        assert_eq!(COUNTING.0.load(Ordering::Relaxed), 3);
+        assert_eq!(Backend::Custom(&COUNTING), Backend::Custom(&COUNTING));
+        assert_ne!(Backend::Custom(&COUNTING), Backend::Custom(&Broken));
+        let broken = Spec::default().backend(Backend::Custom(&Broken));
+        assert_eq!(broken.try_format(1.5), Err(GError::Backend));
+        let huge = Spec::default().backend(Backend::Custom(&Huge));
+        assert_eq!(huge.try_format(1.5), Err(GError::Backend));
+    }
+}
@@ -0,0 +1,139 @@
+//! Reformats the numbers read from files or the standard input with a
+//! `printf()` specification, for diffing against C programs' output.
+
+use gpoint::{GWriter, Spec};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::exit;
+
+const USAGE: &str = "\
+Usage: gpoint [--spec SPEC] [--columns] [--delimiter DELIM] [FILE]...
+
+Reads one number per line from each FILE (or the standard input when none is
+given or FILE is `-`), and prints it formatted with SPEC, `%g` by default.
+
+Options:
+  -s, --spec SPEC        printf() conversion specification, e.g. `%10.4g`
+  -c, --columns          format every whitespace-separated field of each line
+  -d, --delimiter DELIM  join fields with DELIM in column mode (default: tab)
+  -h, --help             print this help
+";
+
+struct Args {
+    spec: Spec,
+    columns: bool,
+    delimiter: String,
+    files: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        spec: Spec::default(),
+        columns: false,
+        delimiter: "\t".to_string(),
+        files: Vec::new(),
+    };
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "-s" | "--spec" => {
+                let spec = argv.next().ok_or("missing SPEC")?;
+                args.spec = Spec::parse(&spec).map_err(|e| format!("{} `{}`", e, spec))?;
+            }
+            "-c" | "--columns" => args.columns = true,
+            "-d" | "--delimiter" => args.delimiter = argv.next().ok_or("missing DELIM")?,
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                exit(0);
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option `{}`", arg))
+            }
+            _ => args.files.push(arg),
+        }
+    }
+    if args.files.is_empty() {
+        args.files.push("-".to_string());
+    }
+    Ok(args)
+}
+
+/// Writes `field` formatted with `spec` if it is a number, as is otherwise.
+fn write_field<W: Write>(out: &mut GWriter<W>, field: &str, spec: &Spec) -> io::Result<()> {
+    match field.parse::<f64>() {
+        Ok(value) => out.write_g_spec(value, spec),
+        Err(_) => out.write_sep(field),
+    }
+}
+
+fn write_line<W: Write>(out: &mut GWriter<W>, line: &str, args: &Args) -> io::Result<()> {
+    if args.columns {
+        for (i, field) in line.split_whitespace().enumerate() {
+            if i > 0 {
+                out.write_sep(&args.delimiter)?;
+            }
+            write_field(out, field, &args.spec)?;
+        }
+    } else {
+        write_field(out, line.trim(), &args.spec)?;
+    }
+    out.write_sep("\n")
+}
+
+fn run(args: &Args) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = GWriter::new(stdout.lock());
+    for file in &args.files {
+        let input: Box<dyn BufRead> = if file == "-" {
+            Box::new(BufReader::new(io::stdin()))
+        } else {
+            Box::new(BufReader::new(File::open(file).map_err(|e| {
+                io::Error::new(e.kind(), format!("{}: {}", file, e))
+            })?))
+        };
+        for line in input.lines() {
+            write_line(&mut out, &line?, args)?;
+        }
+    }
+    out.flush()
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("gpoint: {}\n\n{}", e, USAGE);
+        exit(2);
+    });
+    if let Err(e) = run(&args) {
+        // a closed pipe is the reader's choice, e.g. `gpoint | head`
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("gpoint: {}", e);
+            exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn lines() {
+        let mut args = Args {
+            spec: Spec::parse("%.3g").unwrap(),
+            columns: false,
+            delimiter: ",".to_string(),
+            files: Vec::new(),
+        };
+        let mut out = GWriter::new(Vec::new());
+        for line in [" 1.2345 ", "inf", "x 1.2345", ""] {
+            write_line(&mut out, line, &args).unwrap();
+        }
+        args.columns = true;
+        for line in ["1.2345  12345 x", "  -0.0001 "] {
+            write_line(&mut out, line, &args).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out.into_inner().unwrap()).unwrap(),
+            "1.23\ninf\nx 1.2345\n\n1.23,1.23e+04,x\n-0.0001\n"
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Helpers for writing formatted numbers into byte buffers
+
+use crate::{backend, format_g, Flags, GConfig, GError, Options, Spec, NUMSTR_SIZE};
+use std::fmt;
+
+/// A `fmt::Write` sink into a fixed-size byte buffer, failing on overflow.
+pub(crate) struct ByteWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> ByteWriter<'a> {
+    pub(crate) fn new(buf: &'a mut [u8]) -> Self {
+        ByteWriter { buf, len: 0 }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+}
+
+impl fmt::Write for ByteWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// A buffer numbers are formatted into, for when neither allocating nor
+/// thread-local storage is wanted.
+///
+/// ```
+/// use gpoint::{Buffer, GError, Spec};
+///
+/// let mut buffer = Buffer::new();
+/// let spec = Spec::parse("%+.3g").unwrap();
+/// assert!(buffer.format(1.2345, &spec) == Ok("+1.23"));
+/// let huge = Spec::parse("%#.500g").unwrap();
+/// assert!(buffer.format(1. / 3., &huge) == Err(GError::Overflow));
+/// ```
+///
+/// Its size is `N` bytes, twice, for the output and for rewriting it: 200 by
+/// default, as used by `Display`. Smaller buffers save stack on embedded
+/// targets, larger ones fit extreme precisions.
+///
+/// ```
+/// use gpoint::{Buffer, GError, Spec};
+///
+/// let mut small = Buffer::<24>::sized();
+/// assert!(small.format(1.2345, &Spec::parse("%+.3g").unwrap()) == Ok("+1.23"));
+/// assert!(small.format(1. / 3., &Spec::parse("%.30g").unwrap()) == Err(GError::Overflow));
+/// let mut large = Buffer::<1024>::sized();
+/// assert!(large.format(1. / 3., &Spec::parse("%#.500g").unwrap()).unwrap().len() == 502);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Buffer<const N: usize = NUMSTR_SIZE> {
+    numstr: [u8; N],
+    digits: [u8; N],
+}
+
+impl Buffer {
+    /// A new buffer of the default size; it can be reused for any number of
+    /// numbers.
+    pub fn new() -> Self {
+        Buffer::sized()
+    }
+}
+
+impl<const N: usize> Buffer<N> {
+    /// Backends need room for a final 0 after at least one byte.
+    const MIN_SIZE: () = assert!(N >= 2, "buffers need at least 2 bytes");
+
+    /// A new buffer of `N` bytes; it can be reused for any number of numbers.
+    pub const fn sized() -> Self {
+        let () = Self::MIN_SIZE;
+        Buffer {
+            numstr: [0; N],
+            digits: [0; N],
+        }
+    }
+
+    /// Formats `value` as `printf()` would with the given specification.
+    pub fn format(&mut self, value: f64, spec: &Spec) -> Result<&str, GError> {
+        self.format_with(value, spec, GConfig::get())
+    }
+
+    /// Formats `value` as `printf()` would with the given specification,
+    /// along with `options`.
+    pub fn format_with(
+        &mut self,
+        value: f64,
+        spec: &Spec,
+        options: Options,
+    ) -> Result<&str, GError> {
+        let options = spec.apply(options);
+        let len = format_g(
+            &mut self.numstr,
+            &mut self.digits,
+            value,
+            &spec.flags_with(&options),
+            &options,
+        )?;
+        Ok(unsafe { std::str::from_utf8_unchecked(&self.numstr[..len]) })
+    }
+
+    /// Formats `values` as `printf()` would with the given specification,
+    /// separated by `separator`, e.g. the coordinates of a point in an export
+    /// loop.
+    ///
+    /// With the default [`Options`] and backend, rows of up to 4 finite values
+    /// are formatted with a single `snprintf()` call of a compound format
+    /// string like `"%g %g %g"`, instead of one call per value.
+    ///
+    /// ```
+    /// use gpoint::{Buffer, Spec};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// let spec = Spec::parse("%.3g").unwrap();
+    /// assert!(buffer.format_row(&[1. / 3., -2e-10, 1e6], " ", &spec) == Ok("0.333 -2e-10 1e+06"));
+    /// assert!(buffer.format_row(&[1., f64::NAN], "% ", &spec) == Ok("1% nan"));
+    /// ```
+    pub fn format_row(
+        &mut self,
+        values: &[f64],
+        separator: &str,
+        spec: &Spec,
+    ) -> Result<&str, GError> {
+        self.format_row_with(values, separator, spec, GConfig::get())
+    }
+
+    /// Formats `values` as `printf()` would with the given specification,
+    /// along with `options`, separated by `separator`.
+    pub fn format_row_with(
+        &mut self,
+        values: &[f64],
+        separator: &str,
+        spec: &Spec,
+        options: Options,
+    ) -> Result<&str, GError> {
+        let options = spec.apply(options);
+        let flags = spec.flags_with(&options);
+        // the precision of the options is in `flags`
+        let plain = Options {
+            precision: None,
+            ..options
+        } == Options::new();
+        let len = if plain {
+            backend::libc_row(&mut self.numstr, values, separator, &flags)?
+        } else {
+            None
+        };
+        let len = match len {
+            Some(len) => len,
+            // one value at a time
+            None => {
+                let mut len = 0;
+                for (i, &value) in values.iter().enumerate() {
+                    if i > 0 {
+                        let end = len + separator.len();
+                        if end >= N {
+                            return Err(GError::Overflow);
+                        }
+                        self.numstr[len..end].copy_from_slice(separator.as_bytes());
+                        len = end;
+                    }
+                    len += format_g(
+                        &mut self.numstr[len..],
+                        &mut self.digits[len..],
+                        value,
+                        &flags,
+                        &options,
+                    )?;
+                }
+                len
+            }
+        };
+        Ok(unsafe { std::str::from_utf8_unchecked(&self.numstr[..len]) })
+    }
+}
+
+impl<const N: usize> Default for Buffer<N> {
+    fn default() -> Self {
+        Buffer::sized()
+    }
+}
+
+/// Pads the `len` first bytes of `buf` up to the requested width the way
+/// `printf()` does: with the fill character (a space unless the format string
+/// says otherwise) on the left, on the right when left-aligned, on both sides
+/// when centered, or zeros after the `sign_len` first bytes with the `0` flag
+/// (only if `zeros` is set, i.e. usually for finite numbers). The width counts
+/// characters, not bytes. Returns the padded length.
+pub(crate) fn pad(
+    buf: &mut [u8],
+    len: usize,
+    sign_len: usize,
+    flags: &Flags,
+    zeros: bool,
+) -> Result<usize, GError> {
+    let width = flags.width.unwrap_or(0);
+    let chars = buf[..len].iter().filter(|&&c| c & 0xc0 != 0x80).count();
+    if chars >= width {
+        return Ok(len);
+    }
+    let padding = width - chars;
+    if flags.zero && zeros && !flags.left {
+        if len + padding > buf.len() {
+            return Err(GError::Overflow);
+        }
+        buf.copy_within(sign_len..len, sign_len + padding);
+        buf[sign_len..sign_len + padding].fill(b'0');
+        return Ok(len + padding);
+    }
+    let mut fill = [0u8; 4];
+    let fill = flags.fill.encode_utf8(&mut fill).as_bytes();
+    let padding_len = padding * fill.len();
+    if len + padding_len > buf.len() {
+        return Err(GError::Overflow);
+    }
+    // like Rust, put the odd padding character on the right when centering
+    let before = match (flags.left, flags.center) {
+        (true, _) => 0,
+        (false, true) => padding / 2,
+        (false, false) => padding,
+    } * fill.len();
+    buf.copy_within(..len, before);
+    let (head, tail) = buf[..len + padding_len].split_at_mut(before);
+    for chunk in head
+        .chunks_exact_mut(fill.len())
+        .chain(tail[len..].chunks_exact_mut(fill.len()))
+    {
+        chunk.copy_from_slice(fill);
+    }
+    Ok(len + padding_len)
+}
+
+/// Inserts `bytes` at position `at` in the `len` first bytes of `buf`,
+/// returning the new length.
+pub(crate) fn insert(buf: &mut [u8], len: usize, at: usize, bytes: &[u8]) -> Result<usize, GError> {
+    let new_len = len + bytes.len();
+    if new_len > buf.len() {
+        return Err(GError::Overflow);
+    }
+    buf.copy_within(at..len, at + bytes.len());
+    buf[at..at + bytes.len()].copy_from_slice(bytes);
+    Ok(new_len)
+}
@@ -0,0 +1,97 @@
+//! Memoized formatting
+
+use crate::{formatted, Flags, GConfig, GPoint, GPointWith, Options};
+use std::cell::OnceCell;
+use std::fmt;
+
+/// A floating point number remembering how it was formatted, so that
+/// displaying it again doesn't format it again, e.g. for the legends of
+/// plots rendered on every frame. Returned by [`GPoint::cached()`].
+///
+/// Only the output for the flags, width and precision of the first `Display`
+/// is kept: formatting it in other ways works as usual, without caching.
+///
+/// ```
+/// use gpoint::GPoint;
+///
+/// let legend = GPoint(0.1 + 0.2).cached();
+/// for _frame in 0..3 {
+///     assert!(format!("{:.3}", legend) == "0.3");
+/// }
+/// assert!(format!("{:.17}", legend) == "0.30000000000000004");
+/// ```
+#[derive(Debug, Clone)]
+pub struct GCached<Float> {
+    value: Float,
+    options: Options,
+    cache: OnceCell<(Flags, String)>,
+}
+
+impl<Float> GPoint<Float> {
+    /// Wraps your number so that its formatted output is cached.
+    pub fn cached(self) -> GCached<Float> {
+        GPoint(self.0).with(GConfig::get()).cached()
+    }
+}
+
+impl<Float> GPointWith<Float> {
+    /// Wraps your number and its options so that its formatted output is
+    /// cached.
+    pub fn cached(self) -> GCached<Float> {
+        GCached {
+            value: self.value,
+            options: self.options,
+            cache: OnceCell::new(),
+        }
+    }
+}
+
+impl<Float: Copy + Into<f64>> fmt::Display for GCached<Float> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = Flags::from_formatter(f);
+        flags.precision = flags.precision.or(self.options.precision);
+        let format = || formatted(self.value.into(), &flags, &self.options, str::to_owned);
+        let (cached_flags, cached) = match self.cache.get() {
+            Some(cache) => cache,
+            None => self
+                .cache
+                .get_or_init(|| (flags, format().unwrap_or_default())),
+        };
+        if *cached_flags == flags && !cached.is_empty() {
+            f.write_str(cached)
+        } else {
+            f.write_str(&format()?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn cached() {
+        for (num, res) in [(42., "    42"), (-1.01, " -1.01"), (f64::NAN, "   nan")] {
+            let cached = GPoint(num).cached();
+            assert_eq!(format!("{:6}", cached), res);
+            assert_eq!(format!("{:6}", cached), res);
+            assert_eq!(format!("{}", cached), res.trim_start());
+            assert_eq!(format!("{:6}", cached), res);
+        }
+        let cached = GPoint(1.5f32)
+            .with(Options::new().typographic_minus(true))
+            .cached();
+        assert_eq!(format!("{:<5}|{}", cached, cached), "1.5  |1.5");
+        // the same output as uncached, with the precision of the options
+        let options = Options::new().precision(3);
+        let uncached = GPoint(1.23456).with(options);
+        let cached = uncached.cached();
+        for _ in 0..2 {
+            assert_eq!(format!("{}", cached), format!("{}", uncached));
+            assert_eq!(format!("{:.5}", cached), format!("{:.5}", uncached));
+        }
+        let cached = crate::GPointP::<2>(1.23456).with(Options::new()).cached();
+        assert_eq!(format!("{}", cached), "1.2");
+        let _guard = crate::ConfigGuard::new(options);
+        assert_eq!(format!("{}", GPoint(1.23456).cached()), "1.23");
+    }
+}
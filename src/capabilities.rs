@@ -0,0 +1,255 @@
+//! Detection of the running C library's formatting behavior
+
+use std::sync::OnceLock;
+
+/// The family of C library `printf()` is provided by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LibcFlavor {
+    /// GNU C library, found on most Linux distributions.
+    Glibc,
+    /// musl libc, e.g. on Alpine Linux or static Linux builds.
+    Musl,
+    /// BSD-derived libcs, including macOS and iOS.
+    Bsd,
+    /// Microsoft's C runtime.
+    Msvc,
+    /// Any other C library.
+    Unknown,
+}
+
+/// Formatting behavior of the running C library, as probed by
+/// [`capabilities()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// The family of the C library, see [`libc_flavor()`].
+    pub flavor: LibcFlavor,
+    /// Whether the `%a` hexadecimal float conversion is supported.
+    pub hex_float: bool,
+    /// Minimum number of digits printed in exponents, e.g. 2 for `1e+06`.
+    pub exponent_digits: usize,
+    /// How `printf("%g")` spells a NaN.
+    pub nan: String,
+    /// How `printf("%g")` spells positive infinity.
+    pub inf: String,
+    /// Whether a NaN with its sign bit set is printed with a `-` sign.
+    pub signed_nan: bool,
+}
+
+/// What the build script found out about the target C library by compiling
+/// probes with its C compiler, see [`BUILD_CAPABILITIES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BuildCapabilities {
+    /// Whether probing was possible at all, i.e. a C compiler was found:
+    /// when it wasn't, every other field is `false` or `None`.
+    pub probed: bool,
+    /// Whether the `%a` hexadecimal float conversion is supported. Assumed,
+    /// as C99 requires it, when cross-compiling.
+    pub hex_float: bool,
+    /// Whether the BSD `snprintf_l()`, formatting in a given locale, exists.
+    pub snprintf_l: bool,
+    /// Whether C23's `strfromd()` exists, which
+    /// [`Backend::Strfromd`](crate::Backend::Strfromd) still looks up at
+    /// runtime.
+    pub strfromd: bool,
+    /// The size in bytes of C's `long double`: 8 where it is a `double`, 12
+    /// or 16 for x87 extended precision or quadruple precision.
+    pub long_double_size: Option<usize>,
+}
+
+/// The capabilities of the target C library known at build time, unlike
+/// the ones [`capabilities()`] probes at runtime. Within this crate, they
+/// are the `gpoint_probed`, `gpoint_hex_float`, `gpoint_snprintf_l`,
+/// `gpoint_strfromd` and `gpoint_long_double = "<size>"` cfgs.
+///
+/// ```
+/// use gpoint::BUILD_CAPABILITIES;
+///
+/// if BUILD_CAPABILITIES.probed && !BUILD_CAPABILITIES.strfromd {
+///     eprintln!("strfromd() unavailable, Backend::Strfromd falls back to Backend::Pure");
+/// }
+/// ```
+pub const BUILD_CAPABILITIES: BuildCapabilities = BuildCapabilities {
+    probed: cfg!(gpoint_probed),
+    hex_float: cfg!(gpoint_hex_float),
+    snprintf_l: cfg!(gpoint_snprintf_l),
+    strfromd: cfg!(gpoint_strfromd),
+    long_double_size: if cfg!(gpoint_long_double = "8") {
+        Some(8)
+    } else if cfg!(gpoint_long_double = "12") {
+        Some(12)
+    } else if cfg!(gpoint_long_double = "16") {
+        Some(16)
+    } else {
+        None
+    },
+};
+
+/// Finds out the family of the C library this crate is linked with, as
+/// probed by [`capabilities()`].
+///
+/// ```
+/// use gpoint::{libc_flavor, LibcFlavor};
+///
+/// if libc_flavor() == LibcFlavor::Msvc {
+///     eprintln!("exponents may have 3 digits with older C runtimes");
+/// }
+/// ```
+pub fn libc_flavor() -> LibcFlavor {
+    capabilities().flavor
+}
+
+/// Tells glibc by its `gnu_get_libc_version()` and Microsoft's runtimes by
+/// their NaN spellings or 3-digit exponents, and otherwise trusts the build
+/// target, but for a glibc target running another C library.
+fn probe_flavor(nan: &str, exponent_digits: usize) -> LibcFlavor {
+    if has_symbol(b"gnu_get_libc_version\0") {
+        return LibcFlavor::Glibc;
+    }
+    if exponent_digits == 3 || nan.contains('#') || nan.contains("(ind)") {
+        return LibcFlavor::Msvc;
+    }
+    match target_flavor() {
+        LibcFlavor::Glibc if cfg!(unix) => LibcFlavor::Unknown,
+        flavor => flavor,
+    }
+}
+
+#[cfg(all(unix, not(gpoint_no_libc)))]
+fn has_symbol(name: &[u8]) -> bool {
+    let symbol = unsafe { libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr() as *const libc::c_char) };
+    !symbol.is_null()
+}
+
+#[cfg(any(not(unix), gpoint_no_libc))]
+fn has_symbol(_name: &[u8]) -> bool {
+    false
+}
+
+/// Guesses the family of the C library from the build target. Windows
+/// targets get [`LibcFlavor::Msvc`] whatever their environment, as MinGW's
+/// `printf()` comes from Microsoft's C runtime too.
+fn target_flavor() -> LibcFlavor {
+    if cfg!(gpoint_no_libc) {
+        LibcFlavor::Unknown
+    } else if cfg!(windows) {
+        LibcFlavor::Msvc
+    } else if cfg!(target_env = "gnu") {
+        LibcFlavor::Glibc
+    } else if cfg!(target_env = "musl") {
+        LibcFlavor::Musl
+    } else if cfg!(any(
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+    )) {
+        LibcFlavor::Bsd
+    } else {
+        LibcFlavor::Unknown
+    }
+}
+
+/// Probes the running C library's formatting behavior.
+///
+/// Probing is done only once, on first call.
+///
+/// ```
+/// let capabilities = gpoint::capabilities();
+/// if capabilities.nan != "nan" {
+///     eprintln!("warning: this libc spells NaN as {}", capabilities.nan);
+/// }
+/// ```
+pub fn capabilities() -> &'static Capabilities {
+    static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+    CAPABILITIES.get_or_init(|| {
+        let exponent_digits = probe(b"%e\0", 1.)
+            .and_then(|s| {
+                let (_, exponent) = s.split_once('e')?;
+                Some(exponent.trim_start_matches(['+', '-']).len())
+            })
+            .unwrap_or(2);
+        let nan = probe(b"%g\0", f64::NAN).unwrap_or_default();
+        Capabilities {
+            flavor: probe_flavor(&nan, exponent_digits),
+            hex_float: probe(b"%a\0", 1.).is_some_and(|s| s.starts_with("0x1")),
+            exponent_digits,
+            nan,
+            inf: probe(b"%g\0", f64::INFINITY).unwrap_or_default(),
+            signed_nan: probe(b"%g\0", -f64::NAN).is_some_and(|s| s.starts_with('-')),
+        }
+    })
+}
+
+#[cfg(not(gpoint_no_libc))]
+fn probe(format: &[u8], value: f64) -> Option<String> {
+    use std::os::raw::c_char;
+    let mut buf = [0u8; 64];
+    let nbchars = unsafe {
+        libc::snprintf(
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+            format.as_ptr() as *const c_char,
+            value,
+        )
+    };
+    if nbchars < 0 || nbchars as usize >= buf.len() {
+        return None;
+    }
+    String::from_utf8(buf[..nbchars as usize].to_vec()).ok()
+}
+
+/// Formats as [`Backend::Libc`](crate::Backend::Libc) does without a C
+/// library, i.e. in pure Rust, which has no `%a`.
+#[cfg(gpoint_no_libc)]
+fn probe(format: &[u8], value: f64) -> Option<String> {
+    let spec = std::str::from_utf8(&format[..format.len() - 1]).ok()?;
+    let spec = crate::Spec::parse(spec).ok()?;
+    let mut buf = [0u8; 64];
+    let len = crate::backend::format_g(crate::Backend::Pure, &mut buf, value, &spec.flags).ok()?;
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn probed() {
+        let capabilities = capabilities();
+        assert!(capabilities.exponent_digits >= 2);
+        assert!(capabilities.nan.to_lowercase().contains("nan"));
+        assert!(capabilities.inf.to_lowercase().contains("inf"));
+    }
+    #[test]
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    fn glibc() {
+        assert_eq!(libc_flavor(), LibcFlavor::Glibc);
+        assert_eq!(
+            capabilities(),
+            &Capabilities {
+                flavor: LibcFlavor::Glibc,
+                hex_float: true,
+                exponent_digits: 2,
+                nan: "nan".into(),
+                inf: "inf".into(),
+                signed_nan: true,
+            }
+        );
+        // unless built without a C compiler
+        if BUILD_CAPABILITIES.probed {
+            assert_eq!(
+                BUILD_CAPABILITIES,
+                BuildCapabilities {
+                    probed: true,
+                    hex_float: true,
+                    snprintf_l: false,
+                    strfromd: true,
+                    long_double_size: Some(if cfg!(target_arch = "x86") { 12 } else { 16 }),
+                }
+            );
+        }
+    }
+}
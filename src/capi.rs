@@ -0,0 +1,126 @@
+//! A C ABI for C and C++ programs, e.g. to diff the output against their own
+//! `printf()`
+
+use crate::{GConfig, GPoint, Options, Spec};
+use libc::{c_char, c_int, size_t};
+use std::convert::TryFrom;
+use std::ffi::CStr;
+
+/// Formats `value` into `buf` as `snprintf(buf, size, spec, value)` would,
+/// with the options of [`GConfig`], for C and C++ programs:
+/// the output is truncated to `size - 1` bytes and always terminated by a 0
+/// when `size` isn't 0. A null `spec` means `%g`.
+///
+/// Returns the length of the whole output, without its final 0, as
+/// `snprintf()` does: the output was truncated if it isn't below `size`.
+/// Returns -1 if `spec` isn't a valid `printf()` specification for a single
+/// floating point number, or if formatting fails.
+///
+/// It is exported with the `capi` feature, in a shared library built with
+/// `cargo rustc --release --features capi --crate-type cdylib`, and declared
+/// in C as:
+///
+/// ```c
+/// int gpoint_format_g(double value, const char *spec, char *buf, size_t size);
+/// ```
+///
+/// # Safety
+///
+/// `spec` must be null or point to a 0-terminated string, and `buf` must be
+/// null with a `size` of 0, or point to at least `size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn gpoint_format_g(
+    value: f64,
+    spec: *const c_char,
+    buf: *mut c_char,
+    size: size_t,
+) -> c_int {
+    format_c(value, spec, GConfig::get(), buf, size)
+}
+
+/// Formats `value` into `buf` like [`gpoint_format_g()`], with `options`.
+///
+/// # Safety
+///
+/// As for [`gpoint_format_g()`].
+pub(crate) unsafe fn format_c(
+    value: f64,
+    spec: *const c_char,
+    options: Options,
+    buf: *mut c_char,
+    size: size_t,
+) -> c_int {
+    let spec = if spec.is_null() {
+        Spec::default()
+    } else {
+        match CStr::from_ptr(spec).to_str().ok().map(Spec::parse) {
+            Some(Ok(spec)) => spec,
+            _ => return -1,
+        }
+    };
+    let len = GPoint(value).with(options).with_formatted(&spec, |numstr| {
+        if size > 0 && !buf.is_null() {
+            let copied = numstr.len().min(size - 1);
+            std::ptr::copy_nonoverlapping(numstr.as_ptr() as *const c_char, buf, copied);
+            *buf.add(copied) = 0;
+        }
+        numstr.len()
+    });
+    match len.map(c_int::try_from) {
+        Ok(Ok(len)) => len,
+        _ => -1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn format_g() {
+        let format = |value, spec: &[u8], size| {
+            let mut buf = [b'x' as c_char; 16];
+            let len = unsafe {
+                gpoint_format_g(
+                    value,
+                    spec.as_ptr() as *const c_char,
+                    buf.as_mut_ptr(),
+                    size,
+                )
+            };
+            let output = unsafe { CStr::from_ptr(buf.as_ptr()) };
+            (len, output.to_str().unwrap().to_owned())
+        };
+        assert_eq!(format(1. / 3., b"%g\0", 16), (8, "0.333333".into()));
+        assert_eq!(format(-1234.5, b"%+.3e\0", 16), (10, "-1.234e+03".into()));
+        assert_eq!(format(1. / 3., b"%.3g\0", 4), (5, "0.3".into()));
+        assert_eq!(format(1., b"%5d\0", 16).0, -1);
+        assert_eq!(format(1., b"\xff\0", 16).0, -1);
+        let mut buf = [0 as c_char; 16];
+        let len = unsafe { gpoint_format_g(2.5, std::ptr::null(), buf.as_mut_ptr(), 16) };
+        assert_eq!(len, 3);
+        assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_bytes(), b"2.5");
+        // only measuring, as with `snprintf(NULL, 0, ...)`
+        let len = unsafe { gpoint_format_g(1e100, std::ptr::null(), std::ptr::null_mut(), 0) };
+        assert_eq!(len, 6);
+        // the same output as the C library
+        for &num in crate::testing::corpus() {
+            for spec in [&b"%g\0"[..], b"%#.3g\0", b"%-12.5e\0", b"%+.1f\0"] {
+                let mut c = [0 as c_char; 400];
+                let c_len = unsafe {
+                    libc::snprintf(c.as_mut_ptr(), c.len(), spec.as_ptr() as *const c_char, num)
+                };
+                let mut rust = [0 as c_char; 400];
+                let rust_len = unsafe {
+                    gpoint_format_g(
+                        num,
+                        spec.as_ptr() as *const c_char,
+                        rust.as_mut_ptr(),
+                        rust.len(),
+                    )
+                };
+                assert_eq!(rust_len, c_len);
+                assert_eq!(rust[..], c[..]);
+            }
+        }
+    }
+}
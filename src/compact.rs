@@ -0,0 +1,167 @@
+//! Formatted numbers stored inline
+
+use crate::{formatted, GConfig, GError, GPoint, GPointWith, Spec};
+use std::fmt;
+use std::ops::Deref;
+
+/// How many bytes a [`GString`] stores without allocating: enough for any
+/// `%g` output with the default precision.
+const INLINE_SIZE: usize = 30;
+
+/// A formatted number, stored inline when short enough, as returned by
+/// [`GPoint::to_compact_string()`]: formatted floats are almost always a
+/// couple dozen bytes long, so high-volume serializers don't allocate for
+/// each of them.
+///
+/// ```
+/// use gpoint::GPoint;
+///
+/// let compact = GPoint(-1.5e-10).to_compact_string().unwrap();
+/// assert!(compact == "-1.5e-10" && compact.is_inline());
+/// ```
+#[derive(Clone)]
+pub struct GString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, bytes: [u8; INLINE_SIZE] },
+    Heap(String),
+}
+
+impl GString {
+    /// Returns the formatted number.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            // only ever filled from a `str`
+            Repr::Inline { len, bytes } => unsafe {
+                std::str::from_utf8_unchecked(&bytes[..usize::from(*len)])
+            },
+            Repr::Heap(string) => string,
+        }
+    }
+
+    /// Whether the formatted number is stored inline, i.e. without
+    /// allocating.
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+}
+
+impl From<&str> for GString {
+    fn from(string: &str) -> Self {
+        if string.len() > INLINE_SIZE {
+            return GString(Repr::Heap(string.to_owned()));
+        }
+        let mut bytes = [0; INLINE_SIZE];
+        bytes[..string.len()].copy_from_slice(string.as_bytes());
+        GString(Repr::Inline {
+            len: string.len() as u8,
+            bytes,
+        })
+    }
+}
+
+impl From<GString> for String {
+    fn from(string: GString) -> Self {
+        match string.0 {
+            Repr::Inline { .. } => string.as_str().to_owned(),
+            Repr::Heap(string) => string,
+        }
+    }
+}
+
+impl Deref for GString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for GString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for GString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for GString {}
+
+impl PartialEq<str> for GString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for GString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for GString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl fmt::Debug for GString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for GString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<Float: Copy + Into<f64>> GPoint<Float> {
+    /// Formats your number like [`GPoint::try_to_string()`], into a
+    /// [`GString`] which only allocates for long outputs.
+    pub fn to_compact_string(&self) -> Result<GString, GError> {
+        self.with(GConfig::get()).to_compact_string()
+    }
+}
+
+impl<Float: Copy + Into<f64>> GPointWith<Float> {
+    /// Formats your number like [`GPointWith::try_to_string()`], into a
+    /// [`GString`] which only allocates for long outputs.
+    pub fn to_compact_string(&self) -> Result<GString, GError> {
+        let spec = Spec::default();
+        let options = spec.apply(self.options);
+        formatted(
+            self.value.into(),
+            &spec.flags_with(&options),
+            &options,
+            |numstr| GString::from(numstr),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Options;
+    #[test]
+    fn compact() {
+        for num in [0., -1.01, 1e-300, -f64::MAX, f64::NAN, -f64::INFINITY] {
+            let compact = GPoint(num).to_compact_string().unwrap();
+            assert!(compact.is_inline());
+            assert_eq!(compact, GPoint(num).try_to_string().unwrap());
+        }
+        let options = Options::new().precision(40);
+        let compact = GPoint(0.1).with(options).to_compact_string().unwrap();
+        assert!(!compact.is_inline());
+        assert_eq!(
+            String::from(compact),
+            "0.1000000000000000055511151231257827021182"
+        );
+    }
+}
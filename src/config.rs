@@ -0,0 +1,113 @@
+//! Process-wide default options
+
+use crate::Options;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+static GLOBAL: RwLock<Options> = RwLock::new(Options::new());
+/// Whether [`GLOBAL`] may differ from the defaults, to skip locking it.
+static CONFIGURED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static SCOPED: Cell<Option<Options>> = const { Cell::new(None) };
+}
+
+/// The process-wide default [`Options`], used by every number formatted
+/// without options of its own, so that conventions such as a default
+/// precision or a NaN spelling are enforced in one place.
+///
+/// Options given with [`GPoint::with()`](crate::GPoint::with) replace the
+/// defaults: build them from [`GConfig::get()`] to only change some.
+///
+/// ```
+/// use gpoint::{ConfigGuard, GConfig, GPoint};
+///
+/// {
+///     let _guard = ConfigGuard::new(GConfig::get().precision(3).nan("NaN"));
+///     assert!(format!("{} {}", GPoint(1.23456), GPoint(f64::NAN)) == "1.23 NaN");
+/// }
+/// assert!(format!("{}", GPoint(1.23456)) == "1.23456");
+/// ```
+#[derive(Debug)]
+pub struct GConfig;
+
+impl GConfig {
+    /// Sets the default options of the whole process.
+    pub fn set(options: Options) {
+        *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = options;
+        CONFIGURED.store(true, Ordering::Release);
+    }
+
+    /// Returns the default options of the calling thread: the ones of its
+    /// innermost [`ConfigGuard`], or else the ones of the process.
+    pub fn get() -> Options {
+        if let Some(options) = SCOPED.with(Cell::get) {
+            return options;
+        }
+        if !CONFIGURED.load(Ordering::Acquire) {
+            return Options::new();
+        }
+        *GLOBAL.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Overrides the default options of the calling thread for as long as it
+/// lives, restoring the previous ones when dropped, e.g. in tests run in
+/// parallel.
+#[derive(Debug)]
+#[must_use = "the previous options are restored when the guard is dropped"]
+pub struct ConfigGuard {
+    previous: Option<Options>,
+    /// Restoring must happen on the same thread.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ConfigGuard {
+    /// Makes `options` the default ones of the calling thread.
+    pub fn new(options: Options) -> Self {
+        ConfigGuard {
+            previous: SCOPED.with(|scoped| scoped.replace(Some(options))),
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for ConfigGuard {
+    fn drop(&mut self) {
+        SCOPED.with(|scoped| scoped.set(self.previous));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExponentStyle, GPoint, Spec};
+    #[test]
+    fn scoped() {
+        assert_eq!(GConfig::get(), Options::new());
+        {
+            let _guard = ConfigGuard::new(Options::new().precision(2));
+            assert_eq!(format!("{}", GPoint(1.23456)), "1.2");
+            {
+                let _guard = ConfigGuard::new(Options::new().exponent(ExponentStyle::Latex));
+                assert_eq!(format!("{}", GPoint(1e10)), "1\\times10^{10}");
+                assert_eq!(
+                    Spec::parse("%.3e").unwrap().format(1.),
+                    "1.000\\times10^{0}"
+                );
+            }
+            assert_eq!(GPoint(1.23456).try_to_string().unwrap(), "1.2");
+            // explicit options replace the defaults
+            assert_eq!(
+                format!("{}", GPoint(1.23456).with(Options::new())),
+                "1.23456"
+            );
+            std::thread::spawn(|| assert_eq!(format!("{}", GPoint(1.23456)), "1.23456"))
+                .join()
+                .unwrap();
+        }
+        assert_eq!(format!("{}", GPoint(1.23456)), "1.23456");
+    }
+}
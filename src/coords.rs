@@ -0,0 +1,107 @@
+//! Coordinates, i.e. tuples of numbers
+
+use crate::{fmt_g, GConfig, Options};
+use std::fmt;
+
+/// A 2- or 3-tuple of floats displayed as coordinates, e.g. `(1.5, -2)`,
+/// each of them with `%g` and the flags, width and precision of the format
+/// string.
+///
+/// `GPoint` itself can't display tuples, since it displays any type
+/// converting into `f64`, which tuples might do one day.
+///
+/// ```
+/// use gpoint::GCoords;
+///
+/// assert!(format!("{}", GCoords((1.5, -2.))) == "(1.5, -2)");
+/// assert!(format!("{:.3}", GCoords((1. / 3., 2f32, 1e10))) == "(0.333, 2, 1e+10)");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GCoords<T>(
+    /// Your coordinates you want to `Display`
+    pub T,
+);
+
+impl<T> GCoords<T> {
+    /// Attaches formatting [`Options`] to your coordinates.
+    pub fn with(self, options: Options) -> GCoordsWith<T> {
+        GCoordsWith {
+            coords: self.0,
+            options,
+        }
+    }
+}
+
+/// Coordinates along with the [`Options`] used to `Display` them, as
+/// returned by [`GCoords::with()`].
+#[derive(Debug, Clone, Copy)]
+pub struct GCoordsWith<T> {
+    coords: T,
+    options: Options,
+}
+
+/// Writes `coords` between parentheses, separated by commas.
+fn fmt_coords(f: &mut fmt::Formatter<'_>, coords: &[f64], options: &Options) -> fmt::Result {
+    f.write_str("(")?;
+    for (i, &coord) in coords.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        fmt_g(f, coord, options)?;
+    }
+    f.write_str(")")
+}
+
+impl<X: Copy + Into<f64>, Y: Copy + Into<f64>> fmt::Display for GCoords<(X, Y)> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with(GConfig::get()).fmt(f)
+    }
+}
+
+impl<X: Copy + Into<f64>, Y: Copy + Into<f64>, Z: Copy + Into<f64>> fmt::Display
+    for GCoords<(X, Y, Z)>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with(GConfig::get()).fmt(f)
+    }
+}
+
+impl<X: Copy + Into<f64>, Y: Copy + Into<f64>> fmt::Display for GCoordsWith<(X, Y)> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (x, y) = self.coords;
+        fmt_coords(f, &[x.into(), y.into()], &self.options)
+    }
+}
+
+impl<X: Copy + Into<f64>, Y: Copy + Into<f64>, Z: Copy + Into<f64>> fmt::Display
+    for GCoordsWith<(X, Y, Z)>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (x, y, z) = self.coords;
+        fmt_coords(f, &[x.into(), y.into(), z.into()], &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn coords() {
+        for (coords, res) in [
+            ((0., 0.), "(0, 0)"),
+            ((-1.01, 1e-10), "(-1.01, 1e-10)"),
+            ((f64::NAN, f64::INFINITY), "(nan, inf)"),
+        ] {
+            assert_eq!(format!("{}", GCoords(coords)), res);
+        }
+        assert_eq!(
+            format!("{:6.2}", GCoords((1.234, 5.678, 9.))),
+            "(   1.2,    5.7,      9)"
+        );
+        let typographic = Options::new().typographic_minus(true);
+        assert_eq!(
+            format!("{}", GCoords((-1., 2f32)).with(typographic)),
+            "(\u{2212}1, 2)"
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! Decimal floating point numbers, such as IEEE 754 decimal64 and decimal128
+
+use crate::buffer::ByteWriter;
+use crate::{
+    finish, notation_flags, significant_digits, Conversion, Flags, GConfig, GError, GPoint,
+    GPointWith, Notation, Options, MAX_PRECISION, MAX_SCRATCH_SIZE,
+};
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+
+/// A decimal floating point number `coefficient × 10^exponent`, as decimal64
+/// and decimal128 values or decNumber's `digits` and `exponent` decompose
+/// into, formatted from its decimal digits without going through a binary
+/// `f64`.
+///
+/// The digits are rounded to the precision half to even, as decimal
+/// arithmetic does by default, and `%g` switches to the exponent form at the
+/// same thresholds as for floats: `%g` and the `#`, `+`, width and alignment
+/// flags are supported. Numbers are limited to the range of decimal128, with
+/// a first digit worth `10^-6176` to `10^6144`: formatting others fails with
+/// [`GError::Overflow`].
+///
+/// ```
+/// use gpoint::{GDecimal, GPoint};
+///
+/// let price = GDecimal::new(1999, -2);
+/// assert!(format!("{}", GPoint(price)) == "19.99");
+/// assert!(format!("{:.3}", GPoint(price)) == "20");
+/// assert!(format!("{}", GPoint(GDecimal::new(1, 6144))) == "1e+6144");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GDecimal {
+    /// The signed coefficient: up to 16 digits for decimal64, 34 for
+    /// decimal128.
+    pub coefficient: i128,
+    /// The power of ten the coefficient is multiplied by.
+    pub exponent: i32,
+}
+
+impl GDecimal {
+    /// The number `coefficient × 10^exponent`.
+    pub fn new(coefficient: i128, exponent: i32) -> Self {
+        GDecimal {
+            coefficient,
+            exponent,
+        }
+    }
+}
+
+/// The power of ten of the first digit of the smallest decimal128.
+const MIN_EXPONENT: i64 = -6176;
+
+/// The power of ten of the first digit of the largest decimal128.
+const MAX_EXPONENT: i64 = 6144;
+
+impl GDecimal {
+    /// The power of ten of the first digit, 0 for zero.
+    fn first_exponent(self) -> i64 {
+        match self.coefficient.unsigned_abs().checked_ilog10() {
+            Some(digits) => i64::from(digits) + i64::from(self.exponent),
+            None => 0,
+        }
+    }
+}
+
+/// Writes `value` scaled by `10^scale` as `printf()` would with `flags`,
+/// without padding, into `out`.
+fn write_g(
+    out: &mut ByteWriter<'_>,
+    value: GDecimal,
+    flags: &Flags,
+    scale: i32,
+) -> Result<(), GError> {
+    let mut digits = value.coefficient.unsigned_abs().to_string().into_bytes();
+    let mut exponent = match value.coefficient {
+        0 => 0,
+        _ => value.first_exponent() + i64::from(scale),
+    };
+    let count = digit_count(flags, exponent);
+    round(&mut digits, &mut exponent, count, false, Rounding::Nearest);
+    write_digits(out, value.coefficient < 0, &digits, exponent, flags)
+}
+
+/// How [`round()`] rounds digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rounding {
+    /// To nearest, ties to even, as decimal arithmetic and C libraries do.
+    Nearest,
+    /// Away from zero.
+    Up,
+    /// Toward zero.
+    Down,
+}
+
+/// The number of significant digits `flags` keep of a number whose first
+/// digit is worth `10^exponent`: for `%f`, none or less when the number is
+/// below the last decimal.
+pub(crate) fn digit_count(flags: &Flags, exponent: i64) -> i64 {
+    let precision = flags.precision.unwrap_or(6) as i64;
+    match flags.conversion {
+        Conversion::General => significant_digits(flags.precision) as i64,
+        Conversion::Scientific => precision + 1,
+        Conversion::Fixed => exponent + 1 + precision,
+    }
+}
+
+/// Rounds the significant `digits` of a number, whose first one is worth
+/// `10^exponent`, to `count` digits. `inexact` tells whether nonzero digits
+/// follow the given ones. Numbers rounded to zero are left without digits.
+pub(crate) fn round(
+    digits: &mut Vec<u8>,
+    exponent: &mut i64,
+    count: i64,
+    inexact: bool,
+    rounding: Rounding,
+) {
+    if count >= digits.len() as i64 {
+        return;
+    }
+    let kept = count.max(0) as usize;
+    let dropped = &digits[kept..];
+    let round_up = match rounding {
+        // below half the last digit kept
+        Rounding::Nearest if count < 0 => false,
+        Rounding::Nearest => match dropped[0] {
+            b'0'..=b'4' => false,
+            b'5' if !inexact && dropped[1..].iter().all(|&digit| digit == b'0') => {
+                kept > 0 && (digits[kept - 1] - b'0') % 2 == 1
+            }
+            _ => true,
+        },
+        Rounding::Up => inexact || dropped.iter().any(|&digit| digit != b'0'),
+        Rounding::Down => false,
+    };
+    digits.truncate(kept);
+    if round_up {
+        if kept == 0 {
+            // a single unit of the last digit kept
+            digits.push(b'1');
+            *exponent += 1 - count;
+        } else {
+            increment(digits, exponent);
+        }
+    }
+}
+
+/// Adds one unit in the last place to `digits`, carrying into `exponent`.
+fn increment(digits: &mut Vec<u8>, exponent: &mut i64) {
+    match digits.iter().rposition(|&digit| digit != b'9') {
+        Some(last) => {
+            digits[last] += 1;
+            digits.truncate(last + 1);
+        }
+        None => {
+            *digits = vec![b'1'];
+            *exponent += 1;
+        }
+    }
+}
+
+/// Writes the significant `digits` of a number, already rounded by
+/// [`round()`], whose first digit is worth `10^exponent`, as `printf()` would
+/// with `flags`, without padding, into `out`. Missing digits are zeros.
+pub(crate) fn write_digits(
+    out: &mut ByteWriter<'_>,
+    negative: bool,
+    digits: &[u8],
+    exponent: i64,
+    flags: &Flags,
+) -> Result<(), GError> {
+    if negative {
+        out.write_char('-')?;
+    } else if flags.plus {
+        out.write_char('+')?;
+    }
+    let precision = flags.precision.unwrap_or(6) as i64;
+    let (scientific, fraction) = match flags.conversion {
+        Conversion::General => {
+            let precision = significant_digits(flags.precision) as i64;
+            if exponent >= -4 && exponent < precision {
+                (false, precision - 1 - exponent)
+            } else {
+                (true, precision - 1)
+            }
+        }
+        Conversion::Scientific => (true, precision),
+        Conversion::Fixed => (false, precision),
+    };
+    // the power of ten of the first digit, as written
+    let first = if scientific { 0 } else { exponent };
+    let digit = |power: i64| {
+        usize::try_from(first - power)
+            .ok()
+            .and_then(|i| digits.get(i))
+            .map_or('0', |&digit| digit as char)
+    };
+    let start = out.len();
+    for power in (0..=first.max(0)).rev() {
+        out.write_char(digit(power))?;
+    }
+    // `%g` drops trailing zeros, and the point if nothing is left after it
+    let trim = flags.conversion == Conversion::General && !flags.alternate;
+    if fraction > 0 || flags.alternate || trim {
+        out.write_char('.')?;
+    }
+    for power in 1..=fraction {
+        out.write_char(digit(-power))?;
+    }
+    if trim {
+        let digits = &out.as_bytes()[start..];
+        let zeros = digits.iter().rev().take_while(|&&c| c == b'0').count();
+        let trimmed = digits.len() - zeros;
+        let trimmed = if digits[trimmed - 1] == b'.' {
+            trimmed - 1
+        } else {
+            trimmed
+        };
+        out.truncate(start + trimmed);
+    }
+    if scientific {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        write!(out, "e{}{:02}", sign, exponent.abs())?;
+    }
+    Ok(())
+}
+
+/// The flags the digits of exact numbers are written with for
+/// `options.notation`, and the power of ten they are scaled by first: the
+/// notations printing the shortest digits parsing back to an `f64`, or all
+/// the digits of one, are refused.
+fn exact_notation(flags: &Flags, options: &Options) -> Result<(Flags, i32), GError> {
+    match options.notation {
+        Notation::Exact | Notation::Excel => return Err(GError::UnsupportedNotation),
+        Notation::Go | Notation::DotNet | Notation::Xsd if flags.precision.is_none() => {
+            return Err(GError::UnsupportedNotation)
+        }
+        _ => (),
+    }
+    Ok(notation_flags(flags, options))
+}
+
+/// Hands the output of `write_g`, a writer for a finite number of the given
+/// sign with the flags and the power of ten it is scaled by, to `f` once
+/// rewritten and padded as `flags` and `options` ask, in the notation of
+/// `options`.
+pub(crate) fn formatted_with<R>(
+    flags: &Flags,
+    negative: bool,
+    options: &Options,
+    write_g: impl Fn(&mut ByteWriter<'_>, &Flags, i32) -> Result<(), GError>,
+    f: impl FnOnce(&str) -> R,
+) -> Result<R, GError> {
+    let (digit_flags, scale) = exact_notation(flags, options)?;
+    let precision = significant_digits(digit_flags.precision);
+    if precision > MAX_PRECISION {
+        return Err(GError::Overflow);
+    }
+    // the `%g` output is at most all digits, a point and some zeros or an
+    // exponent, rewriting may group digits with 4-byte separators
+    let mut size = 2
+        * (4 * (precision + 16) + options.min_fraction_digits + options.min_exponent_digits)
+        + 4 * flags.width.unwrap_or(0);
+    // any finite number of the same sign
+    let sign = if negative { -1. } else { 1. };
+    loop {
+        let mut scratch = vec![0; 2 * size];
+        let (numstr, digits) = scratch.split_at_mut(size);
+        let mut out = ByteWriter::new(&mut numstr[..size - 1]);
+        let len = write_g(&mut out, &digit_flags, scale)
+            .map(|()| out.len())
+            .and_then(|len| finish(numstr, digits, len, sign, flags, options));
+        match len {
+            Ok(len) => {
+                return Ok(f(
+                    std::str::from_utf8(&numstr[..len]).map_err(|_| GError::Backend)?
+                ))
+            }
+            // `%f` writes every integer digit of large numbers
+            Err(GError::Overflow) if size < MAX_SCRATCH_SIZE => size *= 4,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Writes the output of `write_g` as [`formatted_with()`] does, with the
+/// flags of `f`.
+pub(crate) fn fmt_with(
+    f: &mut fmt::Formatter<'_>,
+    negative: bool,
+    options: &Options,
+    write_g: impl Fn(&mut ByteWriter<'_>, &Flags, i32) -> Result<(), GError>,
+) -> fmt::Result {
+    let mut flags = Flags::from_formatter(f);
+    flags.precision = flags.precision.or(options.precision);
+    formatted_with(&flags, negative, options, write_g, |numstr| {
+        f.write_str(numstr)
+    })?
+}
+
+fn fmt_decimal(f: &mut fmt::Formatter<'_>, value: GDecimal, options: &Options) -> fmt::Result {
+    if !(MIN_EXPONENT..=MAX_EXPONENT).contains(&value.first_exponent()) {
+        return Err(GError::Overflow.into());
+    }
+    fmt_with(f, value.coefficient < 0, options, |out, flags, scale| {
+        write_g(out, value, flags, scale)
+    })
+}
+
+impl fmt::Display for GPoint<GDecimal> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_decimal(f, self.0, &GConfig::get())
+    }
+}
+
+impl fmt::Display for GPointWith<GDecimal> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_decimal(f, self.value, &self.options)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The notations exact number types support, with the precisions they are
+    /// tested with, for tests comparing them with floats.
+    pub(crate) fn notations() -> Vec<(Notation, Option<usize>)> {
+        let mut notations = Vec::new();
+        for notation in [
+            Notation::General,
+            Notation::Percent {
+                fixed: false,
+                space: false,
+            },
+            Notation::Percent {
+                fixed: true,
+                space: true,
+            },
+            Notation::Engineering,
+            Notation::Si,
+            Notation::GCode { trim: false },
+            Notation::GCode { trim: true },
+            Notation::Toml,
+            Notation::Json5,
+            Notation::Yaml,
+        ] {
+            for precision in [None, Some(0), Some(3), Some(10)] {
+                notations.push((notation, precision));
+            }
+        }
+        for notation in [Notation::Go, Notation::DotNet, Notation::Xsd] {
+            for precision in [Some(1), Some(3), Some(10)] {
+                notations.push((notation, precision));
+            }
+        }
+        notations
+    }
+
+    /// Formats `value` and `GPoint(float)` with every notation of
+    /// [`notations()`] as the configured one, for tests comparing them.
+    pub(crate) fn formatted_notations(
+        value: impl fmt::Display,
+        float: f64,
+    ) -> Vec<(Notation, Option<usize>, String, String)> {
+        notations()
+            .into_iter()
+            .map(|(notation, precision)| {
+                let _guard = crate::ConfigGuard::new(Options::new().notation(notation));
+                let (value, float) = match precision {
+                    Some(p) => (
+                        format!("{:.*}", p, value),
+                        format!("{:.*}", p, GPoint(float)),
+                    ),
+                    None => (format!("{}", value), format!("{}", GPoint(float))),
+                };
+                (notation, precision, value, float)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decimal() {
+        for (coefficient, exponent, precision, res) in [
+            (12345, -2, None, "123.45"),
+            (1234567, -2, None, "12345.7"),
+            (5, -1, None, "0.5"),
+            (-5, -5, None, "-5e-05"),
+            (5, -5, Some(1), "5e-05"),
+            (5, -4, Some(1), "0.0005"),
+            (125, -2, Some(2), "1.2"),
+            (135, -2, Some(2), "1.4"),
+            (1251, -3, Some(2), "1.3"),
+            (9999995, -6, None, "10"),
+            (9999995, 0, None, "1e+07"),
+            (0, 5, None, "0"),
+            (0, -5, Some(3), "0"),
+            (1, 6144, None, "1e+6144"),
+            (-1, -6176, None, "-1e-6176"),
+            (
+                9_999_999_999_999_999_999_999_999_999_999_999,
+                6111,
+                Some(34),
+                "9.999999999999999999999999999999999e+6144",
+            ),
+            (1_000_000_000_000_000, -16, Some(20), "0.1"),
+        ] {
+            let num = GPoint(GDecimal::new(coefficient, exponent));
+            let formatted = match precision {
+                Some(p) => format!("{:.*}", p, num),
+                None => format!("{}", num),
+            };
+            assert_eq!(formatted, res, "{:?}", num);
+        }
+        let num = GPoint(GDecimal::new(123, 0));
+        assert_eq!(format!("{:#}", num), "123.000");
+        assert_eq!(format!("{:+10.2}|", num), "  +1.2e+02|");
+        let grouped = Options::new().grouping(',');
+        assert_eq!(
+            format!("{}", GPoint(GDecimal::new(-1234567, -3)).with(grouped)),
+            "-1,234.57"
+        );
+        // same as floats in every notation, for numbers both hold exactly
+        for (coefficient, exponent) in [(5, -1), (-125, -3), (125, 1), (1, 10), (3, -7)] {
+            let decimal = GPoint(GDecimal::new(coefficient, exponent));
+            let float = coefficient as f64 * 10f64.powi(exponent);
+            for (notation, precision, decimal, float) in formatted_notations(decimal, float) {
+                assert_eq!(decimal, float, "{:?} {:?}", notation, precision);
+            }
+        }
+        // exponents beyond decimal128 fail instead of wrapping
+        let engineering = Options::new().notation(Notation::Engineering);
+        for (coefficient, exponent) in
+            [(5, i32::MIN), (i128::MIN, i32::MAX), (10, 6144), (1, -6177)]
+        {
+            let num = GPoint(GDecimal::new(coefficient, exponent));
+            let mut out = String::new();
+            assert!(write!(out, "{:.3}", num).is_err());
+            assert!(write!(out, "{}", num.with(engineering)).is_err());
+        }
+        let num = GPoint(GDecimal::new(i128::MIN, 6106));
+        assert_eq!(format!("{:.3}", num), "-1.7e+6144");
+        assert_eq!(format!("{:.3}", num.with(engineering)), "-1.7e+6144");
+        assert_eq!(format!("{}", GPoint(GDecimal::new(0, i32::MIN))), "0");
+        let num = GPoint(GDecimal::new(5, -1));
+        assert_eq!(
+            format!("{}", num.with(Options::new().notation(Notation::Si))),
+            "500m"
+        );
+        for notation in [
+            Notation::Exact,
+            Notation::Excel,
+            Notation::Go,
+            Notation::Xsd,
+        ] {
+            let mut out = String::new();
+            let options = Options::new().notation(notation);
+            assert!(write!(out, "{}", num.with(options)).is_err());
+        }
+        // same as floats for integers, which they hold exactly
+        for coefficient in [1, 7, 45, 125, 999, 123_456_789, (1 << 53) - 1] {
+            for precision in [1, 2, 3, 6, 17] {
+                assert_eq!(
+                    format!("{:.*}", precision, GPoint(GDecimal::new(coefficient, 0))),
+                    format!("{:.*}", precision, GPoint(coefficient as f64)),
+                    "{}",
+                    coefficient
+                );
+            }
+        }
+    }
+}
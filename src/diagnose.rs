@@ -0,0 +1,220 @@
+//! Explaining why C and Rust print a number differently
+
+use crate::{formatted, Backend, GError, Options, Spec};
+use std::fmt;
+
+/// How the output of the C library and Rust's own differ, see
+/// [`Diagnosis::differences`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Difference {
+    /// Only one of them is negative, e.g. `-nan` and `NaN`, or `-0` and `0`.
+    Sign,
+    /// Non-finite values are spelled differently, e.g. `INF` and `inf`.
+    NonFinite,
+    /// Only one of them has an exponent, e.g. `1e+06` and `1000000`.
+    Notation,
+    /// They show different significant digits, e.g. `%g` rounds to 6 of them
+    /// where Rust shows as many as needed to parse back to the number.
+    Digits,
+    /// The same number is laid out differently, e.g. with trailing zeros, a
+    /// longer exponent or padding.
+    Layout,
+    /// The C library disagrees with glibc, as emulated by
+    /// [`Backend::Pure`].
+    Platform,
+}
+
+/// A comparison between the C library's output for a number and Rust's, as
+/// returned by [`diagnose()`], displayed as a report for bug reports.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Diagnosis {
+    /// The number.
+    pub value: f64,
+    /// The `printf()` specification it was formatted with.
+    pub spec: Spec,
+    /// The C library's output.
+    pub libc: Result<String, GError>,
+    /// glibc's output, as emulated by [`Backend::Pure`].
+    pub glibc: Result<String, GError>,
+    /// Rust's `{}` output, with the shortest digits parsing back to the
+    /// number.
+    pub rust: String,
+    /// Rust's `{:e}` output, with the same digits.
+    pub rust_exp: String,
+    /// How the C library's output differs from Rust's `{}` one, empty if
+    /// they are the same.
+    pub differences: Vec<Difference>,
+}
+
+/// Formats `value` with the C library, with its glibc emulation and with
+/// Rust, then describes how their outputs differ.
+///
+/// ```
+/// use gpoint::{diagnose, Difference, Spec};
+///
+/// let diagnosis = diagnose(0.1 + 0.2, &Spec::default());
+/// assert!(diagnosis.libc.as_deref() == Ok("0.3"));
+/// assert!(diagnosis.rust == "0.30000000000000004");
+/// assert!(diagnosis.differences == [Difference::Digits]);
+/// println!("{}", diagnosis);
+/// ```
+pub fn diagnose(value: f64, spec: &Spec) -> Diagnosis {
+    let format = |backend| {
+        let options = spec.backend(backend).apply(Options::new());
+        formatted(value, &spec.flags, &options, str::to_owned)
+    };
+    let libc = format(Backend::Libc);
+    let glibc = format(Backend::Pure);
+    let rust = format!("{}", value);
+    let mut differences = match &libc {
+        Ok(libc) => compare(libc, &rust),
+        Err(_) => Vec::new(),
+    };
+    if let (Ok(libc), Ok(glibc)) = (&libc, &glibc) {
+        if libc != glibc {
+            differences.push(Difference::Platform);
+        }
+    }
+    Diagnosis {
+        value,
+        spec: *spec,
+        libc,
+        glibc,
+        rust,
+        rust_exp: format!("{:e}", value),
+        differences,
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let output = |output: &Result<String, GError>| match output {
+            Ok(output) => format!("{:?}", output),
+            Err(e) => format!("error: {}", e),
+        };
+        writeln!(
+            f,
+            "value: {:e} (0x{:016x})",
+            self.value,
+            self.value.to_bits()
+        )?;
+        writeln!(f, "libc {}: {}", self.spec, output(&self.libc))?;
+        writeln!(f, "glibc {}: {}", self.spec, output(&self.glibc))?;
+        writeln!(f, "rust {{}}: {:?}", self.rust)?;
+        writeln!(f, "rust {{:e}}: {:?}", self.rust_exp)?;
+        write!(f, "differences:")?;
+        if self.differences.is_empty() {
+            write!(f, " none")?;
+        }
+        for difference in &self.differences {
+            write!(f, " {:?}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+/// A number as printed, split into what it shows.
+#[derive(Debug, PartialEq)]
+struct Shape {
+    negative: bool,
+    /// The spelling of non-finite numbers.
+    non_finite: Option<String>,
+    /// The significant digits, without leading nor trailing zeros.
+    digits: String,
+    /// The decimal exponent of the first significant digit.
+    exponent: i32,
+    scientific: bool,
+}
+
+impl Shape {
+    fn parse(text: &str) -> Shape {
+        let text = text.trim();
+        let negative = text.starts_with('-');
+        let text = text.trim_start_matches(['-', '+', ' ']);
+        if !text.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            return Shape {
+                negative,
+                non_finite: Some(text.to_owned()),
+                digits: String::new(),
+                exponent: 0,
+                scientific: false,
+            };
+        }
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(e) => (&text[..e], text[e + 1..].parse().ok()),
+            None => (text, None),
+        };
+        let point = mantissa.find('.').unwrap_or(mantissa.len());
+        let all: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+        let leading = all.chars().take_while(|&c| c == '0').count();
+        let digits = all[leading..].trim_end_matches('0').to_owned();
+        Shape {
+            negative,
+            non_finite: None,
+            exponent: if digits.is_empty() {
+                0
+            } else {
+                exponent.unwrap_or(0) + point as i32 - leading as i32 - 1
+            },
+            digits,
+            scientific: exponent.is_some(),
+        }
+    }
+}
+
+fn compare(libc: &str, rust: &str) -> Vec<Difference> {
+    let (c, r) = (Shape::parse(libc), Shape::parse(rust));
+    let mut differences = Vec::new();
+    if c.negative != r.negative {
+        differences.push(Difference::Sign);
+    }
+    if (c.non_finite.is_some() || r.non_finite.is_some()) && c.non_finite != r.non_finite {
+        differences.push(Difference::NonFinite);
+    }
+    if c.non_finite.is_none() && r.non_finite.is_none() {
+        if c.scientific != r.scientific {
+            differences.push(Difference::Notation);
+        }
+        if (&c.digits, c.exponent) != (&r.digits, r.exponent) {
+            differences.push(Difference::Digits);
+        }
+    }
+    if differences.is_empty() && libc != rust {
+        differences.push(Difference::Layout);
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn differences() {
+        let g = Spec::default();
+        for (num, spec, differences) in [
+            (1.5, "%g", &[][..]),
+            (1e6, "%g", &[Difference::Notation][..]),
+            (
+                1234567.,
+                "%g",
+                &[Difference::Notation, Difference::Digits][..],
+            ),
+            (1.5, "%.3f", &[Difference::Layout][..]),
+            (1.5, "%8g", &[Difference::Layout][..]),
+            (f64::INFINITY, "%G", &[Difference::NonFinite][..]),
+            (f64::INFINITY, "%g", &[][..]),
+            (1e-7, "%g", &[Difference::Notation][..]),
+        ] {
+            let diagnosis = diagnose(num, &Spec::parse(spec).unwrap());
+            assert_eq!(diagnosis.differences, differences, "{} {}", spec, num);
+        }
+        let nan = diagnose(f64::NAN, &g);
+        assert_eq!(nan.rust, "NaN");
+        assert!(nan.differences.contains(&Difference::NonFinite));
+        let report = diagnose(0.1, &g).to_string();
+        assert!(report.starts_with("value: 1e-1 (0x3fb999999999999a)\nlibc %g: \"0.1\"\n"));
+        assert!(report.ends_with("differences: none"));
+    }
+}
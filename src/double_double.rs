@@ -0,0 +1,221 @@
+//! Double-double numbers, such as the ones of the `twofloat` crate
+
+use crate::buffer::ByteWriter;
+use crate::decimal::{digit_count, fmt_with, round, write_digits, Rounding};
+use crate::{exact, fmt_g, Flags, GConfig, GError, GPoint, GPointWith, Options};
+use std::fmt::{self, Write};
+
+/// The digits of an exact decimal expansion: a guard digit for carries, the
+/// 309 integer digits of the largest `f64`, then its fractional digits.
+const UNITS: usize = 309;
+const WIDTH: usize = UNITS + 1 + exact::FRACTIONAL_DIGITS;
+
+/// An unevaluated sum of two `f64`, with about 32 significant digits, as
+/// carried by compensated summations or the `twofloat` crate (whose
+/// `hi()` and `lo()` give the two halves).
+///
+/// Both halves are added exactly before rounding to the precision, instead of
+/// collapsing the number to its high half: `%g` and the `#`, `+`, width and
+/// alignment flags are supported.
+///
+/// ```
+/// use gpoint::{GDoubleDouble, GPoint};
+///
+/// let third = GDoubleDouble::new(1. / 3., 1.850371707708594e-17);
+/// assert!(format!("{:.32}", GPoint(third)) == "0.33333333333333333333333333333333");
+/// assert!(format!("{:.32}", GPoint(1. / 3.)) == "0.33333333333333331482961625624739");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GDoubleDouble {
+    /// The high half, i.e. the sum rounded to an `f64`.
+    pub hi: f64,
+    /// The low half, i.e. the rounding error of the high one.
+    pub lo: f64,
+}
+
+impl GDoubleDouble {
+    /// The number `hi + lo`.
+    pub fn new(hi: f64, lo: f64) -> Self {
+        GDoubleDouble { hi, lo }
+    }
+}
+
+/// Writes the exact decimal expansion of `|value|` into `out`, one digit per
+/// byte, aligned on the units.
+fn expand(value: f64, out: &mut [u8; WIDTH]) -> fmt::Result {
+    let mut text = [0u8; WIDTH + 1];
+    let mut text = ByteWriter::new(&mut text);
+    write!(text, "{:.*}", exact::FRACTIONAL_DIGITS, value.abs())?;
+    let text = text.as_bytes();
+    let point = text.len() - exact::FRACTIONAL_DIGITS - 1;
+    for (digit, &c) in out[UNITS + 1 - point..]
+        .iter_mut()
+        .zip(text[..point].iter().chain(&text[point + 1..]))
+    {
+        *digit = c - b'0';
+    }
+    Ok(())
+}
+
+/// Computes `a + b` or `a - b` in place in `a`, which must be the largest for
+/// subtractions.
+fn add(a: &mut [u8; WIDTH], b: &[u8; WIDTH], subtract: bool) {
+    let mut carry = 0;
+    for (a, &b) in a.iter_mut().zip(b).rev() {
+        let digit = if subtract {
+            *a as i8 - b as i8 - carry
+        } else {
+            (*a + b) as i8 + carry
+        };
+        carry = !(0..=9).contains(&digit) as i8;
+        *a = digit.rem_euclid(10) as u8;
+    }
+}
+
+/// Writes the sum of `value`'s halves scaled by `10^scale` as `printf()`
+/// would with `flags`, without padding, into `out`.
+fn write_g(
+    out: &mut ByteWriter<'_>,
+    value: GDoubleDouble,
+    flags: &Flags,
+    scale: i32,
+) -> Result<(), GError> {
+    let hi_negative = value.hi.is_sign_negative();
+    let mut sum = [0u8; WIDTH];
+    let mut lo = [0u8; WIDTH];
+    expand(value.hi, &mut sum)?;
+    expand(value.lo, &mut lo)?;
+    let negative = if hi_negative == value.lo.is_sign_negative() {
+        add(&mut sum, &lo, false);
+        hi_negative
+    } else if sum >= lo {
+        add(&mut sum, &lo, true);
+        // zero sums keep the sign of the high half, i.e. of the rounded sum
+        hi_negative
+    } else {
+        add(&mut lo, &sum, true);
+        sum = lo;
+        !hi_negative
+    };
+
+    let (mut digits, mut exponent) = match sum.iter().position(|&digit| digit != 0) {
+        Some(first) => {
+            let last = sum.iter().rposition(|&digit| digit != 0).unwrap_or(first);
+            let digits = sum[first..=last]
+                .iter()
+                .map(|&digit| b'0' + digit)
+                .collect();
+            (digits, UNITS as i64 - first as i64 + i64::from(scale))
+        }
+        None => (Vec::new(), 0),
+    };
+    let count = digit_count(flags, exponent);
+    round(&mut digits, &mut exponent, count, false, Rounding::Nearest);
+    write_digits(out, negative, &digits, exponent, flags)
+}
+
+fn fmt_dd(f: &mut fmt::Formatter<'_>, value: GDoubleDouble, options: &Options) -> fmt::Result {
+    if !value.hi.is_finite() || !value.lo.is_finite() {
+        return fmt_g(f, value.hi + value.lo, options);
+    }
+    fmt_with(
+        f,
+        value.hi.is_sign_negative(),
+        options,
+        |out, flags, scale| write_g(out, value, flags, scale),
+    )
+}
+
+impl fmt::Display for GPoint<GDoubleDouble> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_dd(f, self.0, &GConfig::get())
+    }
+}
+
+impl fmt::Display for GPointWith<GDoubleDouble> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_dd(f, self.value, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::tests::formatted_notations;
+    #[test]
+    fn double_double() {
+        let third = 1.850371707708594e-17;
+        for (hi, lo, precision, res) in [
+            (
+                1. / 3.,
+                third,
+                Some(32),
+                "0.33333333333333333333333333333333",
+            ),
+            (1. / 3., third, None, "0.333333"),
+            (1., 1e-30, Some(31), "1.000000000000000000000000000001"),
+            (1., -1e-30, Some(30), "0.999999999999999999999999999999"),
+            (1152921504606846976., 1., Some(19), "1152921504606846977"),
+            (
+                1152921504606846976.,
+                1.,
+                Some(18),
+                "1.15292150460684698e+18",
+            ),
+            (-2.5, 0., Some(1), "-2"),
+            (9.5, 0., Some(1), "1e+01"),
+            (1., -1., None, "0"),
+            (-0., -0., None, "-0"),
+            (f64::MAX, 0., None, "1.79769e+308"),
+            (f64::NAN, 1., None, "nan"),
+        ] {
+            let num = GPoint(GDoubleDouble::new(hi, lo));
+            let formatted = match precision {
+                Some(p) => format!("{:.*}", p, num),
+                None => format!("{}", num),
+            };
+            assert_eq!(formatted, res, "{:?}", num);
+        }
+        let num = GPoint(GDoubleDouble::new(1. / 3., third));
+        assert_eq!(format!("{:+#12.3}", num), "      +0.333");
+        assert_eq!(
+            format!("{:#}", GPoint(GDoubleDouble::new(1e-5, 0.))),
+            "1.00000e-05"
+        );
+        let grouped = Options::new().grouping(',');
+        let big = GDoubleDouble::new(1e20, 1.);
+        assert_eq!(
+            format!("{:.21}", GPoint(big).with(grouped)),
+            "100,000,000,000,000,000,001"
+        );
+        // same as plain floats in every notation when the low half is zero
+        for num in [0.5, -0.125, 1250., 1e10, 3e-7, 1. / 3.] {
+            let dd = GPoint(GDoubleDouble::new(num, 0.));
+            for (notation, precision, dd, float) in formatted_notations(dd, num) {
+                assert_eq!(dd, float, "{:?} {:?} {}", notation, precision, num);
+            }
+        }
+        let exact = Options::new().notation(crate::Notation::Exact);
+        let mut out = String::new();
+        assert!(write!(
+            out,
+            "{}",
+            GPoint(GDoubleDouble::new(1. / 3., third)).with(exact)
+        )
+        .is_err());
+        // same as plain floats when the low half is zero
+        for &num in crate::testing::corpus()
+            .iter()
+            .filter(|num| num.is_finite())
+        {
+            for precision in [0, 1, 6, 17, 40] {
+                assert_eq!(
+                    format!("{:.*}", precision, GPoint(GDoubleDouble::new(num, 0.))),
+                    format!("{:.*}", precision, GPoint(num)),
+                    "{:e}",
+                    num
+                );
+            }
+        }
+    }
+}
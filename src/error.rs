@@ -0,0 +1,180 @@
+//! Why formatting failed
+
+use std::fmt;
+use std::io;
+
+/// The reason formatting a number failed, as reported by the fallible entry
+/// points like [`Spec::try_format()`](crate::Spec::try_format); `Display`
+/// implementations can only report a bare `fmt::Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GError {
+    /// The output doesn't fit in the buffer it is formatted into.
+    Overflow,
+    /// A `printf()` conversion specification couldn't be parsed.
+    InvalidSpec,
+    /// The C library failed formatting the number, with the `errno` it set
+    /// if any, e.g. `EOVERFLOW` for precisions beyond `INT_MAX`.
+    Libc(Option<i32>),
+    /// A non-finite value was refused, as requested by
+    /// [`NonFinite::Error`](crate::NonFinite::Error).
+    NonFinite,
+    /// The destination refused the output.
+    Write,
+    /// The requested locale couldn't be selected, see
+    /// [`NumericLocale`](crate::NumericLocale).
+    Locale,
+    /// The output isn't pure ASCII, as required by
+    /// [`GPointWith::to_ascii_string()`](crate::GPointWith::to_ascii_string).
+    NonAscii,
+    /// A [`FormatBackend`](crate::FormatBackend) output something else than
+    /// a number.
+    Backend,
+    /// Text couldn't be parsed as a number, see
+    /// [`GTextAs::from_text()`](crate::GTextAs::from_text).
+    InvalidNumber,
+    /// The number type can't be written in the requested
+    /// [`Notation`](crate::Notation), e.g. exact decimals with the shortest
+    /// digits parsing back to an `f64`.
+    UnsupportedNotation,
+    /// A fixed-point number was given more fractional bits than supported,
+    /// see [`GFixed::from_bits()`](crate::GFixed::from_bits).
+    FractionalBits,
+}
+
+impl GError {
+    /// The value of `errno` when the C library failed, if it set it.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            GError::Libc(errno) => *errno,
+            _ => None,
+        }
+    }
+
+    /// Captures `errno` after the C library failed. C functions may change
+    /// it even when they succeed, and may fail without setting it: callers
+    /// clear it with [`clear_errno()`] right before the failing call, for a
+    /// failure which doesn't set it to be reported without any.
+    pub(crate) fn libc() -> Self {
+        GError::Libc(
+            io::Error::last_os_error()
+                .raw_os_error()
+                .filter(|&errno| errno != 0),
+        )
+    }
+}
+
+/// Sets `errno` to 0 before calling the C library, where it can be set.
+pub(crate) fn clear_errno() {
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "emscripten",
+        target_os = "dragonfly",
+        target_os = "redox",
+        target_os = "hurd",
+        target_os = "fuchsia"
+    ))]
+    unsafe {
+        *libc::__errno_location() = 0
+    };
+    #[cfg(any(target_vendor = "apple", target_os = "freebsd"))]
+    unsafe {
+        *libc::__error() = 0
+    };
+    #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+    unsafe {
+        *libc::__errno() = 0
+    };
+    #[cfg(target_os = "solaris")]
+    unsafe {
+        *libc::___errno() = 0
+    };
+    #[cfg(windows)]
+    {
+        extern "C" {
+            fn _errno() -> *mut std::os::raw::c_int;
+        }
+        unsafe { *_errno() = 0 };
+    }
+}
+
+impl fmt::Display for GError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GError::Overflow => "formatted number too long for its buffer",
+            GError::InvalidSpec => "invalid printf() conversion specification",
+            GError::Libc(None) => "the C library failed formatting a number",
+            GError::Libc(Some(errno)) => {
+                return write!(
+                    f,
+                    "the C library failed formatting a number: {}",
+                    io::Error::from_raw_os_error(*errno)
+                )
+            }
+            GError::NonFinite => "non-finite number refused",
+            GError::Write => "writing a formatted number failed",
+            GError::Locale => "unavailable locale",
+            GError::NonAscii => "formatted number not pure ASCII",
+            GError::Backend => "invalid output from the formatting backend",
+            GError::InvalidNumber => "invalid number",
+            GError::UnsupportedNotation => "notation unsupported for this number type",
+            GError::FractionalBits => "too many fractional bits",
+        })
+    }
+}
+
+impl std::error::Error for GError {}
+
+impl From<GError> for fmt::Error {
+    fn from(_: GError) -> Self {
+        fmt::Error
+    }
+}
+
+/// Errors from `fmt::Write` sinks: only fixed-size buffers fail while
+/// formatting, everything else is the destination's doing.
+impl From<fmt::Error> for GError {
+    fn from(_: fmt::Error) -> Self {
+        GError::Overflow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Buffer, NonFinite, Options, Spec};
+    #[test]
+    fn causes() {
+        struct Full;
+        impl fmt::Write for Full {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+        let spec = Spec::default();
+        let strict = Options::new().non_finite(NonFinite::Error);
+        let mut buffer = Buffer::new();
+        assert_eq!(Spec::parse("%5d").unwrap_err(), GError::InvalidSpec);
+        assert_eq!(
+            buffer.format(1e300, &Spec::parse("%f").unwrap()),
+            Err(GError::Overflow)
+        );
+        assert_eq!(
+            buffer.format_with(f64::NAN, &spec, strict),
+            Err(GError::NonFinite)
+        );
+        assert_eq!(spec.write_to(1., &mut Full), Err(GError::Write));
+        assert_eq!(spec.try_format(-0.5), Ok("-0.5".to_string()));
+        assert_eq!(
+            Spec::parse("%f").unwrap().try_format(1e300).unwrap().len(),
+            308
+        );
+    }
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn stale_errno() {
+        unsafe { *libc::__errno_location() = libc::EINVAL };
+        clear_errno();
+        assert_eq!(GError::libc(), GError::Libc(None));
+    }
+}
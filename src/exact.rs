@@ -0,0 +1,94 @@
+//! Exact decimal expansions, for any precision
+
+use crate::{pure, Conversion, Flags};
+use std::fmt;
+
+/// More than the 767 significant digits of the longest exact decimal
+/// expansion of an `f64`: any further digit is a zero.
+const SIGNIFICANT_DIGITS: usize = 800;
+/// More than the 1074 fractional digits of the smallest subnormal `f64`.
+pub(crate) const FRACTIONAL_DIGITS: usize = 1100;
+
+/// The precision the output of [`format_g()`] is as long as: `%g` drops
+/// the trailing zeros of larger ones.
+pub(crate) fn output_precision(flags: &Flags) -> usize {
+    let precision = flags.precision.unwrap_or(6);
+    if flags.conversion == Conversion::General && !flags.alternate {
+        precision.min(SIGNIFICANT_DIGITS)
+    } else {
+        precision
+    }
+}
+
+/// Formats `value` into `buf` like [`pure::format_g()`], for any precision:
+/// digits are computed up to the length of the exact decimal expansion of
+/// `value`, then completed with zeros.
+pub(crate) fn format_g(buf: &mut [u8], value: f64, flags: &Flags) -> Result<usize, fmt::Error> {
+    let precision = flags.precision.unwrap_or(6);
+    let exact = match flags.conversion {
+        Conversion::General => SIGNIFICANT_DIGITS,
+        Conversion::Scientific => SIGNIFICANT_DIGITS - 1,
+        Conversion::Fixed => FRACTIONAL_DIGITS,
+    };
+    if precision <= exact || !value.is_finite() {
+        return pure::format_g(buf, value, flags);
+    }
+    let mut exact_flags = *flags;
+    exact_flags.precision = Some(exact);
+    let len = pure::format_g(buf, value, &exact_flags)?;
+    // `%g` drops trailing zeros, and uses the same notation for both
+    // precisions since exponents can't reach them
+    if flags.conversion == Conversion::General && !flags.alternate {
+        return Ok(len);
+    }
+    let zeros = precision - exact;
+    if len + zeros > buf.len() {
+        return Err(fmt::Error);
+    }
+    let at = buf[..len].iter().position(|&c| c == b'e').unwrap_or(len);
+    buf.copy_within(at..len, at + zeros);
+    buf[at..at + zeros].fill(b'0');
+    Ok(len + zeros)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Backend, GPoint, Options, Spec};
+
+    #[test]
+    fn same_as_libc() {
+        let exact = Options::new().backend(Backend::Exact);
+        for num in [
+            0.,
+            -1.,
+            1. / 3.,
+            1e300,
+            -5e-324,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+        ] {
+            for spec in [
+                "%.1200f", "%#.1200g", "%.1200e", "%.2000g", "%#.799g", "%.5000f",
+            ] {
+                let spec = Spec::parse(spec).unwrap();
+                assert_eq!(
+                    GPoint(num).with(exact).try_to_string_with(&spec),
+                    GPoint(num).try_to_string_with(&spec),
+                    "{} with {:?}",
+                    num,
+                    spec
+                );
+            }
+        }
+    }
+    #[test]
+    fn any_precision() {
+        let spec = Spec::parse("%.4000000000g").unwrap();
+        assert_eq!(spec.try_format(0.1).unwrap().len(), 57);
+        let spec = Spec::parse("%.100000e").unwrap();
+        let res = spec.try_format(-0.5).unwrap();
+        assert_eq!(res.len(), 100007);
+        assert!(res.starts_with("-5.000"));
+        assert!(res.ends_with("000e-01"));
+    }
+}
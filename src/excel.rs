@@ -0,0 +1,107 @@
+//! Excel's "General" number format
+
+use crate::buffer::ByteWriter;
+use crate::pure::write_exponent;
+use crate::Flags;
+use std::fmt::{self, Write};
+
+/// How many characters Excel's General format fits numbers in, signs aside,
+/// in columns of the default width.
+const WIDTH: usize = 11;
+
+/// Formats a finite `value` into `buf` as Excel's General format displays
+/// it, returning the output length: rounded to fit in 11 characters, in the
+/// fixed notation for decimal exponents from -9 up to 10, when that leaves
+/// enough digits, otherwise with 6 significant digits in the scientific one.
+pub(crate) fn format_g(buf: &mut [u8], value: f64, flags: &Flags) -> Result<usize, fmt::Error> {
+    let mut out = ByteWriter::new(buf);
+    if value.is_sign_negative() && value != 0. {
+        out.write_char('-')?;
+    } else if flags.plus {
+        out.write_char('+')?;
+    }
+    let value = value.abs();
+    if value == 0. {
+        out.write_char('0')?;
+        return Ok(out.len());
+    }
+    let start = out.len();
+    let exponent = value.log10().floor() as i32;
+    let fixed = match exponent {
+        // the integer digits, then what's left of the width
+        0..=9 => Some(9 - exponent as usize),
+        10 => Some(0),
+        -4..=-1 => Some(WIDTH - 2),
+        // only when the digits aren't cut
+        -9..=-5 => Some(12).filter(|&decimals| {
+            let mut digits = [0; 16];
+            let mut digits = ByteWriter::new(&mut digits);
+            write!(digits, "{:.*}", decimals, value).is_ok()
+                && trimmed(digits.as_bytes()).len() <= WIDTH
+        }),
+        _ => None,
+    };
+    if let Some(decimals) = fixed {
+        write!(out, "{:.*}", decimals, value)?;
+        let len = trimmed(&out.as_bytes()[start..]).len();
+        // unless rounding carried into another integer digit
+        if len <= WIDTH {
+            out.truncate(start + len);
+            return Ok(out.len());
+        }
+        out.truncate(start);
+    }
+    write!(out, "{:.5e}", value)?;
+    let e = out.as_bytes()[start..]
+        .iter()
+        .position(|&c| c == b'e')
+        .ok_or(fmt::Error)?;
+    let exponent: i32 = std::str::from_utf8(&out.as_bytes()[start + e + 1..])
+        .ok()
+        .and_then(|exponent| exponent.parse().ok())
+        .ok_or(fmt::Error)?;
+    let len = trimmed(&out.as_bytes()[start..start + e]).len();
+    out.truncate(start + len);
+    write_exponent(&mut out, exponent)?;
+    Ok(out.len())
+}
+
+/// Removes the trailing zeros after a decimal point, and the point itself if
+/// nothing is left after it.
+fn trimmed(digits: &[u8]) -> &[u8] {
+    if !digits.contains(&b'.') {
+        return digits;
+    }
+    let zeros = digits.iter().rev().take_while(|&&c| c == b'0').count();
+    let digits = &digits[..digits.len() - zeros];
+    digits.strip_suffix(b".").unwrap_or(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn general() {
+        for (num, res) in [
+            (0., "0"),
+            (-0., "0"),
+            (1., "1"),
+            (-1.5, "-1.5"),
+            (1. / 3., "0.333333333"),
+            (2. / 3., "0.666666667"),
+            (123.456789012345, "123.456789"),
+            (12345678901., "12345678901"),
+            (123456789012., "1.23457e+11"),
+            (0.0001234567, "0.000123457"),
+            (0.00001234, "0.00001234"),
+            (0.0000123456789, "1.23457e-05"),
+            (1e-10, "1e-10"),
+            (9.99999999999, "10"),
+            (99999999999.9, "1e+11"),
+        ] {
+            let mut buf = [0; 32];
+            let len = format_g(&mut buf, num, &Flags::default()).unwrap();
+            assert_eq!(std::str::from_utf8(&buf[..len]).unwrap(), res, "{:e}", num);
+        }
+    }
+}
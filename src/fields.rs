@@ -0,0 +1,156 @@
+//! Structured log fields, as recorded by `tracing` visitors
+
+use crate::{GConfig, GPoint, Options};
+use std::fmt::{self, Write};
+
+/// Structured log fields written as `name=value`, separated by spaces, with
+/// floats formatted with `%g` instead of Rust's default rendering, so that
+/// logs read the same as the ones of C services.
+///
+/// Its `record_*()` methods match the ones of `tracing`'s `Visit` trait: a
+/// visitor of a `tracing-subscriber` layer forwards each of them with the
+/// field's `name()`, e.g. `record_f64(&mut self, field: &Field, value: f64)`
+/// calls `self.0.record_f64(field.name(), value)`. Like `tracing-subscriber`
+/// does, the `message` field is written without its name. Single fields are
+/// recorded with `%g` by `tracing`'s `%` sigil: `info!(speed = %GPoint(x))`.
+///
+/// ```
+/// use gpoint::GFields;
+///
+/// let mut fields = GFields::new();
+/// fields.record_debug("message", &format_args!("step done"));
+/// fields.record_f64("dt", 1.5e-5);
+/// fields.record_u64("step", 42);
+/// fields.record_str("mode", "fast path");
+/// assert!(fields.as_str() == "step done dt=1.5e-05 step=42 mode=\"fast path\"");
+/// ```
+#[derive(Debug, Clone)]
+pub struct GFields {
+    text: String,
+    options: Options,
+}
+
+impl GFields {
+    /// No fields, whose floats will be formatted with the default
+    /// [`Options`].
+    pub fn new() -> Self {
+        GFields {
+            text: String::new(),
+            options: GConfig::get(),
+        }
+    }
+
+    /// Sets the [`Options`] floats are formatted with, e.g. their precision.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Records a float with `%g`, or with Rust's rendering if the options
+    /// refuse it, e.g. a NaN with [`NonFinite::Error`](crate::NonFinite::Error).
+    pub fn record_f64(&mut self, name: &str, value: f64) {
+        let formatted = GPoint(value)
+            .with(self.options)
+            .try_to_string()
+            .unwrap_or_else(|_| value.to_string());
+        self.field(name, format_args!("{}", formatted));
+    }
+
+    /// Records a signed integer.
+    pub fn record_i64(&mut self, name: &str, value: i64) {
+        self.field(name, format_args!("{}", value));
+    }
+
+    /// Records an unsigned integer.
+    pub fn record_u64(&mut self, name: &str, value: u64) {
+        self.field(name, format_args!("{}", value));
+    }
+
+    /// Records a boolean.
+    pub fn record_bool(&mut self, name: &str, value: bool) {
+        self.field(name, format_args!("{}", value));
+    }
+
+    /// Records a string, quoted if it is empty or has spaces, quotes or `=`.
+    pub fn record_str(&mut self, name: &str, value: &str) {
+        if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+            self.field(name, format_args!("{:?}", value));
+        } else {
+            self.field(name, format_args!("{}", value));
+        }
+    }
+
+    /// Records anything else, with its `Debug` output.
+    pub fn record_debug(&mut self, name: &str, value: &dyn fmt::Debug) {
+        self.field(name, format_args!("{:?}", value));
+    }
+
+    /// The fields recorded so far.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the fields recorded so far.
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Forgets the fields recorded so far, e.g. to record the ones of the
+    /// next event.
+    pub fn clear(&mut self) {
+        self.text.clear();
+    }
+
+    fn field(&mut self, name: &str, value: fmt::Arguments<'_>) {
+        if !self.text.is_empty() {
+            self.text.push(' ');
+        }
+        // writing into a `String` doesn't fail
+        let _ = if name == "message" {
+            self.text.write_fmt(value)
+        } else {
+            write!(self.text, "{}={}", name, value)
+        };
+    }
+}
+
+impl Default for GFields {
+    fn default() -> Self {
+        GFields::new()
+    }
+}
+
+impl fmt::Display for GFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NonFinite;
+    #[test]
+    fn fields() {
+        let mut fields = GFields::new().with(Options::new().precision(3));
+        fields.record_f64("x", 1. / 3.);
+        fields.record_f64("big", 1e100);
+        fields.record_i64("n", -7);
+        fields.record_bool("ok", true);
+        fields.record_str("empty", "");
+        fields.record_str("path", "/tmp/a=b");
+        fields.record_str("id", "abc");
+        fields.record_debug("v", &vec![1, 2]);
+        assert_eq!(
+            fields.to_string(),
+            "x=0.333 big=1e+100 n=-7 ok=true empty=\"\" path=\"/tmp/a=b\" id=abc v=[1, 2]"
+        );
+        fields.clear();
+        assert_eq!(fields.as_str(), "");
+        let mut strict = GFields::new().with(Options::new().non_finite(NonFinite::Error));
+        strict.record_f64("nan", f64::NAN);
+        strict.record_f64("inf", -f64::INFINITY);
+        strict.record_f64("one", 1.);
+        assert_eq!(strict.into_string(), "nan=NaN inf=-inf one=1");
+    }
+}
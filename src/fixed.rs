@@ -0,0 +1,203 @@
+//! Fixed-point numbers, such as the ones of the `fixed` crate
+
+use crate::buffer::ByteWriter;
+use crate::decimal::{digit_count, fmt_with, round, write_digits, Rounding};
+use crate::{fmt_g, Flags, GConfig, GError, GPoint, GPointWith, Options};
+use std::fmt::{self, Write};
+
+/// A binary fixed-point number given by its raw bits, e.g. `x.to_bits()` for
+/// the `fixed` crate's `I16F16` and 16 fractional bits, to render fixed-point
+/// telemetry with the same `%g` conventions as floats.
+///
+/// Numbers an `f64` holds exactly are formatted as that `f64`. The others
+/// are formatted from their exact decimal expansion, rounded half to even,
+/// like [`GRational`](crate::GRational): in the notations exact numbers
+/// support, with the `#`, `+`, width and alignment flags.
+///
+/// ```
+/// use gpoint::{GFixed, GPoint};
+///
+/// // 1.5 in Q16.16
+/// let fixed = GFixed::from_bits(0x0001_8000, 16)?;
+/// assert!(format!("{}", GPoint(fixed)) == "1.5");
+/// assert!(fixed.is_exact());
+/// # Ok::<(), gpoint::GError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GFixed {
+    bits: i128,
+    frac_bits: u32,
+}
+
+impl GFixed {
+    /// The fixed-point number `bits / 2^frac_bits`, or
+    /// [`GError::FractionalBits`] beyond 1000 fractional bits.
+    pub fn from_bits(bits: impl Into<i128>, frac_bits: u32) -> Result<Self, GError> {
+        if frac_bits > 1000 {
+            return Err(GError::FractionalBits);
+        }
+        Ok(GFixed {
+            bits: bits.into(),
+            frac_bits,
+        })
+    }
+
+    /// Whether the number converts exactly to an `f64`, i.e. its bits don't
+    /// have more than 53 significant ones.
+    pub fn is_exact(self) -> bool {
+        let magnitude = self.bits.unsigned_abs();
+        magnitude == 0
+            || 128 - magnitude.leading_zeros() - magnitude.trailing_zeros() <= f64::MANTISSA_DIGITS
+    }
+
+    /// Converts to an `f64` with a single rounding, to nearest.
+    pub fn to_f64(self) -> f64 {
+        // i128 conversions round to nearest, and scaling by a power of two
+        // is exact in the range of finite numbers
+        self.bits as f64 * f64::powi(2., -(self.frac_bits as i32))
+    }
+
+    /// The decimal digits of `|bits| × 5^frac_bits`, i.e. of the number
+    /// times `10^frac_bits`, none for zero.
+    fn digits(self) -> Vec<u8> {
+        let magnitude = self.bits.unsigned_abs();
+        // little-endian limbs of a big integer
+        let mut limbs: Vec<u32> = (0..4).map(|i| (magnitude >> (32 * i)) as u32).collect();
+        let mut fives = self.frac_bits;
+        while fives > 0 {
+            // 5^13 is the largest power of 5 in a limb
+            let power = fives.min(13);
+            let mut carry = 0;
+            for limb in limbs.iter_mut() {
+                let product = u64::from(*limb) * u64::from(5u32.pow(power)) + carry;
+                *limb = product as u32;
+                carry = product >> 32;
+            }
+            if carry > 0 {
+                limbs.push(carry as u32);
+            }
+            fives -= power;
+        }
+        // 9 decimal digits at a time, the last ones first
+        let mut chunks = Vec::new();
+        while limbs.iter().any(|&limb| limb != 0) {
+            let mut remainder = 0;
+            for limb in limbs.iter_mut().rev() {
+                let dividend = remainder << 32 | u64::from(*limb);
+                *limb = (dividend / 1_000_000_000) as u32;
+                remainder = dividend % 1_000_000_000;
+            }
+            chunks.push(remainder);
+        }
+        let mut digits = String::new();
+        if let Some((first, rest)) = chunks.split_last() {
+            digits = first.to_string();
+            for chunk in rest.iter().rev() {
+                let _ = write!(digits, "{:09}", chunk);
+            }
+        }
+        digits.into_bytes()
+    }
+}
+
+/// Writes `value` scaled by `10^scale` as `printf()` would with `flags`,
+/// without padding, into `out`.
+fn write_g(
+    out: &mut ByteWriter<'_>,
+    value: GFixed,
+    flags: &Flags,
+    scale: i32,
+) -> Result<(), GError> {
+    let mut digits = value.digits();
+    let mut exponent = match digits.len() {
+        0 => 0,
+        len => len as i64 - 1 - i64::from(value.frac_bits) + i64::from(scale),
+    };
+    let count = digit_count(flags, exponent);
+    round(&mut digits, &mut exponent, count, false, Rounding::Nearest);
+    write_digits(out, value.bits < 0, &digits, exponent, flags)
+}
+
+fn fmt_fixed(f: &mut fmt::Formatter<'_>, value: GFixed, options: &Options) -> fmt::Result {
+    if value.is_exact() {
+        return fmt_g(f, value.to_f64(), options);
+    }
+    fmt_with(f, value.bits < 0, options, |out, flags, scale| {
+        write_g(out, value, flags, scale)
+    })
+}
+
+impl fmt::Display for GPoint<GFixed> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_fixed(f, self.0, &GConfig::get())
+    }
+}
+
+impl fmt::Display for GPointWith<GFixed> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_fixed(f, self.value, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GPoint, Options};
+    #[test]
+    fn fixed() {
+        for (bits, frac_bits, res, exact) in [
+            (0x0001_8000, 16, "1.5", true),
+            (-0x40, 8, "-0.25", true),
+            (1, 32, "2.32831e-10", true),
+            (0, 16, "0", true),
+            (i64::MAX.into(), 0, "9.22337e+18", false),
+            (-1 - (1 << 60), 61, "-0.5", false),
+            (i64::MIN.into(), 63, "-1", true),
+            ((1 << 53) + 1, 0, "9.0072e+15", false),
+            ((1 << 60) + (1 << 8), 4, "7.20576e+16", true),
+            (i128::MAX, 127, "1", false),
+        ] {
+            let fixed = GFixed::from_bits(bits, frac_bits).unwrap();
+            assert_eq!(format!("{}", GPoint(fixed)), res);
+            assert_eq!(fixed.is_exact(), exact, "{:?}", fixed);
+            if exact {
+                assert_eq!(fixed.to_f64(), bits as f64 / 2f64.powi(frac_bits as i32));
+            }
+        }
+        let options = Options::new().precision(17);
+        let fixed = GFixed::from_bits(0x0001_999a, 16).unwrap();
+        assert_eq!(
+            format!("{}", GPoint(fixed).with(options)),
+            "1.600006103515625"
+        );
+        assert_eq!(
+            format!("{}", GPoint(GFixed::from_bits(1, 1000).unwrap())),
+            "9.33264e-302"
+        );
+        assert_eq!(GFixed::from_bits(1, 1001), Err(GError::FractionalBits));
+        // the exact digits of the others, not the ones of the nearest `f64`
+        for (bits, frac_bits, precision, res) in [
+            (i64::MAX.into(), 0, 19, "9223372036854775807"),
+            (i64::MAX.into(), 0, 3, "9.22e+18"),
+            ((1 << 53) + 1, 0, 17, "9007199254740993"),
+            (
+                i128::MAX,
+                127,
+                40,
+                "0.9999999999999999999999999999999999999941",
+            ),
+            (-(1 << 60) - 1, 60, 20, "-1.0000000000000000009"),
+            (3 << 60 | 1, 1000, 4, "3.228e-283"),
+        ] {
+            let fixed = GPoint(GFixed::from_bits(bits, frac_bits).unwrap());
+            assert_eq!(format!("{:.*}", precision, fixed), res);
+        }
+        let fixed = GPoint(GFixed::from_bits(i64::MAX, 0).unwrap());
+        assert_eq!(format!("{:+#25.3}|", fixed), "                +9.22e+18|");
+        let gcode = Options::new().notation(crate::Notation::GCode { trim: false });
+        assert_eq!(
+            format!("{:.2}", fixed.with(gcode)),
+            "9223372036854775807.00"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! G-code lines
+
+use crate::{formatted, Flags, GConfig, GError, Notation, Options};
+use std::fmt;
+
+/// Writes G-code lines, e.g. `G1 X1.500 Y-2.000 F1200.000`, with every
+/// parameter formatted as `%.3f` by default, never with an exponent, in the
+/// [`Notation::GCode`] notation.
+///
+/// ```
+/// use gpoint::GCodeWriter;
+///
+/// let gcode = GCodeWriter::new().trim(true);
+/// let mut out = String::new();
+/// gcode.line(&mut out, "G1", &[('X', 1.5), ('Y', -2.), ('Z', 1e-7)])?;
+/// assert!(out == "G1 X1.5 Y-2 Z0\n");
+/// # Ok::<(), gpoint::GError>(())
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct GCodeWriter {
+    flags: Flags,
+    options: Options,
+    trim: bool,
+}
+
+impl GCodeWriter {
+    /// Writes parameters with 3 decimals, and the default [`Options`].
+    pub fn new() -> Self {
+        GCodeWriter {
+            flags: Flags {
+                precision: Some(3),
+                ..Flags::default()
+            },
+            options: GConfig::get(),
+            trim: false,
+        }
+    }
+
+    /// Sets the number of decimals of parameters.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.flags.precision = Some(precision);
+        self
+    }
+
+    /// Removes the trailing zeros of parameters, e.g. `X1.5` instead of
+    /// `X1.500`.
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Sets the [`Options`] parameters are formatted with. Their notation is
+    /// always [`Notation::GCode`].
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes a line made of a command, e.g. `G1`, its parameters given as
+    /// letters and values, and a newline. Non-finite values are refused, no
+    /// controller reading them.
+    pub fn line(
+        &self,
+        out: &mut impl fmt::Write,
+        command: &str,
+        words: &[(char, f64)],
+    ) -> Result<(), GError> {
+        if words.iter().any(|(_, value)| !value.is_finite()) {
+            return Err(GError::NonFinite);
+        }
+        let options = self.options.notation(Notation::GCode { trim: self.trim });
+        write(out, command)?;
+        for &(letter, value) in words {
+            if !command.is_empty() {
+                write(out, " ")?;
+            }
+            out.write_char(letter).map_err(|_| GError::Write)?;
+            formatted(value, &self.flags, &options, |numstr| out.write_str(numstr))?
+                .map_err(|_| GError::Write)?;
+        }
+        write(out, "\n")
+    }
+}
+
+impl Default for GCodeWriter {
+    fn default() -> Self {
+        GCodeWriter::new()
+    }
+}
+
+fn write(out: &mut impl fmt::Write, text: &str) -> Result<(), GError> {
+    out.write_str(text).map_err(|_| GError::Write)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn lines() {
+        let gcode = GCodeWriter::new();
+        let mut out = String::new();
+        gcode.line(&mut out, "G0", &[]).unwrap();
+        gcode
+            .line(&mut out, "G1", &[('X', 1.5), ('Y', -0.0004), ('F', 1200.)])
+            .unwrap();
+        gcode
+            .precision(1)
+            .line(&mut out, "G2", &[('X', 1e6), ('I', 0.25)])
+            .unwrap();
+        assert_eq!(out, "G0\nG1 X1.500 Y-0.000 F1200.000\nG2 X1000000.0 I0.2\n");
+        assert_eq!(
+            gcode.line(&mut out, "G1", &[('X', f64::NAN)]),
+            Err(GError::NonFinite)
+        );
+        let mut out = String::new();
+        gcode
+            .trim(true)
+            .with(Options::new())
+            .line(&mut out, "", &[('X', 2.), ('Y', 0.125)])
+            .unwrap();
+        assert_eq!(out, "X2Y0.125\n");
+    }
+}
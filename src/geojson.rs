@@ -0,0 +1,163 @@
+//! GeoJSON coordinates
+
+use crate::{formatted, Flags, GConfig, GError, NonFinite, Options};
+use std::fmt;
+
+/// Writes GeoJSON geometries, e.g. `{"type":"Point","coordinates":[1.5,2.25]}`,
+/// with every coordinate formatted with `%g` and a fixed number of
+/// significant digits, like C-based tile pipelines do. Non-finite
+/// coordinates, which JSON lacks, are written as `null`.
+///
+/// ```
+/// use gpoint::GeoJsonWriter;
+///
+/// let geojson = GeoJsonWriter::new().precision(4);
+/// let mut out = String::new();
+/// geojson.point(&mut out, &[1.23456, 2.25]).unwrap();
+/// assert!(out == r#"{"type":"Point","coordinates":[1.235,2.25]}"#);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct GeoJsonWriter {
+    flags: Flags,
+    options: Options,
+}
+
+impl GeoJsonWriter {
+    /// Writes coordinates with the default [`Options`], and their precision
+    /// or else `%g`'s default one.
+    pub fn new() -> Self {
+        GeoJsonWriter {
+            flags: Flags::default(),
+            options: GConfig::get(),
+        }
+    }
+
+    /// Sets the number of significant digits of coordinates.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.flags.precision = Some(precision);
+        self
+    }
+
+    /// Sets the [`Options`] coordinates are formatted with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes a `Point` geometry.
+    pub fn point(&self, out: &mut impl fmt::Write, coord: &[f64]) -> Result<(), GError> {
+        write(out, r#"{"type":"Point","coordinates":"#)?;
+        self.position(out, coord)?;
+        write(out, "}")
+    }
+
+    /// Writes a `LineString` geometry.
+    pub fn linestring<C: AsRef<[f64]>>(
+        &self,
+        out: &mut impl fmt::Write,
+        coords: &[C],
+    ) -> Result<(), GError> {
+        write(out, r#"{"type":"LineString","coordinates":"#)?;
+        self.positions(out, coords)?;
+        write(out, "}")
+    }
+
+    /// Writes a `Polygon` geometry from its rings, the exterior one first.
+    pub fn polygon<R: AsRef<[C]>, C: AsRef<[f64]>>(
+        &self,
+        out: &mut impl fmt::Write,
+        rings: &[R],
+    ) -> Result<(), GError> {
+        write(out, r#"{"type":"Polygon","coordinates":["#)?;
+        for (i, ring) in rings.iter().enumerate() {
+            if i > 0 {
+                write(out, ",")?;
+            }
+            self.positions(out, ring.as_ref())?;
+        }
+        write(out, "]}")
+    }
+
+    /// Writes an array of positions, e.g. `[[0,0],[1.5,2]]`, to build other
+    /// geometries or the `bbox` member.
+    pub fn positions<C: AsRef<[f64]>>(
+        &self,
+        out: &mut impl fmt::Write,
+        coords: &[C],
+    ) -> Result<(), GError> {
+        write(out, "[")?;
+        for (i, coord) in coords.iter().enumerate() {
+            if i > 0 {
+                write(out, ",")?;
+            }
+            self.position(out, coord.as_ref())?;
+        }
+        write(out, "]")
+    }
+
+    /// Writes a position, e.g. `[1.5,2]`.
+    pub fn position(&self, out: &mut impl fmt::Write, coord: &[f64]) -> Result<(), GError> {
+        let options = self.options.non_finite(NonFinite::Null);
+        let flags = Flags {
+            precision: self.flags.precision.or(options.precision),
+            ..self.flags
+        };
+        write(out, "[")?;
+        for (i, &value) in coord.iter().enumerate() {
+            if i > 0 {
+                write(out, ",")?;
+            }
+            formatted(value, &flags, &options, |numstr| out.write_str(numstr))?
+                .map_err(|_| GError::Write)?;
+        }
+        write(out, "]")
+    }
+}
+
+impl Default for GeoJsonWriter {
+    fn default() -> Self {
+        GeoJsonWriter::new()
+    }
+}
+
+fn write(out: &mut impl fmt::Write, text: &str) -> Result<(), GError> {
+    out.write_str(text).map_err(|_| GError::Write)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn geojson() {
+        let geojson = GeoJsonWriter::new().precision(7);
+        let mut out = String::new();
+        geojson
+            .linestring(
+                &mut out,
+                &[
+                    [2.3522219, 48.856614, 35.],
+                    [-0.1277583, 51.5073509, f64::NAN],
+                ],
+            )
+            .unwrap();
+        out.push('\n');
+        let ring = [[0., 0.], [1e-10, 0.], [0., 0.]];
+        geojson.polygon(&mut out, &[ring]).unwrap();
+        assert_eq!(
+            out,
+            "{\"type\":\"LineString\",\"coordinates\":[[2.352222,48.85661,35],[-0.1277583,51.50735,null]]}\n\
+             {\"type\":\"Polygon\",\"coordinates\":[[[0,0],[1e-10,0],[0,0]]]}"
+        );
+        // the precision of the options, unless one is set
+        let _guard = crate::ConfigGuard::new(Options::new().precision(3));
+        let mut out = String::new();
+        GeoJsonWriter::new()
+            .position(&mut out, &[1. / 3., 2.])
+            .unwrap();
+        GeoJsonWriter::new()
+            .precision(5)
+            .position(&mut out, &[1. / 3., 2.])
+            .unwrap();
+        assert_eq!(out, "[0.333,2][0.33333,2]");
+    }
+}
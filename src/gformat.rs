@@ -0,0 +1,94 @@
+//! Opting user types into `%g` rendering
+
+use crate::{fmt_g, GConfig, Options};
+use std::fmt;
+
+/// Types rendered as a number with `%g`, e.g. domain types such as
+/// temperatures or prices, displayed with [`display_g()`].
+///
+/// ```
+/// use gpoint::{display_g, GFormat};
+///
+/// struct Price {
+///     cents: i64,
+/// }
+///
+/// impl GFormat for Price {
+///     fn g_value(&self) -> f64 {
+///         self.cents as f64 / 100.
+///     }
+/// }
+///
+/// let price = Price { cents: 1999 };
+/// assert!(format!("{:8}", display_g(&price)) == "   19.99");
+/// ```
+pub trait GFormat {
+    /// The number rendered.
+    fn g_value(&self) -> f64;
+
+    /// The options it is rendered with, the process-wide ones of [`GConfig`]
+    /// unless overridden.
+    fn g_options(&self) -> Options {
+        GConfig::get()
+    }
+}
+
+impl GFormat for f64 {
+    fn g_value(&self) -> f64 {
+        *self
+    }
+}
+
+impl GFormat for f32 {
+    fn g_value(&self) -> f64 {
+        f64::from(*self)
+    }
+}
+
+impl<T: GFormat + ?Sized> GFormat for &T {
+    fn g_value(&self) -> f64 {
+        (**self).g_value()
+    }
+
+    fn g_options(&self) -> Options {
+        (**self).g_options()
+    }
+}
+
+/// Displays `value` with `%g`, following the flags, width and precision of
+/// the format string.
+pub fn display_g<T: GFormat + ?Sized>(value: &T) -> GDisplay<'_, T> {
+    GDisplay(value)
+}
+
+/// A [`GFormat`] value being displayed, as returned by [`display_g()`].
+#[derive(Debug, Clone, Copy)]
+pub struct GDisplay<'a, T: ?Sized>(&'a T);
+
+impl<T: GFormat + ?Sized> fmt::Display for GDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_g(f, self.0.g_value(), &self.0.g_options())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn gformat() {
+        struct Kelvin(f64);
+        impl GFormat for Kelvin {
+            fn g_value(&self) -> f64 {
+                self.0
+            }
+            fn g_options(&self) -> Options {
+                Options::new().precision(3).min_fraction_digits(1)
+            }
+        }
+        for (value, res) in [(273.15, "273.0"), (1.5, "1.5"), (1e-10, "1.0e-10")] {
+            assert_eq!(format!("{}", display_g(&Kelvin(value))), res);
+        }
+        assert_eq!(format!("{:+.2}", display_g(&1.2345)), "+1.2");
+        assert_eq!(format!("{}", display_g(&&0.5f32)), "0.5");
+    }
+}
@@ -0,0 +1,132 @@
+//! gnuplot scripts
+
+use crate::{formatted, ExponentStyle, Flags, GConfig, GError, Options};
+use std::fmt;
+
+/// Writes gnuplot `set xtics` commands with explicit labels, e.g.
+/// `set xtics ("0" 0, "1.5{\\times}10^{-5}" 1.5e-05)`, labels formatted
+/// with `%g` at the precision data was written with, in enhanced text
+/// markup, and positions with all their digits.
+///
+/// ```
+/// use gpoint::GnuplotTics;
+///
+/// let mut script = String::new();
+/// GnuplotTics::new().precision(2).tics(&mut script, "x", &[0., 1.234e-5, 0.5])?;
+/// assert!(script == r#"set xtics ("0" 0, "1.2{\\times}10^{-5}" 1.234e-05, "0.5" 0.5)
+/// "#);
+/// # Ok::<(), gpoint::GError>(())
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct GnuplotTics {
+    flags: Flags,
+    options: Options,
+}
+
+impl GnuplotTics {
+    /// Writes labels with the default [`Options`], and their precision or
+    /// else `%g`'s default one.
+    pub fn new() -> Self {
+        GnuplotTics {
+            flags: Flags::default(),
+            options: GConfig::get(),
+        }
+    }
+
+    /// Sets the number of significant digits of labels.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.flags.precision = Some(precision);
+        self
+    }
+
+    /// Sets the [`Options`] labels are formatted with. Their exponents are
+    /// always written as [`ExponentStyle::Gnuplot`].
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes a `set <axis>tics` command, e.g. for the `x`, `y` or `cb` axis,
+    /// with a label for each value. Non-finite values are refused, gnuplot
+    /// having no place for them.
+    pub fn tics(
+        &self,
+        out: &mut impl fmt::Write,
+        axis: &str,
+        values: &[f64],
+    ) -> Result<(), GError> {
+        if values.iter().any(|value| !value.is_finite()) {
+            return Err(GError::NonFinite);
+        }
+        let label = self.options.exponent(ExponentStyle::Gnuplot);
+        let flags = Flags {
+            precision: self.flags.precision.or(label.precision),
+            ..self.flags
+        };
+        let position = Flags {
+            precision: Some(17),
+            ..Flags::default()
+        };
+        write(out, "set ")?;
+        write(out, axis)?;
+        write(out, "tics (")?;
+        for (i, &value) in values.iter().enumerate() {
+            if i > 0 {
+                write(out, ", ")?;
+            }
+            write(out, "\"")?;
+            // backslashes are escapes in double-quoted strings
+            formatted(value, &flags, &label, |numstr| {
+                write(out, &numstr.replace('\\', "\\\\"))
+            })??;
+            write(out, "\" ")?;
+            formatted(value, &position, &Options::new(), |numstr| {
+                write(out, numstr)
+            })??;
+        }
+        write(out, ")\n")
+    }
+}
+
+impl Default for GnuplotTics {
+    fn default() -> Self {
+        GnuplotTics::new()
+    }
+}
+
+fn write(out: &mut impl fmt::Write, text: &str) -> Result<(), GError> {
+    out.write_str(text).map_err(|_| GError::Write)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn tics() {
+        let mut script = String::new();
+        let tics = GnuplotTics::new().with(Options::new().grouping(','));
+        tics.tics(&mut script, "y", &[]).unwrap();
+        tics.tics(&mut script, "cb", &[-1e10, 0.1, 12345.]).unwrap();
+        assert_eq!(
+            script,
+            "set ytics ()\nset cbtics (\"-1{\\\\times}10^{10}\" -10000000000, \
+             \"0.1\" 0.10000000000000001, \"12,345\" 12345)\n"
+        );
+        assert_eq!(
+            tics.tics(&mut script, "x", &[f64::NAN]),
+            Err(GError::NonFinite)
+        );
+        // the precision of the options, unless one is set
+        let mut script = String::new();
+        let tics = GnuplotTics::new().with(Options::new().precision(3));
+        tics.tics(&mut script, "x", &[1. / 3.]).unwrap();
+        tics.precision(5)
+            .tics(&mut script, "x", &[1. / 3.])
+            .unwrap();
+        assert_eq!(
+            script,
+            "set xtics (\"0.333\" 0.33333333333333331)\n\
+             set xtics (\"0.33333\" 0.33333333333333331)\n"
+        );
+    }
+}
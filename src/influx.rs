@@ -0,0 +1,152 @@
+//! InfluxDB line protocol fields
+
+use crate::{formatted, Flags, GConfig, GError, Options};
+use std::fmt;
+
+/// Writes the fields of InfluxDB line protocol points, e.g.
+/// `usage=12.5,load=0.25`, with every value formatted with the same `%g`
+/// precision like C collectors do.
+///
+/// The line protocol has no spelling for non-finite values: the fields
+/// holding them are left out, or refused.
+///
+/// ```
+/// use gpoint::InfluxWriter;
+///
+/// let influx = InfluxWriter::new().precision(10);
+/// let mut line = String::from("cpu,host=a ");
+/// influx.fields(&mut line, &[("usage", 12.5), ("idle time", f64::NAN), ("load", 1. / 3.)])?;
+/// assert!(line == "cpu,host=a usage=12.5,load=0.3333333333");
+/// # Ok::<(), gpoint::GError>(())
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct InfluxWriter {
+    flags: Flags,
+    options: Options,
+    skip_non_finite: bool,
+}
+
+impl InfluxWriter {
+    /// Writes values with the default [`Options`], and their precision or
+    /// else `%g`'s default one, leaving non-finite fields out.
+    pub fn new() -> Self {
+        InfluxWriter {
+            flags: Flags::default(),
+            options: GConfig::get(),
+            skip_non_finite: true,
+        }
+    }
+
+    /// Sets the number of significant digits of values.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.flags.precision = Some(precision);
+        self
+    }
+
+    /// Sets the [`Options`] values are formatted with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Whether to leave fields with non-finite values out, the default, or
+    /// to fail with [`GError::NonFinite`].
+    pub fn skip_non_finite(mut self, skip: bool) -> Self {
+        self.skip_non_finite = skip;
+        self
+    }
+
+    /// Writes comma-separated `key=value` fields, escaping the keys. Fails
+    /// with [`GError::NonFinite`] if no field is left, points needing one.
+    pub fn fields(&self, out: &mut impl fmt::Write, fields: &[(&str, f64)]) -> Result<(), GError> {
+        if !self.skip_non_finite && fields.iter().any(|(_, value)| !value.is_finite()) {
+            return Err(GError::NonFinite);
+        }
+        let mut written = false;
+        for &(key, value) in fields.iter().filter(|(_, value)| value.is_finite()) {
+            if written {
+                write(out, ",")?;
+            }
+            for c in key.chars() {
+                if matches!(c, ',' | '=' | ' ') {
+                    write(out, "\\")?;
+                }
+                out.write_char(c).map_err(|_| GError::Write)?;
+            }
+            write(out, "=")?;
+            self.value(out, value)?;
+            written = true;
+        }
+        if written {
+            Ok(())
+        } else {
+            Err(GError::NonFinite)
+        }
+    }
+
+    /// Writes a single field value, failing with [`GError::NonFinite`] for
+    /// non-finite ones.
+    pub fn value(&self, out: &mut impl fmt::Write, value: f64) -> Result<(), GError> {
+        if !value.is_finite() {
+            return Err(GError::NonFinite);
+        }
+        let flags = Flags {
+            precision: self.flags.precision.or(self.options.precision),
+            ..self.flags
+        };
+        formatted(value, &flags, &self.options, |numstr| out.write_str(numstr))?
+            .map_err(|_| GError::Write)
+    }
+}
+
+impl Default for InfluxWriter {
+    fn default() -> Self {
+        InfluxWriter::new()
+    }
+}
+
+fn write(out: &mut impl fmt::Write, text: &str) -> Result<(), GError> {
+    out.write_str(text).map_err(|_| GError::Write)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn fields() {
+        let influx = InfluxWriter::new();
+        let mut out = String::new();
+        influx
+            .fields(
+                &mut out,
+                &[("a,b=c", -1e-20), ("inf", f64::INFINITY), ("d", 1e6)],
+            )
+            .unwrap();
+        assert_eq!(out, "a\\,b\\=c=-1e-20,d=1e+06");
+        assert_eq!(
+            influx.fields(&mut out, &[("nan", f64::NAN)]),
+            Err(GError::NonFinite)
+        );
+        assert_eq!(influx.fields(&mut out, &[]), Err(GError::NonFinite));
+        let strict = influx.skip_non_finite(false).precision(3);
+        let mut out = String::new();
+        assert_eq!(
+            strict.fields(&mut out, &[("a", 1.), ("b", f64::NAN)]),
+            Err(GError::NonFinite)
+        );
+        strict.fields(&mut out, &[("a", 2. / 3.)]).unwrap();
+        assert_eq!(out, "a=0.667");
+        // the precision of the options, unless one is set
+        let _guard = crate::ConfigGuard::new(Options::new().precision(3));
+        let mut out = String::new();
+        InfluxWriter::new()
+            .fields(&mut out, &[("a", 1. / 3.)])
+            .unwrap();
+        out.push(' ');
+        InfluxWriter::new()
+            .precision(5)
+            .fields(&mut out, &[("a", 1. / 3.)])
+            .unwrap();
+        assert_eq!(out, "a=0.333 a=0.33333");
+    }
+}
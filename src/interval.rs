@@ -0,0 +1,196 @@
+//! Intervals of interval arithmetic, rounded outward
+
+use crate::buffer::ByteWriter;
+use crate::decimal::{digit_count, formatted_with, round, write_digits, Rounding};
+use crate::{formatted, Flags, GConfig, GError};
+use std::fmt;
+
+/// An interval of interval arithmetic, e.g. the `inf()` and `sup()` of an
+/// `inari::Interval`, displayed as `[a, b]` with its lower end rounded down
+/// and its upper end rounded up at the precision, so that the printed
+/// interval always encloses the real one, which rounding both ends to
+/// nearest with `%g` doesn't guarantee.
+///
+/// Both ends are rounded from their exact decimal expansion, whatever the
+/// backend or the rounding mode. Intervals whose lower end isn't below its
+/// upper end, e.g. with NaNs, are displayed as `[empty]`.
+///
+/// ```
+/// use gpoint::GInterval;
+///
+/// assert!(format!("{:.3}", GInterval(1. / 3., 2. / 3.)) == "[0.333, 0.667]");
+/// assert!(format!("{:.3}", GInterval(-2. / 3., 2. / 3.)) == "[-0.667, 0.667]");
+/// assert!(format!("{:.3}", GInterval(0.1, 0.1)) == "[0.1, 0.101]");
+/// assert!(format!("{}", GInterval(f64::NEG_INFINITY, 1e10)) == "[-inf, 1e+10]");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GInterval(
+    /// The lower end
+    pub f64,
+    /// The upper end
+    pub f64,
+);
+
+/// Writes the finite `value` scaled by `10^scale` as `printf()` would with
+/// `flags`, without padding, into `out`, rounded toward positive infinity if
+/// `upward`, else toward negative infinity.
+fn write_directed(
+    out: &mut ByteWriter<'_>,
+    value: f64,
+    flags: &Flags,
+    scale: i32,
+    upward: bool,
+) -> Result<(), GError> {
+    // every digit of the exact expansion, which has at most 767 significant
+    // ones
+    let text = format!("{:.800e}", value.abs());
+    let (mantissa, exponent) = text.split_at(text.find('e').ok_or(GError::Backend)?);
+    let mut exponent: i64 = exponent[1..].parse().map_err(|_| GError::Backend)?;
+    let mut digits: Vec<u8> = mantissa.bytes().filter(u8::is_ascii_digit).collect();
+    if value == 0. {
+        digits.clear();
+        exponent = 0;
+    } else {
+        exponent += i64::from(scale);
+    }
+    // away from zero for the upper end of positive numbers, and for the
+    // lower end of negative ones
+    let rounding = if upward != value.is_sign_negative() {
+        Rounding::Up
+    } else {
+        Rounding::Down
+    };
+    let count = digit_count(flags, exponent);
+    round(&mut digits, &mut exponent, count, false, rounding);
+    write_digits(out, value.is_sign_negative(), &digits, exponent, flags)
+}
+
+impl fmt::Display for GInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let GInterval(low, high) = *self;
+        if low.is_nan() || high.is_nan() || low > high {
+            return f.write_str("[empty]");
+        }
+        let options = GConfig::get();
+        let mut flags = Flags::from_formatter(f);
+        flags.precision = flags.precision.or(options.precision);
+        let format = |value: f64, upward| {
+            if value.is_finite() {
+                formatted_with(
+                    &flags,
+                    value.is_sign_negative(),
+                    &options,
+                    |out, flags, scale| write_directed(out, value, flags, scale, upward),
+                    str::to_owned,
+                )
+            } else {
+                formatted(value, &flags, &options, str::to_owned)
+            }
+        };
+        write!(f, "[{}, {}]", format(low, false)?, format(high, true)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::tests::formatted_notations;
+
+    /// Reads back a number formatted in any of the notations compared, up to
+    /// the scale of percents.
+    fn value(formatted: &str) -> f64 {
+        let number = formatted.trim_end_matches(['%', ' ']);
+        let prefix =
+            number.trim_start_matches(|c: char| !c.is_alphabetic() || c == 'e' || c == 'E');
+        let exponent = match prefix {
+            "k" => "e3",
+            "M" => "e6",
+            "G" => "e9",
+            "m" => "e-3",
+            "\u{b5}" => "e-6",
+            "n" => "e-9",
+            _ => "",
+        };
+        let number = format!("{}{}", &number[..number.len() - prefix.len()], exponent);
+        number
+            .parse()
+            .unwrap_or_else(|_| panic!("unreadable {}", formatted))
+    }
+
+    #[test]
+    fn interval() {
+        for (low, high, precision, res) in [
+            (0., 1., None, "[0, 1]"),
+            (1. / 3., 2. / 3., None, "[0.333333, 0.666667]"),
+            (1. / 3., 1. / 3., None, "[0.333333, 0.333334]"),
+            (-1. / 3., -1. / 3., None, "[-0.333334, -0.333333]"),
+            (0.1, 0.1, Some(17), "[0.1, 0.10000000000000001]"),
+            (
+                0.1,
+                0.1,
+                Some(20),
+                "[0.10000000000000000555, 0.10000000000000000556]",
+            ),
+            (0.5, 1.5, Some(1), "[0.5, 2]"),
+            (9.99, 9.999, Some(3), "[9.99, 10]"),
+            (999_999.5, 999_999.5, None, "[999999, 1e+06]"),
+            (-999_999.5, -999_999.5, None, "[-1e+06, -999999]"),
+            (-0., 0., None, "[-0, 0]"),
+            (5e-324, 5e-324, Some(1), "[4e-324, 5e-324]"),
+            (f64::MAX, f64::INFINITY, Some(3), "[1.79e+308, inf]"),
+            (f64::NEG_INFINITY, -f64::MAX, Some(3), "[-inf, -1.79e+308]"),
+            (2., 1., None, "[empty]"),
+            (f64::NAN, 1., None, "[empty]"),
+        ] {
+            let interval = GInterval(low, high);
+            let formatted = match precision {
+                Some(p) => format!("{:.*}", p, interval),
+                None => format!("{}", interval),
+            };
+            assert_eq!(formatted, res, "{:?}", interval);
+        }
+        assert_eq!(
+            format!("{:+#8.2}", GInterval(1.01, 1.01)),
+            "[    +1.0,     +1.1]"
+        );
+        // the ends enclose the float rounded to nearest in every notation,
+        // and are the same for the numbers 3 digits hold exactly
+        for num in [0.5, -0.125, 1250., 1e10, 3e-7, 1. / 3.] {
+            let interval = GInterval(num, num);
+            for (notation, precision, interval, float) in formatted_notations(interval, num) {
+                let (low, high) = interval[1..interval.len() - 1].split_once(", ").unwrap();
+                let (low, high, nearest) = (value(low), value(high), value(&float));
+                assert!(
+                    low <= nearest && nearest <= high,
+                    "{:?} {:?}: {} {}",
+                    notation,
+                    precision,
+                    interval,
+                    float
+                );
+                if num != 3e-7 && num != 1. / 3. && precision.is_none_or(|p| p >= 3) {
+                    assert_eq!(low, high, "{:?} {:?}: {}", notation, precision, interval);
+                }
+            }
+        }
+        let _guard = crate::ConfigGuard::new(crate::Options::new().notation(crate::Notation::Si));
+        assert_eq!(format!("{:.2}", GInterval(1250., 1260.)), "[1.2k, 1.3k]");
+        drop(_guard);
+        // the ends enclose the ones rounded to nearest
+        for &num in crate::testing::corpus()
+            .iter()
+            .filter(|num| num.is_finite())
+        {
+            for precision in [1, 3, 6, 17] {
+                let nearest: f64 = format!("{:.*}", precision, crate::GPoint(num))
+                    .parse()
+                    .unwrap();
+                let formatted = format!("{:.*}", precision, GInterval(num, num));
+                let (low, high) = formatted[1..formatted.len() - 1].split_once(", ").unwrap();
+                let (low, high): (f64, f64) = (low.parse().unwrap(), high.parse().unwrap());
+                assert!(low <= num && num <= high, "{}: {}", num, formatted);
+                assert!(low <= nearest && nearest <= high, "{}: {}", num, formatted);
+            }
+        }
+    }
+}
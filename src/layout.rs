@@ -0,0 +1,170 @@
+//! How `%g` lays out numbers, without formatting them
+
+use crate::buffer::ByteWriter;
+use crate::significant_digits;
+use std::fmt::Write;
+
+/// How `printf("%g")` lays out a number, as returned by [`layout()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Layout {
+    /// The decimal exponent of the number once rounded, e.g. 3 for `1234.5`
+    /// since it is `1.2345e+03`.
+    pub exponent: i32,
+    /// Whether the scientific notation is used, instead of the fixed one.
+    pub scientific: bool,
+    /// How many significant digits are printed, once trailing zeros are
+    /// dropped as `%g` does without the `#` flag.
+    pub significant_digits: usize,
+    /// How many digits are printed after the decimal point, once trailing
+    /// zeros are dropped.
+    pub fraction_digits: usize,
+}
+
+/// Returns how `printf("%g")` would lay out `value` with the given precision,
+/// or `None` for non-finite values.
+///
+/// This is computed in Rust, identically on every platform, and cheaper than
+/// formatting: layout engines and adaptive-precision code can call it freely.
+///
+/// ```
+/// use gpoint::layout;
+///
+/// let thousands = layout(1200., Some(3)).unwrap();
+/// assert!(thousands.scientific && thousands.exponent == 3 && thousands.significant_digits == 2);
+/// let small = layout(0.00125, None).unwrap();
+/// assert!(!small.scientific && small.fraction_digits == 5);
+/// ```
+pub fn layout(value: f64, precision: Option<usize>) -> Option<Layout> {
+    if !value.is_finite() {
+        return None;
+    }
+    // no exact expansion has more significant digits, nor can exponents reach
+    // such precisions: larger ones lay out the same
+    let precision = significant_digits(precision).min(800);
+    let mut scientific = [0u8; 820];
+    let mut scientific = ByteWriter::new(&mut scientific);
+    write!(scientific, "{:.*e}", precision - 1, value.abs()).ok()?;
+    let text = std::str::from_utf8(scientific.as_bytes()).ok()?;
+    let (mantissa, exponent) = text.split_once('e')?;
+    let exponent: i32 = exponent.parse().ok()?;
+    let digits = mantissa.bytes().filter(|&c| c != b'.');
+    let significant = digits.clone().count() - digits.rev().take_while(|&c| c == b'0').count();
+    let significant = significant.max(1);
+    let scientific = !(exponent >= -4 && exponent < precision as i32);
+    let fraction_digits = if scientific {
+        significant - 1
+    } else {
+        (significant as i32 - 1 - exponent).max(0) as usize
+    };
+    Some(Layout {
+        exponent,
+        scientific,
+        significant_digits: significant,
+        fraction_digits,
+    })
+}
+
+/// Returns `value` rounded to the significant digits `printf("%g")` would
+/// print with the given precision, as a plain decimal number without
+/// exponent nor grouping, or `None` for non-finite values.
+///
+/// This is what localization libraries take, e.g. ICU4X's
+/// `FixedDecimal::from_str()`: they can then display numbers with the digits
+/// `%g` selects and the separators of any CLDR locale.
+///
+/// ```
+/// use gpoint::plain_decimal;
+///
+/// assert!(plain_decimal(1.23456e20, Some(3)).unwrap() == "123000000000000000000");
+/// assert!(plain_decimal(-0.000012345, None).unwrap() == "-0.000012345");
+/// assert!(plain_decimal(2. / 3., None).unwrap() == "0.666667");
+/// ```
+pub fn plain_decimal(value: f64, precision: Option<usize>) -> Option<String> {
+    let layout = layout(value, precision)?;
+    let text = format!("{:.*e}", layout.significant_digits - 1, value.abs());
+    let mantissa = text.split('e').next()?;
+    let digits: String = mantissa.chars().filter(|&c| c != '.').collect();
+    let mut plain = String::new();
+    if value.is_sign_negative() {
+        plain.push('-');
+    }
+    if layout.exponent < 0 {
+        plain.push_str("0.");
+        plain.extend(std::iter::repeat_n('0', (-layout.exponent - 1) as usize));
+        plain.push_str(&digits);
+    } else {
+        let integer = layout.exponent as usize + 1;
+        plain.push_str(&digits[..integer.min(digits.len())]);
+        plain.extend(std::iter::repeat_n(
+            '0',
+            integer.saturating_sub(digits.len()),
+        ));
+        if digits.len() > integer {
+            plain.push('.');
+            plain.push_str(&digits[integer..]);
+        }
+    }
+    Some(plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GPoint;
+
+    #[test]
+    fn plain() {
+        for (num, precision, res) in [
+            (0., None, "0"),
+            (-0., None, "-0"),
+            (1., None, "1"),
+            (999999.5, None, "1000000"),
+            (1e-10, Some(2), "0.0000000001"),
+            (1234.5, Some(2), "1200"),
+            (1234.5, Some(10), "1234.5"),
+            (0.1, Some(20), "0.10000000000000000555"),
+        ] {
+            assert_eq!(plain_decimal(num, precision).unwrap(), res, "{:e}", num);
+        }
+        assert_eq!(plain_decimal(f64::NAN, None), None);
+    }
+
+    #[test]
+    fn same_as_formatted() {
+        for num in [
+            0.,
+            -0.,
+            1.,
+            42.,
+            -1.01,
+            1e-5,
+            0.0001,
+            123456.,
+            1234567.,
+            999999.5,
+            1e100,
+            5e-324,
+            f64::MAX,
+        ] {
+            for precision in [None, Some(0), Some(1), Some(3), Some(17), Some(1000)] {
+                let formatted = match precision {
+                    Some(p) => format!("{:.*}", p, GPoint(num)),
+                    None => format!("{}", GPoint(num)),
+                };
+                let layout = layout(num, precision).unwrap();
+                let (mantissa, _) = formatted.split_once('e').unwrap_or((&formatted, ""));
+                let fraction = mantissa
+                    .split_once('.')
+                    .map_or("", |(_, fraction)| fraction);
+                let digits = mantissa.trim_start_matches('-').replace('.', "");
+                let digits = digits.trim_start_matches('0').len().max(1);
+                assert_eq!(layout.scientific, formatted.contains('e'), "{}", formatted);
+                assert_eq!(layout.fraction_digits, fraction.len(), "{}", formatted);
+                assert_eq!(layout.significant_digits, digits, "{}", formatted);
+            }
+        }
+        assert_eq!(layout(f64::NAN, None), None);
+        assert_eq!(layout(999999.5, None).unwrap().exponent, 6);
+    }
+}
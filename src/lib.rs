@@ -2,9 +2,160 @@
 
 //! Wrapper for using libc's `printf("%g")` format for your floating point output
 
-use libc::c_char;
+use std::cell::RefCell;
 use std::fmt;
-use std::io::Write;
+
+// lets `#[derive(GDisplay)]` refer to this crate in its own tests
+#[cfg(all(test, feature = "derive"))]
+extern crate self as gpoint;
+
+mod arena;
+mod backend;
+mod buffer;
+mod cached;
+mod capabilities;
+#[cfg(feature = "capi")]
+mod capi;
+mod compact;
+mod config;
+mod coords;
+mod decimal;
+mod diagnose;
+mod double_double;
+mod error;
+mod exact;
+mod excel;
+mod fields;
+mod fixed;
+mod gcode;
+mod geojson;
+mod gformat;
+mod gnuplot;
+mod influx;
+mod interval;
+mod layout;
+mod literal;
+mod locale;
+mod macros;
+mod mesh;
+#[cfg(feature = "mpfr")]
+mod mpfr;
+mod options;
+mod pure;
+#[cfg(feature = "python")]
+mod python;
+mod quantity;
+mod range;
+mod rational;
+mod reader;
+mod rewrite;
+mod rounding;
+mod scaled;
+mod secs;
+mod shortest;
+mod spec;
+mod stats;
+mod svg;
+pub mod testing;
+mod text_as;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod wkt;
+mod writer;
+
+pub use arena::GArena;
+pub use backend::{Backend, FormatBackend, MAX_PRECISION};
+pub use buffer::Buffer;
+pub use cached::GCached;
+pub use capabilities::{
+    capabilities, libc_flavor, BuildCapabilities, Capabilities, LibcFlavor, BUILD_CAPABILITIES,
+};
+#[cfg(feature = "capi")]
+pub use capi::gpoint_format_g;
+pub use compact::GString;
+pub use config::{ConfigGuard, GConfig};
+pub use coords::{GCoords, GCoordsWith};
+pub use decimal::GDecimal;
+pub use diagnose::{diagnose, Diagnosis, Difference};
+pub use double_double::GDoubleDouble;
+pub use error::GError;
+pub use fields::GFields;
+pub use fixed::GFixed;
+pub use gcode::GCodeWriter;
+pub use geojson::GeoJsonWriter;
+pub use gformat::{display_g, GDisplay, GFormat};
+pub use gnuplot::GnuplotTics;
+/// Formats a number literal at compile time, as [`Backend::Pure`] would at
+/// runtime, with `%g` or the given `%g`, `%e` or `%f` specification with
+/// optional `+` and `#` flags and precision. Expands to a `&'static str`.
+///
+/// ```
+/// use gpoint::g_str;
+///
+/// const HEADER: &str = concat!("# step=", g_str!(1e-6), " tol=", g_str!("%.2e", 0.00125));
+/// assert!(HEADER == "# step=1e-06 tol=1.25e-03");
+/// assert!(g_str!("%+.3G", -1_234_567f32) == "-1.23E+06");
+/// ```
+#[cfg(feature = "derive")]
+pub use gpoint_derive::g_str;
+/// Implements `Display` for a struct with named fields, formatting each
+/// float field with `%g` as `name=value`, separated by spaces.
+///
+/// The separator is set with `#[gpoint(separator = ", ")]` on the struct,
+/// fields accept `#[gpoint(precision = 3)]`, `#[gpoint(rename = "x")]` and
+/// `#[gpoint(skip)]`. Fields which aren't `f64` nor `f32` are displayed as
+/// they are.
+///
+/// ```
+/// use gpoint::GDisplay;
+///
+/// #[derive(GDisplay)]
+/// #[gpoint(separator = ", ")]
+/// struct Telemetry {
+///     #[gpoint(precision = 3)]
+///     speed: f64,
+///     #[gpoint(rename = "alt")]
+///     altitude: f32,
+///     #[gpoint(skip)]
+///     _raw: Vec<u8>,
+///     mode: &'static str,
+/// }
+///
+/// let telemetry = Telemetry { speed: 12.3456, altitude: 1e4, _raw: vec![], mode: "auto" };
+/// assert!(telemetry.to_string() == "speed=12.3, alt=10000, mode=auto");
+/// ```
+#[cfg(feature = "derive")]
+pub use gpoint_derive::GDisplay;
+pub use influx::InfluxWriter;
+pub use interval::GInterval;
+pub use layout::{layout, plain_decimal, Layout};
+pub use literal::{parse_c, CLiteral};
+pub use locale::NumericLocale;
+#[doc(hidden)]
+pub use macros::__private;
+pub use mesh::MeshWriter;
+#[cfg(feature = "mpfr")]
+pub use mpfr::GMpfr;
+pub use options::{
+    ExponentStyle, NegativeZero, NonFinite, Notation, OnError, Options, Shortest, ZeroPadding,
+};
+#[cfg(feature = "python")]
+pub use python::gpoint_gformat;
+pub use quantity::GQuantity;
+pub use range::GRange;
+pub use rational::GRational;
+pub use reader::GReader;
+pub use rounding::{RoundingGuard, RoundingMode};
+pub use scaled::GScaled;
+pub use secs::GSecs;
+pub use spec::{max_formatted_len, Spec};
+pub use stats::GStats;
+pub use svg::SvgPathWriter;
+pub use text_as::{GPointAs, GPointAsPrec, GTextAs};
+#[cfg(feature = "wasm")]
+pub use wasm::{gpoint_alloc, gpoint_format_js, gpoint_free};
+pub use wkt::WktWriter;
+pub use writer::GWriter;
 
 /// A wrapper around floats providing an implementation of `Display` which uses
 /// the underlying `libc`'s `printf()` with format `"%g"`, for when you need to
@@ -20,10 +171,12 @@ use std::io::Write;
 /// assert!(format!("{}",    GPoint(42f64))  == "42");
 /// assert!(format!("{:.3}", GPoint(1.2345)) == "1.23");
 /// assert!(format!("{:4}",  GPoint(42.))    == "  42");
-/// assert!(format!("{:-4}", GPoint(42.))    == "42  ");
+/// assert!(format!("{:<4}", GPoint(42.))    == "42  ");
 /// assert!(format!("{:04}", GPoint(42.))    == "0042");
 /// assert!(format!("{:+}",  GPoint(42.))    == "+42");
 /// assert!(format!("{:#4}", GPoint(42.))    == "42.0000");
+/// assert!(format!("{:*>4}", GPoint(42.))   == "**42");
+/// assert!(format!("{:^6}", GPoint(42.))    == "  42  ");
 /// ```
 #[derive(Debug, Default, Clone, Copy)]
 #[repr(transparent)]
@@ -32,61 +185,667 @@ pub struct GPoint<Float>(
     pub Float,
 );
 
-impl std::fmt::Display for GPoint<f64> {
+impl<Float> GPoint<Float> {
+    /// Attaches formatting [`Options`] to your number.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let consistent = Options::new().consistent(true);
+    /// assert!(format!("{:.3}", GPoint(1.2345).with(consistent)) == "1.23");
+    /// ```
+    pub fn with(self, options: Options) -> GPointWith<Float> {
+        GPointWith {
+            value: self.0,
+            options,
+        }
+    }
+}
+
+impl<Float: Copy + Into<f64>> GPoint<Float> {
+    /// Formats your number like `format!("{}")` would, but without panicking
+    /// when formatting fails.
+    ///
+    /// ```
+    /// use gpoint::{GError, GPoint, NonFinite, Options};
+    ///
+    /// assert!(GPoint(42.).try_to_string() == Ok("42".to_string()));
+    /// let strict = Options::new().non_finite(NonFinite::Error);
+    /// assert!(GPoint(f64::NAN).with(strict).try_to_string() == Err(GError::NonFinite));
+    /// ```
+    pub fn try_to_string(&self) -> Result<String, GError> {
+        self.with(GConfig::get()).try_to_string()
+    }
+
+    /// Formats your number as `printf()` would with the given specification,
+    /// without panicking when formatting fails.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Spec};
+    ///
+    /// let spec = Spec::parse("%08.3f").unwrap();
+    /// assert!(GPoint(-1.5f32).try_to_string_with(&spec) == Ok("-001.500".to_string()));
+    /// ```
+    pub fn try_to_string_with(&self, spec: &Spec) -> Result<String, GError> {
+        self.with(GConfig::get()).try_to_string_with(spec)
+    }
+
+    /// Formats your number like [`GPoint::try_to_string()`], guaranteeing
+    /// the output is pure ASCII, see [`GPointWith::to_ascii_string()`].
+    pub fn to_ascii_string(&self) -> Result<String, GError> {
+        self.with(GConfig::get()).to_ascii_string()
+    }
+
+    /// Appends your number to `out` like `write!(out, "{}")` would, but
+    /// without going through `fmt::Arguments`, nor panicking when formatting
+    /// fails.
+    ///
+    /// ```
+    /// use gpoint::GPoint;
+    ///
+    /// let mut json = String::from("[");
+    /// for (i, &value) in [0.1 + 0.2, 1e-10].iter().enumerate() {
+    ///     if i > 0 {
+    ///         json.push(',');
+    ///     }
+    ///     GPoint(value).format_into(&mut json).unwrap();
+    /// }
+    /// json.push(']');
+    /// assert!(json == "[0.3,1e-10]");
+    /// ```
+    pub fn format_into(&self, out: &mut String) -> Result<(), GError> {
+        self.with(GConfig::get()).format_into(out)
+    }
+
+    /// Appends your number to `out` as `printf()` would with the given
+    /// specification.
+    pub fn format_into_with(&self, spec: &Spec, out: &mut String) -> Result<(), GError> {
+        self.with(GConfig::get()).format_into_with(spec, out)
+    }
+
+    /// Writes your number into any `fmt::Write` sink like
+    /// [`GPoint::format_into()`], failing with [`GError::Write`] if the sink
+    /// does.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Spec};
+    ///
+    /// let mut out = String::new();
+    /// GPoint(1.5f32).write_to(&mut out).unwrap();
+    /// GPoint(1234.5).write_to_with(&Spec::parse("% .2e").unwrap(), &mut out).unwrap();
+    /// assert!(out == "1.5 1.23e+03");
+    /// ```
+    pub fn write_to(&self, out: &mut impl fmt::Write) -> Result<(), GError> {
+        self.with(GConfig::get()).write_to(out)
+    }
+
+    /// Writes your number into `out` as `printf()` would with the given
+    /// specification.
+    pub fn write_to_with(&self, spec: &Spec, out: &mut impl fmt::Write) -> Result<(), GError> {
+        self.with(GConfig::get()).write_to_with(spec, out)
+    }
+
+    /// Formats your number like `to_string()` would, straight into a string
+    /// of the right size instead of through `fmt::Arguments` and a growing
+    /// buffer, or returns an empty string if that fails, see
+    /// [`GPoint::try_to_string()`].
+    ///
+    /// ```
+    /// use gpoint::GPoint;
+    ///
+    /// assert!(GPoint(0.1 + 0.2).to_string_fast() == "0.3");
+    /// ```
+    pub fn to_string_fast(&self) -> String {
+        self.try_to_string().unwrap_or_default()
+    }
+
+    /// Formats your number like [`GPoint::to_string_fast()`], with the given
+    /// precision.
+    ///
+    /// ```
+    /// use gpoint::GPoint;
+    ///
+    /// assert!(GPoint(0.1 + 0.2).to_string_prec(17) == "0.30000000000000004");
+    /// ```
+    pub fn to_string_prec(&self, precision: usize) -> String {
+        self.with(GConfig::get().precision(precision))
+            .try_to_string()
+            .unwrap_or_default()
+    }
+}
+
+impl<Float: Copy + Into<f64>> GPointWith<Float> {
+    /// Formats your number like `format!("{}")` would, but without panicking
+    /// when formatting fails.
+    pub fn try_to_string(&self) -> Result<String, GError> {
+        self.try_to_string_with(&Spec::default())
+    }
+
+    /// Formats your number as `printf()` would with the given specification
+    /// and your options, without panicking when formatting fails.
+    pub fn try_to_string_with(&self, spec: &Spec) -> Result<String, GError> {
+        self.with_formatted(spec, str::to_owned)
+    }
+
+    /// Appends your number to `out` like `write!(out, "{}")` would, without
+    /// panicking when formatting fails, see [`GPoint::format_into()`].
+    pub fn format_into(&self, out: &mut String) -> Result<(), GError> {
+        self.write_to(out)
+    }
+
+    /// Appends your number to `out` as `printf()` would with the given
+    /// specification and your options.
+    pub fn format_into_with(&self, spec: &Spec, out: &mut String) -> Result<(), GError> {
+        self.write_to_with(spec, out)
+    }
+
+    /// Writes your number into any `fmt::Write` sink like
+    /// [`GPointWith::format_into()`], failing with [`GError::Write`] if the
+    /// sink does.
+    pub fn write_to(&self, out: &mut impl fmt::Write) -> Result<(), GError> {
+        self.write_to_with(&Spec::default(), out)
+    }
+
+    /// Writes your number into `out` as `printf()` would with the given
+    /// specification and your options.
+    pub fn write_to_with(&self, spec: &Spec, out: &mut impl fmt::Write) -> Result<(), GError> {
+        self.with_formatted(spec, |numstr| out.write_str(numstr))?
+            .map_err(|_| GError::Write)
+    }
+
+    /// Formats your number as `printf()` would with the given specification
+    /// and your options, and hands the output to `f`, see [`with_formatted()`].
+    pub fn with_formatted<R>(&self, spec: &Spec, f: impl FnMut(&str) -> R) -> Result<R, GError> {
+        let options = spec.apply(self.options);
+        formatted(self.value.into(), &spec.flags_with(&options), &options, f)
+    }
+
+    /// Formats your number like [`GPointWith::try_to_string()`], but fails
+    /// with [`GError::NonAscii`] unless the output is pure ASCII, as protocol
+    /// encoders need: typographic minus signs, superscripts, SI prefixes like
+    /// `µ`, locales and custom spellings may print other characters.
+    ///
+    /// ```
+    /// use gpoint::{GError, GPoint, Options};
+    ///
+    /// assert!(GPoint(-1.5).to_ascii_string() == Ok("-1.5".to_string()));
+    /// let typographic = Options::new().typographic_minus(true);
+    /// assert!(GPoint(-1.5).with(typographic).to_ascii_string() == Err(GError::NonAscii));
+    /// ```
+    pub fn to_ascii_string(&self) -> Result<String, GError> {
+        let string = self.try_to_string()?;
+        if !string.is_ascii() {
+            return Err(GError::NonAscii);
+        }
+        Ok(string)
+    }
+}
+
+/// Any type converting into `f64` can be displayed, such as `f32` or the
+/// wrappers of the `ordered_float` crate:
+/// ```
+/// use gpoint::GPoint;
+///
+/// #[derive(Clone, Copy)]
+/// struct NotNan(f64);
+///
+/// impl From<NotNan> for f64 {
+///     fn from(value: NotNan) -> f64 {
+///         value.0
+///     }
+/// }
+///
+/// assert!(format!("{:.3}", GPoint(NotNan(1.2345))) == "1.23");
+/// ```
+impl<Float: Copy + Into<f64>> std::fmt::Display for GPoint<Float> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_g(f, self.0.into(), &GConfig::get())
+    }
+}
+
+/// A [`GPoint`] with a default precision, used when the format string doesn't
+/// give one. This standardizes `"%.<N>g"` across a codebase without repeating
+/// `:.N` everywhere:
+/// ```
+/// use gpoint::GPointP;
+///
+/// assert!(format!("{}",    GPointP::<3>(1.2345))     == "1.23");
+/// assert!(format!("{:.5}", GPointP::<3>(1.2345))     == "1.2345");
+/// assert!(format!("{:6}",  GPointP::<2, f32>(1.25)) == "   1.2");
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(transparent)]
+pub struct GPointP<const PRECISION: usize, Float = f64>(
+    /// Your floating point number you want to `Display`
+    pub Float,
+);
+
+impl<const PRECISION: usize, Float> GPointP<PRECISION, Float> {
+    /// Attaches formatting [`Options`] to your number, keeping its default
+    /// precision.
+    pub fn with(self, options: Options) -> GPointWith<Float> {
+        GPoint(self.0).with(options.precision(PRECISION))
+    }
+}
+
+impl<const PRECISION: usize, Float: Copy + Into<f64>> std::fmt::Display
+    for GPointP<PRECISION, Float>
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt_g(f, self.0)
+        fmt_g(f, self.0.into(), &GConfig::get().precision(PRECISION))
     }
 }
 
-impl std::fmt::Display for GPoint<f32> {
+/// A floating point number along with the [`Options`] used to `Display` it,
+/// as returned by [`GPoint::with()`].
+#[derive(Debug, Clone, Copy)]
+pub struct GPointWith<Float> {
+    value: Float,
+    options: Options,
+}
+
+impl<Float: Copy + Into<f64>> std::fmt::Display for GPointWith<Float> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt_g(f, self.0 as f64)
+        fmt_g(f, self.value.into(), &self.options)
+    }
+}
+
+/// The `printf()` flags, width, precision and conversion of a conversion
+/// specification, along with Rust's fill character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Flags {
+    pub(crate) alternate: bool,
+    pub(crate) left: bool,
+    pub(crate) center: bool,
+    pub(crate) plus: bool,
+    pub(crate) space: bool,
+    pub(crate) zero: bool,
+    pub(crate) fill: char,
+    pub(crate) width: Option<usize>,
+    pub(crate) precision: Option<usize>,
+    pub(crate) conversion: Conversion,
+}
+
+/// A `printf()` floating point conversion.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Conversion {
+    /// `%g`
+    #[default]
+    General,
+    /// `%f`
+    Fixed,
+    /// `%e`
+    Scientific,
+}
+
+impl Conversion {
+    pub(crate) fn letter(self) -> char {
+        match self {
+            Conversion::General => 'g',
+            Conversion::Fixed => 'f',
+            Conversion::Scientific => 'e',
+        }
+    }
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Flags::new()
+    }
+}
+
+impl Flags {
+    pub(crate) const fn new() -> Self {
+        Flags {
+            alternate: false,
+            left: false,
+            center: false,
+            plus: false,
+            space: false,
+            zero: false,
+            fill: ' ',
+            width: None,
+            precision: None,
+            conversion: Conversion::General,
+        }
+    }
+}
+
+impl Flags {
+    fn from_formatter(formatter: &fmt::Formatter<'_>) -> Self {
+        Flags {
+            alternate: formatter.alternate(),
+            left: formatter.align() == Some(fmt::Alignment::Left)
+                || (cfg!(feature = "legacy-minus") && formatter.sign_minus()),
+            center: formatter.align() == Some(fmt::Alignment::Center),
+            plus: formatter.sign_plus(),
+            space: false,
+            zero: formatter.sign_aware_zero_pad(),
+            fill: formatter.fill(),
+            width: formatter.width(),
+            precision: formatter.precision(),
+            conversion: Conversion::General,
+        }
     }
 }
 
-const FORMAT_SIZE: usize = 20;
 const NUMSTR_SIZE: usize = 200;
+/// The largest buffer [`try_formatted()`] grows for extreme precisions.
+const MAX_SCRATCH_SIZE: usize = 1 << 24;
 
-fn fmt_g(formatter: &mut fmt::Formatter<'_>, value: f64) -> fmt::Result {
-    let mut format = [0u8; FORMAT_SIZE];
-    let numstr = [0u8; NUMSTR_SIZE];
-    let mut fmtbuf = std::io::Cursor::new(&mut format[..FORMAT_SIZE - 1]); // keep final 0
+/// U+2212 MINUS SIGN, see [`Options::typographic_minus()`].
+pub(crate) const MINUS_SIGN: &str = "\u{2212}";
 
-    let zero_pad = if formatter.sign_aware_zero_pad() {
-        "0"
-    } else {
-        ""
+/// The number of significant digits `%g` prints for a given precision.
+pub(crate) fn significant_digits(precision: Option<usize>) -> usize {
+    match precision {
+        None => 6,
+        Some(0) => 1,
+        Some(p) => p,
+    }
+}
+
+/// Formats `value` into `f` like [`GPoint`] does, following the flags,
+/// width and precision of `f`, so that other types can delegate their
+/// `Display` to it.
+///
+/// ```
+/// use std::fmt;
+///
+/// struct Meters(f64);
+///
+/// impl fmt::Display for Meters {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         gpoint::write_g(f, self.0)?;
+///         f.write_str(" m")
+///     }
+/// }
+///
+/// assert!(format!("{:.3}", Meters(1.23456)) == "1.23 m");
+/// ```
+pub fn write_g(f: &mut fmt::Formatter<'_>, value: f64) -> fmt::Result {
+    fmt_g(f, value, &GConfig::get())
+}
+
+/// Formats `value` into `f` like [`write_g()`], with the given options.
+pub fn write_g_with(f: &mut fmt::Formatter<'_>, value: f64, options: &Options) -> fmt::Result {
+    fmt_g(f, value, options)
+}
+
+/// Formats `value` like `format!("{}", GPoint(value))` would, and hands the
+/// output to `f` without allocating nor copying it, e.g. to hash it or compare
+/// it when generating keys or removing duplicates: it lives on the stack, or
+/// in a buffer kept by each thread for extreme precisions.
+///
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::{Hash, Hasher};
+///
+/// let mut hasher = DefaultHasher::new();
+/// gpoint::with_formatted(0.1 + 0.2, |s| s.hash(&mut hasher)).unwrap();
+/// assert!(hasher.finish() == {
+///     let mut hasher = DefaultHasher::new();
+///     "0.3".hash(&mut hasher);
+///     hasher.finish()
+/// });
+/// assert!(gpoint::with_formatted(1e-5, |s| s == "1e-05") == Ok(true));
+/// ```
+pub fn with_formatted<R>(value: f64, f: impl FnMut(&str) -> R) -> Result<R, GError> {
+    GPoint(value)
+        .with(GConfig::get())
+        .with_formatted(&Spec::default(), f)
+}
+
+fn fmt_g(formatter: &mut fmt::Formatter<'_>, value: f64, options: &Options) -> fmt::Result {
+    let mut flags = Flags::from_formatter(formatter);
+    flags.precision = flags.precision.or(options.precision);
+    formatted(value, &flags, options, |numstr| formatter.write_str(numstr))?
+}
+
+/// Formats `value` according to `flags` and `options`, and hands the output
+/// to `f`, or Rust's own `Display` output as [`Options::on_error()`] asks.
+///
+/// This happens on the stack, unless the output is too long for it, e.g.
+/// with extreme precisions: then a growable buffer is kept by each thread.
+pub(crate) fn formatted<R>(
+    value: f64,
+    flags: &Flags,
+    options: &Options,
+    mut f: impl FnMut(&str) -> R,
+) -> Result<R, GError> {
+    match (
+        try_formatted(value, flags, options, &mut f),
+        options.on_error,
+    ) {
+        // refusing non-finite values is a deliberate choice
+        (Err(error), OnError::Rust | OnError::RustAndNotify(_)) if error != GError::NonFinite => {
+            if let OnError::RustAndNotify(notify) = options.on_error {
+                notify(error);
+            }
+            Ok(f(&value.to_string()))
+        }
+        (result, _) => result,
+    }
+}
+
+fn try_formatted<R>(
+    value: f64,
+    flags: &Flags,
+    options: &Options,
+    f: &mut impl FnMut(&str) -> R,
+) -> Result<R, GError> {
+    if let Some(constant) = constant(value, flags, options) {
+        return Ok(f(constant));
+    }
+    let mut numstr = [0u8; NUMSTR_SIZE];
+    let mut digits = [0u8; NUMSTR_SIZE];
+    match format_g(&mut numstr, &mut digits, value, flags, options) {
+        Ok(len) => return Ok(f(unsafe { std::str::from_utf8_unchecked(&numstr[..len]) })),
+        Err(GError::Overflow) => (),
+        Err(e) => return Err(e),
+    }
+
+    thread_local! {
+        static SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+    // enough for the integer digits of the largest `f64`, the precision and
+    // the fraction digits, maybe grouped, with 4-byte characters for padding
+    let precision = match options.notation {
+        Notation::Exact => exact::FRACTIONAL_DIGITS,
+        _ => exact::output_precision(flags),
     };
-    let sign_pad = if formatter.sign_minus() {
-        "-"
-    } else if formatter.sign_plus() {
-        "+"
-    } else {
-        ""
+    let size = 2 * (320 + precision + options.min_fraction_digits + options.min_exponent_digits)
+        + 4 * flags.width.unwrap_or(0);
+    if size > MAX_SCRATCH_SIZE {
+        return Err(GError::Overflow);
+    }
+    let mut format = |scratch: &mut Vec<u8>| {
+        if scratch.len() < 2 * size {
+            scratch.resize(2 * size, 0);
+        }
+        let (numstr, digits) = scratch.split_at_mut(size);
+        let len = format_g(numstr, digits, value, flags, options)?;
+        Ok(f(unsafe { std::str::from_utf8_unchecked(&numstr[..len]) }))
+    };
+    SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut scratch) => format(&mut scratch),
+        // `f` itself formats a number with an extreme precision
+        Err(_) => format(&mut Vec::new()),
+    })
+}
+
+/// Returns the output for values dominating many datasets, when plainly
+/// formatted: every backend agrees on it, so they needn't be called.
+fn constant(value: f64, flags: &Flags, options: &Options) -> Option<&'static str> {
+    // custom backends may well disagree, on purpose
+    if *flags != Flags::default()
+        || options.backend(Backend::Libc) != Options::new()
+        || matches!(options.backend, Backend::Custom(_))
+    {
+        return None;
+    }
+    Some(match value {
+        // the sign of NaNs varies across C libraries
+        _ if value.is_nan() => return None,
+        _ if value == 0. && value.is_sign_negative() => "-0",
+        0. => "0",
+        1. => "1",
+        -1. => "-1",
+        f64::INFINITY => "inf",
+        f64::NEG_INFINITY => "-inf",
+        _ => return None,
+    })
+}
+
+/// Returns the flags the digits of a number are written with for
+/// `options.notation`, before rewriting, and the power of ten the number is
+/// scaled by first.
+pub(crate) fn notation_flags(flags: &Flags, options: &Options) -> (Flags, i32) {
+    let mut digit_flags = *flags;
+    let mut scale = 0;
+    match options.notation {
+        Notation::Percent { fixed, .. } => {
+            scale = 2;
+            if fixed {
+                digit_flags.conversion = Conversion::Fixed;
+            }
+        }
+        Notation::Si => {
+            // the prefix depends on the exponent of the rounded number
+            digit_flags.conversion = Conversion::Scientific;
+            digit_flags.precision = Some(significant_digits(flags.precision) - 1);
+        }
+        Notation::Exact => {
+            // every digit of the expansion, on every platform
+            digit_flags.conversion = Conversion::Fixed;
+            digit_flags.precision = Some(exact::FRACTIONAL_DIGITS);
+        }
+        Notation::GCode { .. } => digit_flags.conversion = Conversion::Fixed,
+        Notation::Xsd => {
+            // the precision is the number of significant digits
+            digit_flags.conversion = Conversion::Scientific;
+            digit_flags.precision = flags
+                .precision
+                .map(|precision| significant_digits(Some(precision)) - 1);
+        }
+        _ => (),
+    }
+    (digit_flags, scale)
+}
+
+/// Formats `value` into `numstr` according to `flags` and `options`,
+/// returning the output length. `digits` is scratch space of the same size.
+pub(crate) fn format_g(
+    numstr: &mut [u8],
+    digits: &mut [u8],
+    value: f64,
+    flags: &Flags,
+    options: &Options,
+) -> Result<usize, GError> {
+    let mut backend_flags = *flags;
+    // padding is applied last, since rewriting may change the length
+    backend_flags.width = None;
+    let mut value = value;
+    // a forced sign is obtained by formatting a positive zero with a `+` sign,
+    // then flipping it
+    let negative_zero = value == 0. && value.is_sign_negative();
+    let force_minus = negative_zero && options.negative_zero == NegativeZero::Preserve;
+    if negative_zero && options.negative_zero != NegativeZero::AsIs {
+        value = 0.;
+        backend_flags.plus |= force_minus;
+    }
+    let (backend_flags, scale) = notation_flags(&backend_flags, options);
+    if scale != 0 {
+        value *= 10f64.powi(scale);
+    }
+    let mut backend = match options.notation {
+        Notation::Exact => Backend::Exact,
+        _ => options.backend,
     };
-    let alternate = if formatter.alternate() { "#" } else { "" };
-    match (formatter.width(), formatter.precision()) {
-        (None, None) => write!(fmtbuf, "%{}{}g", alternate, sign_pad),
-        (Some(w), None) => write!(fmtbuf, "%{}{}{}{}g", alternate, sign_pad, zero_pad, w),
-        (None, Some(p)) => write!(fmtbuf, "%{}.{}g", alternate, p),
-        (Some(w), Some(p)) => write!(fmtbuf, "%{}{}{}{}.{}g", alternate, sign_pad, zero_pad, w, p),
-    }
-    .map_err(|_| fmt::Error)?;
-    let nbchars = unsafe {
-        libc::snprintf(
-            numstr.as_ptr() as *mut c_char,
-            NUMSTR_SIZE,
-            format.as_ptr() as *const c_char,
-            value,
-        )
+    let len = {
+        let _rounding = if options.deterministic_rounding {
+            let guard = RoundingGuard::new(RoundingMode::ToNearest);
+            // C libraries follow the rounding mode which couldn't be set
+            if guard.is_none() && matches!(backend, Backend::Libc | Backend::Strfromd) {
+                backend = Backend::Pure;
+            }
+            guard
+        } else {
+            options.rounding.and_then(RoundingGuard::new)
+        };
+        let _locale = locale::LocaleGuard::new(options.locale)?;
+        match options.notation {
+            Notation::Go | Notation::DotNet | Notation::Xsd
+                if backend_flags.precision.is_none() && value.is_finite() =>
+            {
+                let size = numstr.len();
+                let max_exponent = match options.notation {
+                    Notation::Go => 6,
+                    Notation::DotNet => 15,
+                    // always with an exponent
+                    _ => i32::MIN,
+                };
+                shortest::format_g(
+                    &mut numstr[..size - 1],
+                    value,
+                    &backend_flags,
+                    max_exponent,
+                    options.shortest,
+                )?
+            }
+            Notation::Excel if value.is_finite() => {
+                let size = numstr.len();
+                excel::format_g(&mut numstr[..size - 1], value, &backend_flags)?
+            }
+            _ => backend::format_g(backend, numstr, value, &backend_flags)?,
+        }
     };
-    // check if we (virtually) overflowed our buffer
-    if nbchars < 0 || nbchars >= NUMSTR_SIZE as i32 {
-        return Err(fmt::Error);
+    if force_minus {
+        numstr[0] = b'-';
     }
-    let numstr = &numstr[..nbchars as usize];
+    finish(numstr, digits, len, value, flags, options)
+}
+
+/// Rewrites, signs and pads the `len` first bytes of `numstr`, the output of
+/// a backend for `value`, returning its new length. `digits` is scratch space
+/// of the same size.
+pub(crate) fn finish(
+    numstr: &mut [u8],
+    digits: &mut [u8],
+    len: usize,
+    value: f64,
+    flags: &Flags,
+    options: &Options,
+) -> Result<usize, GError> {
+    let size = numstr.len();
+    let len = rewrite::rewrite(&mut numstr[..size - 1], digits, len, value, flags, options)?;
 
-    formatter.write_str(unsafe { std::str::from_utf8_unchecked(numstr) })
+    // the ` ` flag is unknown to Rust and strfromd(): apply it here
+    let len = if flags.space
+        && !flags.plus
+        && (value.is_finite() || options.non_finite == NonFinite::AsIs)
+        && !matches!(numstr[0], b'-' | b'+')
+        && !numstr.starts_with(MINUS_SIGN.as_bytes())
+    {
+        buffer::insert(&mut numstr[..size - 1], len, 0, b" ")?
+    } else {
+        len
+    };
+    let sign_len = if matches!(numstr[0], b'-' | b'+') || (flags.space && numstr[0] == b' ') {
+        1
+    } else if numstr.starts_with(MINUS_SIGN.as_bytes()) {
+        MINUS_SIGN.len()
+    } else {
+        0
+    };
+    let mut pad_flags = *flags;
+    let mut zero_pad = value.is_finite();
+    if options.zero_padding == ZeroPadding::Rust && flags.zero {
+        pad_flags.left = false;
+        pad_flags.center = false;
+        zero_pad |= options.non_finite == NonFinite::AsIs;
+    }
+    buffer::pad(&mut numstr[..size - 1], len, sign_len, &pad_flags, zero_pad)
 }
 
 #[cfg(test)]
@@ -130,7 +889,7 @@ mod tests {
         }
     }
     #[test]
-    fn minus_pad() {
+    fn left_pad() {
         for (num, res) in [
             (42., "42      "),
             (-1.01, "-1.01   "),
@@ -139,10 +898,20 @@ mod tests {
             (f64::INFINITY, "inf     "),
         ] {
             let num = GPoint(num);
-            assert_eq!(&format!("{:-8}", num), res);
+            assert_eq!(&format!("{:<8}", num), res);
         }
     }
     #[test]
+    #[cfg(feature = "legacy-minus")]
+    fn legacy_minus() {
+        assert_eq!(&format!("{:-8}", GPoint(-1.01)), "-1.01   ");
+    }
+    #[test]
+    #[cfg(not(feature = "legacy-minus"))]
+    fn minus_not_left() {
+        assert_eq!(&format!("{:-8}", GPoint(-1.01)), "   -1.01");
+    }
+    #[test]
     fn plus() {
         for (num, res) in [
             (42., "+42"),
@@ -192,6 +961,269 @@ mod tests {
         }
     }
     #[test]
+    fn fill() {
+        for (num, res) in [
+            (42., "******42"),
+            (-1.01, "***-1.01"),
+            (f64::NAN, "*****nan"),
+            (-f64::INFINITY, "****-inf"),
+        ] {
+            let num = GPoint(num);
+            assert_eq!(&format!("{:*>8}", num), res);
+        }
+        assert_eq!(
+            &format!("{:\u{b7}<6}", GPoint(1.5)),
+            "1.5\u{b7}\u{b7}\u{b7}"
+        );
+        assert_eq!(&format!("{:*>08}", GPoint(-1.5)), "-00001.5");
+    }
+    #[test]
+    fn center() {
+        for (num, res) in [
+            (42., "   42   "),
+            (-1.01, " -1.01  "),
+            (f64::NAN, "  nan   "),
+            (1e-10, " 1e-10  "),
+        ] {
+            let num = GPoint(num);
+            assert_eq!(&format!("{:^8}", num), res);
+        }
+        assert_eq!(&format!("{:*^7}", GPoint(1.5)), "**1.5**");
+        assert_eq!(&format!("{:^08}", GPoint(-1.5)), "-00001.5");
+    }
+    #[test]
+    fn default_precision() {
+        for (num, res) in [
+            (42., "42"),
+            (-1.012345678901, "-1.01"),
+            (4321., "4.32e+03"),
+            (f64::NAN, "nan"),
+        ] {
+            assert_eq!(&format!("{}", GPointP::<3>(num)), res);
+            assert_eq!(&format!("{:.3}", GPoint(num)), res);
+        }
+        assert_eq!(&format!("{:#}", GPointP::<0>(42.)), "4.e+01");
+        assert_eq!(&format!("{:.1}", GPointP::<3, f32>(42.)), "4e+01");
+        let options = Options::new().notation(Notation::Percent {
+            fixed: false,
+            space: false,
+        });
+        assert_eq!(&format!("{}", GPointP::<2>(0.1234).with(options)), "12%");
+    }
+    #[test]
+    fn extreme_precision() {
+        let pure = Options::new().backend(Backend::Pure);
+        for num in [1. / 3., -1e300, 5e-324, f64::MAX] {
+            let libc = format!("{:#.500}", GPoint(num));
+            assert!(libc.len() > 500, "{}", libc);
+            assert_eq!(format!("{:#.500}", GPoint(num).with(pure)), libc);
+            assert_eq!(
+                format!("{:.500}", GPoint(num).with(pure)),
+                format!("{:.500}", GPoint(num))
+            );
+            assert_eq!(
+                format!("{:#.752}", GPoint(num).with(pure)),
+                format!("{:#.752}", GPoint(num))
+            );
+        }
+        assert_eq!(format!("{:.500}", GPoint(0.5)), "0.5");
+        assert_eq!(format!("{:300}", GPoint(0.5)).len(), 300);
+        assert_eq!(Spec::parse("%.0f").unwrap().format(-1e300).len(), 302);
+        let cached = GPoint(1. / 3.).cached();
+        assert_eq!(
+            format!("{:.500}", cached),
+            format!("{:.500}", GPoint(1. / 3.))
+        );
+    }
+    #[test]
+    fn try_to_string() {
+        let spec = Spec::parse("%+.3e").unwrap();
+        for (num, res, res_spec) in [
+            (42., "42", "+4.200e+01"),
+            (-1e-10f32 as f64, "-1e-10", "-1.000e-10"),
+            (f64::NAN, "nan", "+nan"),
+        ] {
+            assert_eq!(GPoint(num).try_to_string().unwrap(), res);
+            assert_eq!(GPoint(num).try_to_string_with(&spec).unwrap(), res_spec);
+        }
+        let options = Options::new().precision(2).uppercase_non_finite(true);
+        assert_eq!(GPoint(1.2345).with(options).try_to_string().unwrap(), "1.2");
+        assert_eq!(
+            GPoint(f32::INFINITY)
+                .with(options)
+                .try_to_string_with(&spec),
+            Ok("+INF".to_string())
+        );
+        let spec = Spec::parse("%#.30000g").unwrap();
+        assert_eq!(GPoint(1.).try_to_string_with(&spec).unwrap().len(), 30001);
+    }
+    #[test]
+    fn with_formatted() {
+        let mut seen = std::collections::HashSet::new();
+        for num in [0.1 + 0.2, 0.3, 1. / 3., 0.333333, -0., 0.] {
+            super::with_formatted(num, |s| {
+                if !seen.contains(s) {
+                    seen.insert(s.to_owned());
+                }
+            })
+            .unwrap();
+        }
+        assert_eq!(seen.len(), 4);
+        let spec = Spec::parse("%#.30000g").unwrap();
+        let options = Options::new().precision(3);
+        assert_eq!(
+            GPoint(1.).with(options).with_formatted(&spec, str::len),
+            Ok(30001)
+        );
+        assert_eq!(
+            GPoint(1. / 3.)
+                .with(options)
+                .with_formatted(&Spec::default(), |s| s == "0.333"),
+            Ok(true)
+        );
+        let strict = Options::new().non_finite(NonFinite::Error);
+        assert_eq!(
+            GPoint(f64::NAN)
+                .with(strict)
+                .with_formatted(&spec, str::len),
+            Err(GError::NonFinite)
+        );
+    }
+    #[test]
+    fn format_into() {
+        struct Full;
+        impl fmt::Write for Full {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+        let spec = Spec::parse("%+08.3f").unwrap();
+        let options = Options::new().precision(2).non_finite(NonFinite::Error);
+        let mut out = String::from("x");
+        GPoint(1. / 3.).format_into(&mut out).unwrap();
+        GPoint(-2.5f32).format_into_with(&spec, &mut out).unwrap();
+        GPoint(1. / 3.).with(options).format_into(&mut out).unwrap();
+        GPoint(1.)
+            .with(options)
+            .format_into_with(&spec, &mut out)
+            .unwrap();
+        GPoint(1e-300).write_to(&mut out).unwrap();
+        assert_eq!(out, "x0.333333-002.5000.33+001.0001e-300");
+        assert_eq!(
+            GPoint(f64::NAN).with(options).format_into(&mut out),
+            Err(GError::NonFinite)
+        );
+        assert_eq!(GPoint(1.).write_to(&mut Full), Err(GError::Write));
+        let spec = Spec::parse("%#.30000g").unwrap();
+        out.clear();
+        GPoint(1.).write_to_with(&spec, &mut out).unwrap();
+        assert_eq!(out.len(), 30001);
+    }
+    #[test]
+    fn ascii() {
+        for (options, res) in [
+            (Options::new(), Ok("-1.5e-06")),
+            (
+                Options::new().typographic_minus(true),
+                Err(GError::NonAscii),
+            ),
+            (
+                Options::new().exponent(ExponentStyle::Superscript),
+                Err(GError::NonAscii),
+            ),
+            (Options::new().notation(Notation::Si), Err(GError::NonAscii)),
+            (
+                Options::new().notation(Notation::Engineering),
+                Ok("-1.5e-06"),
+            ),
+        ] {
+            assert_eq!(
+                GPoint(-1.5e-6).with(options).to_ascii_string(),
+                res.map(str::to_owned)
+            );
+        }
+        assert_eq!(GPoint(f64::NAN).to_ascii_string(), Ok("nan".to_owned()));
+    }
+    #[test]
+    fn constants() {
+        for options in [Options::new(), Options::new().consistent(true)] {
+            for num in [0., -0., 1., -1., f64::INFINITY, f64::NEG_INFINITY] {
+                let constant = constant(num, &Flags::default(), &options).unwrap();
+                let mut numstr = [0; NUMSTR_SIZE];
+                let mut digits = [0; NUMSTR_SIZE];
+                let len = format_g(&mut numstr, &mut digits, num, &Flags::default(), &options);
+                assert_eq!(constant.as_bytes(), &numstr[..len.unwrap()]);
+            }
+        }
+        let flags = Flags {
+            plus: true,
+            ..Flags::default()
+        };
+        assert_eq!(constant(1., &flags, &Options::new()), None);
+        assert_eq!(constant(f64::NAN, &Flags::default(), &Options::new()), None);
+        let typographic = Options::new().typographic_minus(true);
+        assert_eq!(constant(-1., &Flags::default(), &typographic), None);
+        assert_eq!(format!("{:+}", GPoint(1.)), "+1");
+    }
+    #[test]
+    fn delegated() {
+        struct Celsius(f64);
+        impl fmt::Display for Celsius {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_g_with(f, self.0, &Options::new().typographic_minus(true))?;
+                f.write_str("\u{b0}C")
+            }
+        }
+        for (num, res) in [(21.5, "  21.5°C"), (-40., "   \u{2212}40°C")] {
+            assert_eq!(format!("{:6}", Celsius(num)), res);
+        }
+    }
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive() {
+        #[derive(GDisplay)]
+        struct Sample {
+            pub(crate) x: f64,
+            #[gpoint(precision = 2)]
+            r#y: f32,
+            #[doc = "skipped"]
+            #[gpoint(skip)]
+            _map: std::collections::HashMap<String, Vec<f64>>,
+            #[gpoint(skip)]
+            _callback: fn(f64) -> f64,
+            count: usize,
+        }
+        let sample = Sample {
+            x: -1.5e-10,
+            y: 1.2345,
+            _map: Default::default(),
+            _callback: f64::abs,
+            count: 3,
+        };
+        assert_eq!(sample.to_string(), "x=-1.5e-10 y=1.2 count=3");
+    }
+    #[cfg(feature = "derive")]
+    #[test]
+    fn g_str() {
+        let pure = |spec: &str, num: f64| {
+            Spec::parse(spec)
+                .unwrap()
+                .backend(Backend::Pure)
+                .format(num)
+        };
+        assert_eq!(g_str!(0.1), pure("%g", 0.1));
+        assert_eq!(g_str!(-0.0), "-0");
+        assert_eq!(g_str!(100000), "100000");
+        assert_eq!(g_str!(1000000u32), "1e+06");
+        assert_eq!(g_str!(0.1f32), pure("%g", 0.1f32 as f64));
+        assert_eq!(g_str!("%.17g", 0.1), pure("%.17g", 0.1));
+        assert_eq!(g_str!("%#g", 1.5), "1.50000");
+        assert_eq!(g_str!("%.0e", 2.5), "2e+00");
+        assert_eq!(g_str!("%#.0f", 3.5), "4.");
+        assert_eq!(g_str!("%+f", 1e-7), "+0.000000");
+        assert_eq!(g_str!("%G", 1.5e-300), "1.5E-300");
+    }
+    #[test]
     fn in_context() {
         assert_eq!(&format!("answer={}!", GPoint(42.)), "answer=42!");
     }
@@ -0,0 +1,308 @@
+//! Parsing numbers the way C's `strtod()` does
+
+use crate::GError;
+
+/// A number parsed by [`parse_c()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct CLiteral {
+    /// The number, rounded to nearest. NaNs carry their payload in their
+    /// mantissa, as glibc's `strtod()` stores it, quiet bit set.
+    pub value: f64,
+    /// The payload of a NaN written as `nan(n-char-sequence)`, when the
+    /// sequence is a number as `strtoull()` reads it with base 0, e.g.
+    /// `0xdead` or `42`.
+    pub nan_payload: Option<u64>,
+}
+
+/// Parses any floating point literal C's `strtod()` accepts in the `C` locale,
+/// in pure Rust: optional leading and trailing whitespace and sign, decimal
+/// or hexadecimal significands (`0x1.8p-3`), `inf`, `infinity`, `nan` and
+/// `nan(...)` in any case. Out of range numbers become infinite or zero.
+/// Anything else fails with [`GError::InvalidNumber`].
+///
+/// ```
+/// use gpoint::parse_c;
+///
+/// assert!(parse_c(" -0x1.8p1").unwrap().value == -3.);
+/// assert!(parse_c("INFINITY").unwrap().value == f64::INFINITY);
+/// let nan = parse_c("NAN(0xdead)").unwrap();
+/// assert!(nan.value.is_nan() && nan.nan_payload == Some(0xdead));
+/// assert!(nan.value.to_bits() == 0x7ff8_0000_0000_dead);
+/// ```
+pub fn parse_c(text: &str) -> Result<CLiteral, GError> {
+    let text = text.trim_matches(|c: char| c.is_ascii_whitespace() || c == '\x0b');
+    let (negative, unsigned) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let lowercase = unsigned.to_ascii_lowercase();
+    let (magnitude, nan_payload) = if lowercase == "inf" || lowercase == "infinity" {
+        (f64::INFINITY, None)
+    } else if let Some(sequence) = lowercase.strip_prefix("nan") {
+        let payload = match sequence {
+            "" => None,
+            _ => {
+                let sequence = sequence
+                    .strip_prefix('(')
+                    .and_then(|sequence| sequence.strip_suffix(')'))
+                    .filter(|sequence| {
+                        sequence
+                            .bytes()
+                            .all(|c| c.is_ascii_alphanumeric() || c == b'_')
+                    })
+                    .ok_or(GError::InvalidNumber)?;
+                parse_payload(sequence)
+            }
+        };
+        // quiet, with the bits of the payload that fit
+        let bits = 0x7ff8_0000_0000_0000 | payload.unwrap_or(0) & 0x0007_ffff_ffff_ffff;
+        (f64::from_bits(bits), payload)
+    } else if let Some(hex) = lowercase.strip_prefix("0x") {
+        (parse_hex(hex).ok_or(GError::InvalidNumber)?, None)
+    } else {
+        (parse_decimal(unsigned).ok_or(GError::InvalidNumber)?, None)
+    };
+    Ok(CLiteral {
+        value: if negative { -magnitude } else { magnitude },
+        nan_payload,
+    })
+}
+
+/// Reads a NaN's n-char-sequence as `strtoull()` does with base 0, saturating
+/// on overflow.
+fn parse_payload(sequence: &str) -> Option<u64> {
+    let (digits, radix) = if let Some(hex) = sequence.strip_prefix("0x") {
+        (hex, 16)
+    } else if sequence.len() > 1 && sequence.starts_with('0') {
+        (&sequence[1..], 8)
+    } else {
+        (sequence, 10)
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    Some(u64::from_str_radix(digits, radix).unwrap_or(u64::MAX))
+}
+
+/// Parses an unsigned decimal significand with an optional exponent.
+fn parse_decimal(text: &str) -> Option<f64> {
+    let (significand, exponent) = match text.find(['e', 'E']) {
+        Some(e) => (&text[..e], Some(&text[e + 1..])),
+        None => (text, None),
+    };
+    let (integer, fraction) = significand.split_once('.').unwrap_or((significand, ""));
+    let digits = |digits: &str| digits.bytes().all(|c| c.is_ascii_digit());
+    if integer.len() + fraction.len() == 0 || !digits(integer) || !digits(fraction) {
+        return None;
+    }
+    if let Some(exponent) = exponent {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        if exponent.is_empty() || !digits(exponent) {
+            return None;
+        }
+    }
+    // Rust's parsing is correctly rounded too, and accepts those forms
+    text.parse().ok()
+}
+
+/// Parses an unsigned hexadecimal significand, without its `0x`, with an
+/// optional binary exponent, rounded to nearest, ties to even.
+fn parse_hex(text: &str) -> Option<f64> {
+    let (significand, exponent) = match text.find('p') {
+        Some(p) => (&text[..p], Some(&text[p + 1..])),
+        None => (text, None),
+    };
+    let (integer, fraction) = significand.split_once('.').unwrap_or((significand, ""));
+    if integer.len() + fraction.len() == 0 {
+        return None;
+    }
+    // the first 57 to 60 significant bits, whether the others are all zeros,
+    // and the power of two of the last bit kept
+    let mut mantissa = 0u64;
+    let mut sticky = false;
+    let mut exponent2: i64 = 0;
+    for (i, c) in integer.chars().chain(fraction.chars()).enumerate() {
+        let digit = u64::from(c.to_digit(16)?);
+        let in_fraction = i >= integer.len();
+        if mantissa < 1 << 56 {
+            mantissa = mantissa << 4 | digit;
+            if in_fraction {
+                exponent2 -= 4;
+            }
+        } else {
+            sticky |= digit != 0;
+            if !in_fraction {
+                exponent2 += 4;
+            }
+        }
+    }
+    if let Some(exponent) = exponent {
+        let (negative, digits) = match exponent.as_bytes().first() {
+            Some(b'-') => (true, &exponent[1..]),
+            Some(b'+') => (false, &exponent[1..]),
+            _ => (false, exponent),
+        };
+        if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        // beyond this, any number is infinite or zero
+        let value = digits.bytes().fold(0i64, |value, c| {
+            (value * 10 + i64::from(c - b'0')).min(1 << 20)
+        });
+        exponent2 += if negative { -value } else { value };
+    }
+    if mantissa == 0 {
+        return Some(0.);
+    }
+    Some(round_binary(mantissa, sticky, exponent2))
+}
+
+/// Returns `mantissa * 2^exponent2` rounded to the nearest `f64`, `sticky`
+/// telling whether nonzero bits below `mantissa` were dropped.
+fn round_binary(mantissa: u64, sticky: bool, exponent2: i64) -> f64 {
+    let bits = i64::from(64 - mantissa.leading_zeros());
+    // the power of two of the leading bit
+    let leading = exponent2 + bits - 1;
+    if leading > 1023 {
+        return f64::INFINITY;
+    }
+    // subnormals keep less bits, down to none at half the smallest one
+    let keep = if leading >= -1022 { 53 } else { leading + 1075 };
+    if keep < 0 {
+        return 0.;
+    }
+    let shift = bits - keep;
+    let (mut kept, mut exponent2) = if shift > 0 {
+        let mantissa = u128::from(mantissa);
+        let kept = mantissa >> shift;
+        let rest = mantissa & ((1 << shift) - 1);
+        let half = 1 << (shift - 1);
+        let up = rest > half || rest == half && (sticky || kept & 1 == 1);
+        ((kept + u128::from(up)) as u64, exponent2 + shift)
+    } else {
+        (mantissa << -shift, exponent2 + shift)
+    };
+    // rounding up may carry into a new bit
+    if kept == 1 << 53 {
+        kept >>= 1;
+        exponent2 += 1;
+    }
+    if kept < 1 << 52 {
+        // subnormal, with `exponent2` at -1074
+        return f64::from_bits(kept);
+    }
+    let biased = exponent2 + 52 + 1023;
+    if biased >= 0x7ff {
+        return f64::INFINITY;
+    }
+    f64::from_bits((biased as u64) << 52 | (kept & ((1 << 52) - 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libc::c_char;
+    #[test]
+    fn literals() {
+        for (text, res) in [
+            ("0", 0.),
+            ("-0", -0.),
+            ("  +1.5e3\n", 1500.),
+            ("\t.5", 0.5),
+            ("1.", 1.),
+            ("1E-2", 0.01),
+            ("1e400", f64::INFINITY),
+            ("-1e-400", -0.),
+            ("0x10", 16.),
+            ("0X1.8P1", 3.),
+            ("-0x.8", -0.5),
+            ("0x1p-1074", 5e-324),
+            ("0x1p-1075", 0.),
+            ("0x1.8p-1075", 5e-324),
+            ("0x1.fffffffffffff8p1023", f64::INFINITY),
+            ("0x1.fffffffffffff7p1023", f64::MAX),
+            ("0x0p99999999999999999999", 0.),
+            ("inf", f64::INFINITY),
+            ("-Infinity", f64::NEG_INFINITY),
+            ("INFINITY", f64::INFINITY),
+        ] {
+            assert_eq!(
+                parse_c(text).map(|literal| literal.value),
+                Ok(res),
+                "{:?}",
+                text
+            );
+        }
+        for (text, bits, payload) in [
+            ("nan", 0x7ff8_0000_0000_0000, None),
+            ("-NaN", 0xfff8_0000_0000_0000, None),
+            ("nan()", 0x7ff8_0000_0000_0000, None),
+            ("NAN(0xdead)", 0x7ff8_0000_0000_dead, Some(0xdead)),
+            ("nan(42)", 0x7ff8_0000_0000_002a, Some(42)),
+            ("nan(017)", 0x7ff8_0000_0000_000f, Some(15)),
+            (
+                "nan(0xffffffffffffffff)",
+                0x7fff_ffff_ffff_ffff,
+                Some(u64::MAX),
+            ),
+            ("nan(snan_1)", 0x7ff8_0000_0000_0000, None),
+        ] {
+            let literal = parse_c(text).unwrap();
+            assert_eq!(literal.value.to_bits(), bits, "{:?}", text);
+            assert_eq!(literal.nan_payload, payload, "{:?}", text);
+        }
+        for text in [
+            "", " ", "+", "-", ".", "e5", "1e", "1e+", "0x", "0x.p1", "0x1p", "1.5.2", "1,5",
+            "infin", "nan(", "nan(a b)", "nanx", "1 2", "0b1",
+        ] {
+            assert_eq!(parse_c(text), Err(GError::InvalidNumber), "{:?}", text);
+        }
+    }
+    #[test]
+    fn same_as_strtod() {
+        let strtod = |text: &str| {
+            let text = std::ffi::CString::new(text).unwrap();
+            unsafe { libc::strtod(text.as_ptr() as *const c_char, std::ptr::null_mut()) }
+        };
+        let mut texts: Vec<String> = crate::testing::corpus()
+            .iter()
+            .flat_map(|num| [format!("{:e}", num), format!("{:.20}", num)])
+            .collect();
+        for mantissa in [
+            "1",
+            "1.fffffffffffff",
+            "1.00000000000008",
+            "1.000000000000081",
+            "0.0000001",
+        ] {
+            for exponent in [-1080, -1074, -1060, -1022, -3, 0, 52, 1023, 1024] {
+                texts.push(format!("0x{}p{}", mantissa, exponent));
+            }
+        }
+        // the exact hexadecimal expansions
+        for &num in crate::testing::corpus()
+            .iter()
+            .filter(|num| num.is_finite())
+        {
+            let bits = num.to_bits();
+            let (mantissa, exponent) = match (bits >> 52) & 0x7ff {
+                0 => (bits & ((1 << 52) - 1), -1074),
+                e => (bits & ((1 << 52) - 1) | 1 << 52, e as i64 - 1075),
+            };
+            let sign = if num.is_sign_negative() { "-" } else { "" };
+            texts.push(format!("{}0x{:x}p{}", sign, mantissa, exponent));
+        }
+        for text in texts {
+            let (res, expected) = (parse_c(&text).unwrap().value, strtod(&text));
+            assert!(
+                res.to_bits() == expected.to_bits() || res.is_nan() && expected.is_nan(),
+                "{}: {:e} != {:e}",
+                text,
+                res,
+                expected
+            );
+        }
+    }
+}
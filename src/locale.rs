@@ -0,0 +1,218 @@
+//! Selection of the locale the C library formats numbers in
+
+use crate::GError;
+
+/// The `LC_NUMERIC` locale numbers are formatted in, which decides their
+/// decimal point, see [`Options::locale()`](crate::Options::locale).
+///
+/// Only [`Backend::Libc`](crate::Backend::Libc) follows locales: the other
+/// backends always use the `C` locale, [`Backend::Strfromd`](crate::Backend::Strfromd)
+/// by switching to it around each call, since glibc's `strfromd()` follows
+/// `LC_NUMERIC` too.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NumericLocale {
+    /// Whatever the program selected with `setlocale()`, i.e. the `C` locale
+    /// unless it did.
+    #[default]
+    System,
+    /// The `C` locale, whose decimal point is `.`, whatever `setlocale()` did.
+    C,
+    /// The locale with the given name, e.g. `"fr_FR.UTF-8"`. Formatting
+    /// fails with [`GError::Locale`] if it isn't installed.
+    Named(&'static str),
+}
+
+/// Switches the calling thread to a locale for as long as it lives,
+/// restoring the previous one when dropped.
+#[cfg(unix)]
+pub(crate) struct LocaleGuard {
+    previous: libc::locale_t,
+    /// The locale to free once done with it, unless it's cached.
+    owned: Option<libc::locale_t>,
+}
+
+#[cfg(unix)]
+impl LocaleGuard {
+    /// Switches to `locale`, returning `None` for the system one, which is
+    /// already current.
+    pub(crate) fn new(locale: NumericLocale) -> Result<Option<Self>, GError> {
+        let (locale, owned) = match locale {
+            NumericLocale::System => return Ok(None),
+            NumericLocale::C => (c_locale()?, false),
+            NumericLocale::Named(name) => (new_locale(name)?, true),
+        };
+        let previous = unsafe { libc::uselocale(locale) };
+        if previous.is_null() {
+            if owned {
+                unsafe { libc::freelocale(locale) };
+            }
+            return Err(GError::Locale);
+        }
+        Ok(Some(LocaleGuard {
+            previous,
+            owned: if owned { Some(locale) } else { None },
+        }))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for LocaleGuard {
+    fn drop(&mut self) {
+        unsafe { libc::uselocale(self.previous) };
+        if let Some(locale) = self.owned {
+            unsafe { libc::freelocale(locale) };
+        }
+    }
+}
+
+/// Returns the `C` locale, created once and for all.
+#[cfg(unix)]
+fn c_locale() -> Result<libc::locale_t, GError> {
+    use std::sync::OnceLock;
+    // locales are immutable once created, so they can be shared by threads
+    static C_LOCALE: OnceLock<usize> = OnceLock::new();
+    let locale = *C_LOCALE.get_or_init(|| new_locale("C").map_or(0, |locale| locale as usize));
+    if locale == 0 {
+        return Err(GError::Locale);
+    }
+    Ok(locale as libc::locale_t)
+}
+
+#[cfg(unix)]
+fn new_locale(name: &str) -> Result<libc::locale_t, GError> {
+    let name = std::ffi::CString::new(name).map_err(|_| GError::Locale)?;
+    let locale =
+        unsafe { libc::newlocale(libc::LC_NUMERIC_MASK, name.as_ptr(), std::ptr::null_mut()) };
+    if locale.is_null() {
+        return Err(GError::Locale);
+    }
+    Ok(locale)
+}
+
+/// Switches the calling thread to a locale for as long as it lives,
+/// restoring the previous one when dropped: the Windows CRT has no
+/// `uselocale()`, but `setlocale()` can be made to only affect the calling
+/// thread.
+#[cfg(windows)]
+pub(crate) struct LocaleGuard {
+    /// The previous per-thread locale setting, given back to
+    /// `_configthreadlocale()`.
+    config: libc::c_int,
+    previous: std::ffi::CString,
+}
+
+#[cfg(windows)]
+extern "C" {
+    fn _configthreadlocale(per_thread: libc::c_int) -> libc::c_int;
+}
+
+#[cfg(windows)]
+const ENABLE_PER_THREAD_LOCALE: libc::c_int = 1;
+
+#[cfg(windows)]
+impl LocaleGuard {
+    /// Switches to `locale`, returning `None` for the system one, which is
+    /// already current.
+    pub(crate) fn new(locale: NumericLocale) -> Result<Option<Self>, GError> {
+        use std::ffi::{CStr, CString};
+        let name = match locale {
+            NumericLocale::System => return Ok(None),
+            NumericLocale::C => "C",
+            NumericLocale::Named(name) => name,
+        };
+        let name = CString::new(name).map_err(|_| GError::Locale)?;
+        let config = unsafe { _configthreadlocale(ENABLE_PER_THREAD_LOCALE) };
+        if config == -1 {
+            return Err(GError::Locale);
+        }
+        // the thread starts from a copy of the global locale
+        let previous = unsafe { libc::setlocale(libc::LC_NUMERIC, std::ptr::null()) };
+        let previous = if previous.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(previous) }.to_owned())
+        };
+        let set = unsafe { libc::setlocale(libc::LC_NUMERIC, name.as_ptr()) };
+        match previous {
+            Some(previous) if !set.is_null() => Ok(Some(LocaleGuard { config, previous })),
+            _ => {
+                unsafe { _configthreadlocale(config) };
+                Err(GError::Locale)
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for LocaleGuard {
+    fn drop(&mut self) {
+        unsafe {
+            libc::setlocale(libc::LC_NUMERIC, self.previous.as_ptr());
+            _configthreadlocale(self.config);
+        }
+    }
+}
+
+/// Locales other than the system one are only supported on Unix and Windows.
+#[cfg(not(any(unix, windows)))]
+pub(crate) struct LocaleGuard;
+
+#[cfg(not(any(unix, windows)))]
+impl LocaleGuard {
+    pub(crate) fn new(locale: NumericLocale) -> Result<Option<Self>, GError> {
+        match locale {
+            NumericLocale::System => Ok(None),
+            _ => Err(GError::Locale),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Flags, GPoint, Notation, Options};
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn locales() {
+        for (locale, num, res) in [
+            (NumericLocale::System, 1.5, Ok("1.5")),
+            (NumericLocale::C, 1.5, Ok("1.5")),
+            (NumericLocale::C, -2.5e-10, Ok("-2.5e-10")),
+            (NumericLocale::Named("C"), 1.5, Ok("1.5")),
+            (NumericLocale::Named("C"), 1e100, Ok("1e+100")),
+            (
+                NumericLocale::Named("xx_XX.nonexistent"),
+                1.5,
+                Err(GError::Locale),
+            ),
+            (NumericLocale::Named("C\0"), 1.5, Err(GError::Locale)),
+        ] {
+            let options = Options::new().locale(locale);
+            assert_eq!(
+                GPoint(num).with(options).try_to_string(),
+                res.map(str::to_owned),
+                "{:?}",
+                locale
+            );
+        }
+    }
+
+    #[test]
+    fn decimal_comma() {
+        // as printed by glibc in the `fr_FR` locale
+        let mut numstr = *b"1,25e+04\0\0\0\0";
+        let mut digits = [0; 12];
+        let options = Options::new().notation(Notation::Engineering);
+        let len = crate::rewrite::rewrite(
+            &mut numstr,
+            &mut digits,
+            8,
+            1.25e4,
+            &Flags::default(),
+            &options,
+        );
+        assert_eq!(len.map(|len| &numstr[..len]), Ok(&b"12,5e+03"[..]));
+    }
+}
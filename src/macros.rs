@@ -0,0 +1,155 @@
+//! `print!`-like macros formatting floats the `printf("%g")` way
+
+/// Like `format!`, but `f64` and `f32` arguments are displayed with `{}` as
+/// a [`GPoint`](crate::GPoint) would; other arguments, and floats
+/// formatted with `{:?}`, `{:e}` or `{:E}`, are formatted as usual.
+///
+/// Only positional arguments are wrapped, not named nor captured ones.
+///
+/// ```
+/// use gpoint::gformat;
+///
+/// assert!(gformat!("{} {:.3} {}", 42., 1.2345f32, "ok") == "42 1.23 ok");
+/// assert!(gformat!("{:?} {:e}", 2.5, 1500.) == "2.5 1.5e3");
+/// ```
+#[macro_export]
+macro_rules! gformat {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        {
+            #[allow(unused_imports)]
+            use $crate::__private::{WrapAny as _, WrapFloat as _};
+            ::std::format!($fmt $(, (&&$crate::__private::Tag(&$arg)).__gwrap())*)
+        }
+    };
+}
+
+/// Like `print!`, but wraps `f64` and `f32` arguments in a
+/// [`GPoint`](crate::GPoint), see [`gformat!`].
+///
+/// ```
+/// use gpoint::gprint;
+///
+/// gprint!("{}\n", 1e100);
+/// ```
+#[macro_export]
+macro_rules! gprint {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        {
+            #[allow(unused_imports)]
+            use $crate::__private::{WrapAny as _, WrapFloat as _};
+            ::std::print!($fmt $(, (&&$crate::__private::Tag(&$arg)).__gwrap())*)
+        }
+    };
+}
+
+/// Like `println!`, but wraps `f64` and `f32` arguments in a
+/// [`GPoint`](crate::GPoint), see [`gformat!`].
+///
+/// ```
+/// use gpoint::gprintln;
+///
+/// gprintln!("answer: {}", 42.);
+/// ```
+#[macro_export]
+macro_rules! gprintln {
+    () => {
+        ::std::println!()
+    };
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        {
+            #[allow(unused_imports)]
+            use $crate::__private::{WrapAny as _, WrapFloat as _};
+            ::std::println!($fmt $(, (&&$crate::__private::Tag(&$arg)).__gwrap())*)
+        }
+    };
+}
+
+/// Support for the macros, picking a `GPoint` wrapper for floats by
+/// autoref-based specialization: method lookup tries `&&Tag` before `&Tag`.
+#[doc(hidden)]
+pub mod __private {
+    use crate::GPoint;
+    use std::fmt;
+
+    pub struct Tag<'a, T: ?Sized>(pub &'a T);
+
+    /// A float displayed as a `GPoint`, and formatted as itself otherwise.
+    pub struct Float<F>(F);
+
+    impl<F: Copy + Into<f64>> fmt::Display for Float<F> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(&GPoint(self.0), f)
+        }
+    }
+
+    impl<F: fmt::Debug> fmt::Debug for Float<F> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fmt(f)
+        }
+    }
+
+    impl<F: fmt::LowerExp> fmt::LowerExp for Float<F> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fmt(f)
+        }
+    }
+
+    impl<F: fmt::UpperExp> fmt::UpperExp for Float<F> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fmt(f)
+        }
+    }
+
+    pub trait WrapFloat {
+        type Output;
+        fn __gwrap(self) -> Self::Output;
+    }
+
+    impl WrapFloat for &&Tag<'_, f64> {
+        type Output = Float<f64>;
+        fn __gwrap(self) -> Float<f64> {
+            Float(*self.0)
+        }
+    }
+
+    impl WrapFloat for &&Tag<'_, f32> {
+        type Output = Float<f32>;
+        fn __gwrap(self) -> Float<f32> {
+            Float(*self.0)
+        }
+    }
+
+    pub trait WrapAny<'a, T: ?Sized> {
+        fn __gwrap(self) -> &'a T;
+    }
+
+    impl<'a, T: ?Sized> WrapAny<'a, T> for &Tag<'a, T> {
+        fn __gwrap(self) -> &'a T {
+            self.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn gformat() {
+        let x = 1e-10;
+        let v = vec![1.5];
+        assert_eq!(
+            crate::gformat!("{} {:+.3} {:8} {} {:?} {}", x, 1.2345f32, 42., "s", v, 7),
+            "1e-10 +1.23       42 s [1.5] 7"
+        );
+        assert_eq!(crate::gformat!("{0}/{0}", 0.1 + 0.2), "0.3/0.3");
+        // only `Display` is the `%g` way
+        assert_eq!(crate::gformat!("{:?} {:?}", 2.5f64, 0.1f32), "2.5 0.1");
+        assert_eq!(
+            crate::gformat!("{:e} {:.2E} {:>8e}", 1500., 1234.5f32, 0.25),
+            "1.5e3 1.23E3   2.5e-1"
+        );
+        assert_eq!(
+            crate::gformat!("{0} {0:?}", 0.1 + 0.2),
+            "0.3 0.30000000000000004"
+        );
+    }
+}
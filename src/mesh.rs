@@ -0,0 +1,128 @@
+//! Mesh export, e.g. OBJ or ASCII PLY files
+
+use crate::{GWriter, Options, Spec};
+use std::io::{self, Write};
+
+/// A buffered writer streaming mesh data into any `io::Write`, every number
+/// formatted with the same [`Spec`] and separated by the same separator, like
+/// the `fprintf()` loops of C exporters do.
+///
+/// ```
+/// use gpoint::{MeshWriter, Spec};
+///
+/// let mut obj = MeshWriter::new(Vec::new(), Spec::parse("%f").unwrap());
+/// obj.vertex(&[0.5, 1., -2.])?;
+/// obj.normal(&[0., 0., 1.])?;
+/// let obj = obj.into_inner()?;
+/// assert!(obj == b"v 0.500000 1.000000 -2.000000\nvn 0.000000 0.000000 1.000000\n");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct MeshWriter<W: Write> {
+    inner: GWriter<W>,
+    spec: Spec,
+    separator: &'static str,
+}
+
+impl<W: Write> MeshWriter<W> {
+    /// Wraps `inner`, formatting numbers with `spec` separated by spaces.
+    pub fn new(inner: W, spec: Spec) -> Self {
+        MeshWriter {
+            inner: GWriter::new(inner),
+            spec,
+            separator: " ",
+        }
+    }
+
+    /// Sets the separator written between numbers, and after OBJ tags.
+    pub fn separator(mut self, separator: &'static str) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Sets the [`Options`] numbers are formatted with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.inner = self.inner.with(options);
+        self
+    }
+
+    /// Writes an OBJ vertex line, `v` and its coordinates.
+    pub fn vertex(&mut self, coords: &[f64]) -> io::Result<()> {
+        self.tagged("v", coords)
+    }
+
+    /// Writes an OBJ vertex normal line, `vn` and its coordinates.
+    pub fn normal(&mut self, coords: &[f64]) -> io::Result<()> {
+        self.tagged("vn", coords)
+    }
+
+    /// Writes an OBJ texture coordinates line, `vt` and its coordinates.
+    pub fn texture(&mut self, coords: &[f64]) -> io::Result<()> {
+        self.tagged("vt", coords)
+    }
+
+    /// Writes a line of numbers and nothing else, e.g. a vertex of an ASCII
+    /// PLY file.
+    pub fn row(&mut self, values: &[f64]) -> io::Result<()> {
+        self.numbers(values)?;
+        self.inner.write_sep("\n")
+    }
+
+    /// Writes a line per item of `rows`, e.g. all the vertices of a PLY file.
+    pub fn rows<R: AsRef<[f64]>>(&mut self, rows: &[R]) -> io::Result<()> {
+        rows.iter().try_for_each(|row| self.row(row.as_ref()))
+    }
+
+    /// Writes anything else, e.g. headers or faces.
+    pub fn write_str(&mut self, text: &str) -> io::Result<()> {
+        self.inner.write_sep(text)
+    }
+
+    /// Flushes the buffered output into the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Flushes the buffered output and returns the underlying writer.
+    pub fn into_inner(self) -> io::Result<W> {
+        self.inner.into_inner()
+    }
+
+    fn tagged(&mut self, tag: &str, values: &[f64]) -> io::Result<()> {
+        self.inner.write_sep(tag)?;
+        if !values.is_empty() {
+            self.inner.write_sep(self.separator)?;
+        }
+        self.row(values)
+    }
+
+    fn numbers(&mut self, values: &[f64]) -> io::Result<()> {
+        for (i, &value) in values.iter().enumerate() {
+            if i > 0 {
+                self.inner.write_sep(self.separator)?;
+            }
+            self.inner.write_g_spec(value, &self.spec)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn ply() {
+        let spec = Spec::parse("%g").unwrap();
+        let mut ply = MeshWriter::new(Vec::new(), spec).separator("\t");
+        ply.write_str("ply\nformat ascii 1.0\nelement vertex 2\nend_header\n")
+            .unwrap();
+        ply.rows(&[[0.1, 1e-7, 3.], [-1., 2.5, 1e21]]).unwrap();
+        ply.texture(&[0.25, 0.75]).unwrap();
+        ply.row(&[]).unwrap();
+        assert_eq!(
+            String::from_utf8(ply.into_inner().unwrap()).unwrap(),
+            "ply\nformat ascii 1.0\nelement vertex 2\nend_header\n\
+             0.1\t1e-07\t3\n-1\t2.5\t1e+21\nvt\t0.25\t0.75\n\n"
+        );
+    }
+}
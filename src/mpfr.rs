@@ -0,0 +1,241 @@
+//! Arbitrary precision floats of the MPFR library, such as `rug::Float`
+
+use crate::{finish, locale, Flags, GConfig, GError, GPoint, GPointWith, Options};
+use libc::{c_char, c_int, c_void, size_t};
+use std::fmt::{self, Write};
+use std::marker::PhantomData;
+
+#[link(name = "mpfr")]
+extern "C" {
+    fn mpfr_snprintf(buf: *mut c_char, n: size_t, template: *const c_char, ...) -> c_int;
+    fn mpfr_nan_p(x: *const c_void) -> c_int;
+    fn mpfr_inf_p(x: *const c_void) -> c_int;
+    fn mpfr_signbit(x: *const c_void) -> c_int;
+}
+
+/// A borrowed MPFR number, e.g. `rug::Float::as_raw()`, formatted at any
+/// precision by MPFR's own `mpfr_snprintf("%Rg")` instead of being rounded
+/// to an `f64` first: the precision, the `#` and `+` flags, and the
+/// [`Options`] rewriting, width and alignment are the ones of `GPoint`.
+///
+/// ```
+/// # fn pi(digits: usize) -> String {
+/// # unsafe {
+/// #     #[repr(C)]
+/// #     struct Mpfr([u64; 4]);
+/// #     extern "C" {
+/// #         fn mpfr_init2(x: *mut Mpfr, prec: libc::c_long);
+/// #         fn mpfr_const_pi(x: *mut Mpfr, rnd: libc::c_int) -> libc::c_int;
+/// #         fn mpfr_clear(x: *mut Mpfr);
+/// #     }
+/// #     let mut raw = std::mem::MaybeUninit::<Mpfr>::uninit();
+/// #     mpfr_init2(raw.as_mut_ptr(), 256);
+/// #     mpfr_const_pi(raw.as_mut_ptr(), 0);
+/// // e.g. `let raw = float.as_raw();` for a `rug::Float`
+/// let pi = gpoint::GMpfr::from_raw(raw.as_ptr().cast());
+/// let formatted = format!("{:.*}", digits, gpoint::GPoint(pi));
+/// #     mpfr_clear(raw.as_mut_ptr());
+/// #     formatted
+/// # }
+/// # }
+/// assert!(pi(6) == "3.14159");
+/// assert!(pi(40) == "3.141592653589793238462643383279502884197");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct GMpfr<'a> {
+    raw: *const c_void,
+    lifetime: PhantomData<&'a c_void>,
+}
+
+impl GMpfr<'_> {
+    /// Borrows the `mpfr_t` at `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to an initialized `mpfr_t`, which isn't modified or
+    /// cleared as long as the returned value lives.
+    pub unsafe fn from_raw(raw: *const c_void) -> Self {
+        GMpfr {
+            raw,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// An `f64` with the sign and the class of the number, for the rewriting
+    /// of non-finite values and of signs.
+    fn class(self) -> f64 {
+        let (nan, inf, negative) = unsafe {
+            (
+                mpfr_nan_p(self.raw) != 0,
+                mpfr_inf_p(self.raw) != 0,
+                mpfr_signbit(self.raw) != 0,
+            )
+        };
+        let class = if nan {
+            f64::NAN
+        } else if inf {
+            f64::INFINITY
+        } else {
+            1.
+        };
+        if negative {
+            -class
+        } else {
+            class
+        }
+    }
+}
+
+/// Writes `value` with `mpfr_snprintf()` into `numstr`, returning its length.
+fn snprintf_g(numstr: &mut [u8], value: GMpfr<'_>, template: &[u8]) -> Result<usize, GError> {
+    crate::error::clear_errno();
+    let nbchars = unsafe {
+        mpfr_snprintf(
+            numstr.as_mut_ptr() as *mut c_char,
+            numstr.len(),
+            template.as_ptr() as *const c_char,
+            value.raw,
+        )
+    };
+    if nbchars < 0 {
+        return Err(GError::libc());
+    }
+    Ok(nbchars as usize)
+}
+
+fn fmt_mpfr(f: &mut fmt::Formatter<'_>, value: GMpfr<'_>, options: &Options) -> fmt::Result {
+    let mut flags = Flags::from_formatter(f);
+    flags.precision = flags.precision.or(options.precision);
+    let class = value.class();
+
+    let mut template = String::from("%");
+    if flags.alternate {
+        template.push('#');
+    }
+    if flags.plus {
+        template.push('+');
+    }
+    if let Some(precision) = flags.precision.filter(|_| class.is_finite()) {
+        write!(template, ".{}", precision)?;
+    }
+    template.push_str("Rg\0");
+
+    let _locale = locale::LocaleGuard::new(options.locale)?;
+    let len = snprintf_g(&mut [], value, template.as_bytes())?;
+    // the output is at most rewritten with 4-byte separators between digits
+    let size = 2 * (4 * (len + 16) + options.min_fraction_digits + options.min_exponent_digits)
+        + 4 * flags.width.unwrap_or(0);
+    let mut scratch = vec![0; 2 * size];
+    let (numstr, digits) = scratch.split_at_mut(size);
+    let len = snprintf_g(numstr, value, template.as_bytes())?;
+    let len = finish(numstr, digits, len, class, &flags, options)?;
+    f.write_str(std::str::from_utf8(&numstr[..len]).map_err(|_| fmt::Error)?)
+}
+
+impl fmt::Display for GPoint<GMpfr<'_>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_mpfr(f, self.0, &GConfig::get())
+    }
+}
+
+impl fmt::Display for GPointWith<GMpfr<'_>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_mpfr(f, self.value, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libc::c_long;
+    use std::mem::MaybeUninit;
+
+    /// `mpfr_t` on LP64 platforms.
+    #[repr(C)]
+    struct Mpfr {
+        prec: c_long,
+        sign: c_int,
+        exp: c_long,
+        d: *mut c_void,
+    }
+
+    extern "C" {
+        fn mpfr_init2(x: *mut Mpfr, prec: c_long);
+        fn mpfr_set_str(x: *mut Mpfr, s: *const c_char, base: c_int, rnd: c_int) -> c_int;
+        fn mpfr_clear(x: *mut Mpfr);
+    }
+
+    /// Formats `number`, parsed with `bits` of precision, with `f`.
+    fn with_mpfr(number: &str, bits: c_long, f: impl FnOnce(GMpfr<'_>) -> String) -> String {
+        let number = format!("{}\0", number);
+        let mut raw = MaybeUninit::<Mpfr>::uninit();
+        unsafe {
+            mpfr_init2(raw.as_mut_ptr(), bits);
+            mpfr_set_str(raw.as_mut_ptr(), number.as_ptr() as *const c_char, 10, 0);
+            let formatted = f(GMpfr::from_raw(raw.as_ptr().cast()));
+            mpfr_clear(raw.as_mut_ptr());
+            formatted
+        }
+    }
+
+    #[test]
+    fn mpfr() {
+        let third = "0.33333333333333333333333333333333333333333333333333";
+        for (number, bits, precision, res) in [
+            (
+                third,
+                256,
+                Some(40),
+                "0.3333333333333333333333333333333333333333",
+            ),
+            (third, 53, Some(20), "0.33333333333333331483"),
+            (third, 256, None, "0.333333"),
+            ("1e-100000", 64, None, "1e-100000"),
+            (
+                "-12345678901234567890",
+                128,
+                Some(20),
+                "-12345678901234567890",
+            ),
+            (
+                "-12345678901234567890",
+                128,
+                Some(19),
+                "-1.234567890123456789e+19",
+            ),
+            ("100000", 53, None, "100000"),
+            ("1000000", 53, None, "1e+06"),
+            ("-0", 53, None, "-0"),
+            ("nan", 53, Some(3), "nan"),
+            ("-inf", 53, None, "-inf"),
+        ] {
+            let formatted = with_mpfr(number, bits, |num| match precision {
+                Some(p) => format!("{:.*}", p, GPoint(num)),
+                None => format!("{}", GPoint(num)),
+            });
+            assert_eq!(formatted, res, "{} with {} bits", number, bits);
+        }
+        let formatted = with_mpfr("1.5", 53, |num| format!("{:+#10.3}|", GPoint(num)));
+        assert_eq!(formatted, "     +1.50|");
+        let options = Options::new().grouping(',').typographic_minus(true);
+        let formatted = with_mpfr("-1234567.5", 53, |num| {
+            format!("{:.10}", GPoint(num).with(options))
+        });
+        assert_eq!(formatted, "\u{2212}1,234,567.5");
+        // the same as `f64`, given all the digits of subnormals, and
+        // except for the sign of NaNs, which isn't parsed
+        for &num in crate::testing::corpus().iter().filter(|num| !num.is_nan()) {
+            for precision in [1, 6, 17] {
+                let formatted = with_mpfr(&format!("{:.800e}", num), 53, |mpfr| {
+                    format!("{:.*}", precision, GPoint(mpfr))
+                });
+                assert_eq!(
+                    formatted,
+                    format!("{:.*}", precision, GPoint(num)),
+                    "{:e}",
+                    num
+                );
+            }
+        }
+    }
+}
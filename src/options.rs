@@ -0,0 +1,1191 @@
+//! Options tuning how numbers are formatted
+
+use crate::{Backend, GError, NumericLocale, RoundingMode, MINUS_SIGN};
+
+/// Options tuning how a [`GPoint`](crate::GPoint) is formatted, on top of the
+/// flags given in the format string.
+///
+/// The defaults give the same output as the underlying `libc`'s `printf()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub(crate) backend: Backend,
+    pub(crate) rounding: Option<RoundingMode>,
+    pub(crate) deterministic_rounding: bool,
+    pub(crate) negative_zero: NegativeZero,
+    pub(crate) nan: &'static str,
+    pub(crate) inf: &'static str,
+    pub(crate) non_finite: NonFinite,
+    pub(crate) uppercase_non_finite: bool,
+    pub(crate) uppercase_exponent: bool,
+    pub(crate) exponent_char: Option<char>,
+    pub(crate) unsigned_nan: bool,
+    pub(crate) notation: Notation,
+    pub(crate) exponent: ExponentStyle,
+    pub(crate) typographic_minus: bool,
+    pub(crate) grouping: Option<char>,
+    pub(crate) min_fraction_digits: usize,
+    pub(crate) min_exponent_digits: usize,
+    pub(crate) zero_padding: ZeroPadding,
+    pub(crate) precision: Option<usize>,
+    pub(crate) on_error: OnError,
+    pub(crate) locale: NumericLocale,
+    pub(crate) shortest: Shortest,
+}
+
+/// How to print negative zero, see [`Options::negative_zero()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeZero {
+    /// Print it the way the backend does, i.e. `-0` for glibc.
+    #[default]
+    AsIs,
+    /// Print it as a positive zero, i.e. `0`.
+    Normalize,
+    /// Always print it with its sign, i.e. `-0`.
+    Preserve,
+}
+
+/// How to output non-finite values, see [`Options::non_finite()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Print them with their spelling, i.e. `nan`, `inf` or `-inf` by default.
+    #[default]
+    AsIs,
+    /// Print them as `null`, as JSON would have it.
+    Null,
+    /// Print them with their spelling between double quotes, e.g. `"-inf"`.
+    Quoted,
+    /// Fail formatting them.
+    Error,
+}
+
+/// How numbers are written, see [`Options::notation()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Notation {
+    /// The plain `printf("%g")` output.
+    #[default]
+    General,
+    /// A percentage: the number is multiplied by 100 and followed by a `%`
+    /// sign. Non-finite values are printed as is.
+    Percent {
+        /// Use `%f` instead of `%g`, i.e. the precision is the number of
+        /// decimals instead of the number of significant digits.
+        fixed: bool,
+        /// Put a space between the number and the `%` sign.
+        space: bool,
+    },
+    /// Engineering notation: like `%g`, but exponents are always multiples of
+    /// 3, e.g. `12.5e+03` instead of `1.25e+04`.
+    Engineering,
+    /// Exponents multiples of 3 written as SI prefixes, e.g. `1.25k` or
+    /// `3.3µ`. The precision is the number of significant digits, the
+    /// exponent is used when there is no prefix for it.
+    Si,
+    /// The exact decimal value of the number, with all its digits and
+    /// without exponent, e.g. `0.1000000000000000055511151231257827021181583404541015625`
+    /// for `0.1`. The precision is ignored.
+    Exact,
+    /// Decimals as CNC controllers read them in G-code: like `%f`, the
+    /// precision is the number of decimals, and there is never an exponent.
+    GCode {
+        /// Remove the trailing zeros after the decimal point, and the point
+        /// itself if nothing is left after it, unless the `#` flag is given.
+        trim: bool,
+    },
+    /// Go's `strconv.FormatFloat(x, 'g', -1, 64)`, as used by Prometheus:
+    /// without precision, the shortest digits parsing back to the number,
+    /// with an exponent from `1e+06` on, e.g. `123456` but `1.234567e+06`.
+    /// Non-finite values are spelled `NaN`, `+Inf` and `-Inf`. With a
+    /// precision, like `%g`.
+    Go,
+    /// .NET's `ToString("R")`, its default since .NET Core 3.0: without
+    /// precision, the shortest digits parsing back to the number, with an
+    /// exponent from `1E+15` on, e.g. `123456789012345` but `1E+15`. With a
+    /// precision, `ToString("G17")` and the like, i.e. `%G`. Non-finite
+    /// values are spelled `NaN`, `Infinity` and `-Infinity`, as with the
+    /// invariant culture.
+    DotNet,
+    /// Excel's General format, as cells of the default width display typed
+    /// numbers: at most 11 characters and signs, with up to 6 significant
+    /// digits for exponents, e.g. `0.333333333` or `1.23457E+11`. The
+    /// precision is ignored.
+    Excel,
+    /// Floats as TOML reads them: like `%g`, with a decimal point and a
+    /// digit after it when there is no exponent, e.g. `1.0` or `1e+06` but
+    /// never `1` nor `1.`. Non-finite values are spelled `nan` and `inf`.
+    /// Combined with [`Options::grouping()`], `_` separates thousands.
+    Toml,
+    /// Floats as JSON5 reads them: like [`Notation::Toml`], but non-finite
+    /// values are spelled `NaN` and `Infinity`.
+    Json5,
+    /// Floats as YAML 1.2's core schema resolves them: like
+    /// [`Notation::Toml`], so that they aren't read as integers, but with a
+    /// fraction digit even before canonical exponents without leading zeros,
+    /// e.g. `1.0` or `1.0e+6`. Non-finite values are spelled `.nan`, `.inf` and `-.inf`.
+    Yaml,
+    /// XML Schema's canonical representation of `xs:double`: a single nonzero
+    /// digit before the decimal point, at least one after it, and an exponent
+    /// after `E` without `+` sign nor leading zeros, e.g. `1.25E2` or
+    /// `0.0E0`. Without precision, the shortest digits parsing back to the
+    /// number, else that many significant digits. Non-finite values are
+    /// spelled `NaN`, `INF` and `-INF`.
+    Xsd,
+}
+
+/// How the shortest digits parsing back to numbers are found, for the
+/// notations printing them, see [`Options::shortest()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Shortest {
+    /// Rust's own algorithm, the fastest available: Grisu, falling back to
+    /// Dragon4 for the rare numbers it can't handle.
+    #[default]
+    Grisu,
+    /// Dragon4 on big integers: slow, but simple enough to be trusted as a
+    /// reference when verifying the other algorithms.
+    Dragon4,
+}
+
+/// How exponents are written, see [`Options::exponent()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExponentStyle {
+    /// The `printf()` way, e.g. `1.5e-03`.
+    #[default]
+    Printf,
+    /// LaTeX math, e.g. `1.5\times10^{-3}`.
+    Latex,
+    /// The whole number as an argument to siunitx's `\num{}`, e.g.
+    /// `\num{1.5e-3}`.
+    Siunitx,
+    /// Unicode superscripts, e.g. `1.5×10⁻³`.
+    Superscript,
+    /// The `printf()` way without `+` signs nor leading zeros, e.g. `1.5e-3`
+    /// or `1e5`.
+    Compact,
+    /// gnuplot's enhanced text markup, e.g. `1.5{\times}10^{-3}`.
+    Gnuplot,
+}
+
+/// How the `0` flag pads numbers, see [`Options::zero_padding()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ZeroPadding {
+    /// The `printf()` way: left alignment wins over the `0` flag, and
+    /// non-finite values are padded with spaces.
+    #[default]
+    Printf,
+    /// The way Rust pads its own floats: the `0` flag wins over any
+    /// alignment, and non-finite values are padded with zeros too.
+    Rust,
+}
+
+/// What to do when formatting fails, see [`Options::on_error()`].
+#[derive(Debug, Default, Clone, Copy, Eq)]
+pub enum OnError {
+    /// Report the failure: `Display` returns a `fmt::Error`, making
+    /// `format!` panic.
+    #[default]
+    Fail,
+    /// Fall back to Rust's own `Display` output for floats.
+    Rust,
+    /// Fall back to Rust's own `Display` output for floats, after calling
+    /// the given function, e.g. to record that this happened.
+    RustAndNotify(fn(GError)),
+}
+
+impl PartialEq for OnError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (OnError::RustAndNotify(a), OnError::RustAndNotify(b)) => std::ptr::fn_addr_eq(*a, *b),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Options {
+    /// Default options: forward everything to `libc`.
+    pub const fn new() -> Self {
+        Options {
+            backend: Backend::Libc,
+            rounding: None,
+            deterministic_rounding: false,
+            negative_zero: NegativeZero::AsIs,
+            nan: "nan",
+            inf: "inf",
+            non_finite: NonFinite::AsIs,
+            uppercase_non_finite: false,
+            uppercase_exponent: false,
+            exponent_char: None,
+            unsigned_nan: false,
+            notation: Notation::General,
+            exponent: ExponentStyle::Printf,
+            typographic_minus: false,
+            grouping: None,
+            min_fraction_digits: 0,
+            min_exponent_digits: 2,
+            zero_padding: ZeroPadding::Printf,
+            precision: None,
+            on_error: OnError::Fail,
+            locale: NumericLocale::System,
+            shortest: Shortest::Grisu,
+        }
+    }
+
+    /// Emulates glibc's `printf("%g")` in pure Rust instead of calling the
+    /// platform's `libc`, so that output is byte-identical whatever the OS or
+    /// C library (glibc, musl, BSD libc, Windows CRT).
+    ///
+    /// This is a shortcut for selecting [`Backend::Pure`].
+    pub const fn consistent(self, consistent: bool) -> Self {
+        self.backend(if consistent {
+            Backend::Pure
+        } else {
+            Backend::Libc
+        })
+    }
+
+    /// Selects the engine formatting numbers.
+    pub const fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Sets the given rounding mode while formatting, instead of following the
+    /// calling thread's current one. This has no effect on [`Backend::Pure`],
+    /// which always rounds to nearest.
+    pub const fn rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = Some(rounding);
+        self
+    }
+
+    /// Rounds to nearest, ties to even, whatever the rounding mode of the
+    /// calling thread: the C library is called in the default rounding mode,
+    /// or if it can't be set on this platform, numbers are formatted by
+    /// [`Backend::Pure`] instead. This takes precedence over
+    /// [`Options::rounding()`]; custom backends are left alone.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options, RoundingGuard, RoundingMode};
+    ///
+    /// let deterministic = Options::new().deterministic_rounding(true);
+    /// let _guard = RoundingGuard::new(RoundingMode::Upward);
+    /// assert!(format!("{:.3}", GPoint(1.0001).with(deterministic)) == "1");
+    /// ```
+    pub const fn deterministic_rounding(mut self, deterministic: bool) -> Self {
+        self.deterministic_rounding = deterministic;
+        self
+    }
+
+    /// Chooses how negative zero is printed.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, NegativeZero, Options};
+    ///
+    /// let normalize = Options::new().negative_zero(NegativeZero::Normalize);
+    /// assert!(format!("{:+.3}", GPoint(-0.).with(normalize)) == "+0");
+    /// ```
+    pub const fn negative_zero(mut self, negative_zero: NegativeZero) -> Self {
+        self.negative_zero = negative_zero;
+        self
+    }
+
+    /// Spells NaN values with the given string instead of `nan`. Any sign the
+    /// backend printed for the NaN is kept.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().nan("NaN").inf("Infinity");
+    /// assert!(format!("{}", GPoint(f64::NAN).with(options)) == "NaN");
+    /// assert!(format!("{}", GPoint(-f64::INFINITY).with(options)) == "-Infinity");
+    /// ```
+    pub const fn nan(mut self, nan: &'static str) -> Self {
+        self.nan = nan;
+        self
+    }
+
+    /// Spells infinite values with the given string instead of `inf`, after
+    /// their sign.
+    pub const fn inf(mut self, inf: &'static str) -> Self {
+        self.inf = inf;
+        self
+    }
+
+    /// Chooses how non-finite values are output, e.g. to always produce valid
+    /// JSON.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, NonFinite, Options};
+    /// use std::fmt::Write;
+    ///
+    /// let json = Options::new().non_finite(NonFinite::Null);
+    /// assert!(format!("[{}]", GPoint(f64::NAN).with(json)) == "[null]");
+    /// let strict = Options::new().non_finite(NonFinite::Error);
+    /// assert!(write!(String::new(), "{}", GPoint(f64::NAN).with(strict)).is_err());
+    /// ```
+    pub const fn non_finite(mut self, non_finite: NonFinite) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// Prints the spelling of non-finite values in uppercase, e.g. `INF` and
+    /// `NAN`, independently of the exponent's case.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().uppercase_non_finite(true);
+    /// assert!(format!("{}", GPoint(-f64::INFINITY).with(options)) == "-INF");
+    /// assert!(format!("{}", GPoint(1e100).with(options)) == "1e+100");
+    /// ```
+    pub const fn uppercase_non_finite(mut self, uppercase: bool) -> Self {
+        self.uppercase_non_finite = uppercase;
+        self
+    }
+
+    /// Prints the exponent marker in uppercase, i.e. `E`, independently of
+    /// the case of non-finite values' spelling.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().uppercase_exponent(true);
+    /// assert!(format!("{}", GPoint(1e100).with(options)) == "1E+100");
+    /// assert!(format!("{}", GPoint(f64::NAN).with(options)) == "nan");
+    /// ```
+    pub const fn uppercase_exponent(mut self, uppercase: bool) -> Self {
+        self.uppercase_exponent = uppercase;
+        self
+    }
+
+    /// Prints `marker` instead of `e` or `E` before exponents written the
+    /// `printf()` way or the [compact](ExponentStyle::Compact) way, whatever
+    /// the notation and case, e.g. `D` for Fortran's double precision
+    /// literals.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Notation, Options};
+    ///
+    /// let fortran = Options::new().exponent_char('D');
+    /// assert!(format!("{:.3}", GPoint(1.5e-10).with(fortran)) == "1.5D-10");
+    /// let engineering = Options::new().notation(Notation::Engineering).exponent_char('^');
+    /// assert!(format!("{}", GPoint(1.25e7).with(engineering)) == "12.5^+06");
+    /// ```
+    pub const fn exponent_char(mut self, marker: char) -> Self {
+        self.exponent_char = Some(marker);
+        self
+    }
+
+    /// Never prints a sign for NaN values, which may carry one depending on
+    /// the platform and the operations that produced them. Infinities keep
+    /// their sign.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().unsigned_nan(true);
+    /// assert!(format!("{}", GPoint(-f64::NAN).with(options)) == "nan");
+    /// assert!(format!("{}", GPoint(-f64::INFINITY).with(options)) == "-inf");
+    /// ```
+    pub const fn unsigned_nan(mut self, unsigned: bool) -> Self {
+        self.unsigned_nan = unsigned;
+        self
+    }
+
+    /// Chooses how numbers are written.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Notation, Options};
+    ///
+    /// let percent = Options::new().notation(Notation::Percent {
+    ///     fixed: true,
+    ///     space: false,
+    /// });
+    /// assert!(format!("{:.1}", GPoint(0.1234).with(percent)) == "12.3%");
+    /// ```
+    pub const fn notation(mut self, notation: Notation) -> Self {
+        self.notation = notation;
+        self
+    }
+
+    /// Chooses how exponents are written.
+    ///
+    /// ```
+    /// use gpoint::{ExponentStyle, GPoint, Options};
+    ///
+    /// let latex = Options::new().exponent(ExponentStyle::Latex);
+    /// assert!(format!("${}$", GPoint(1.5e-5).with(latex)) == r"$1.5\times10^{-5}$");
+    /// ```
+    pub const fn exponent(mut self, exponent: ExponentStyle) -> Self {
+        self.exponent = exponent;
+        self
+    }
+
+    /// Uses U+2212 MINUS SIGN instead of the ASCII hyphen for negative
+    /// numbers and exponents, as typographers would have it.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().typographic_minus(true);
+    /// assert!(format!("{}", GPoint(-1.5e-10).with(options)) == "\u{2212}1.5e\u{2212}10");
+    /// ```
+    pub const fn typographic_minus(mut self, typographic: bool) -> Self {
+        self.typographic_minus = typographic;
+        self
+    }
+
+    /// Separates groups of three integer digits with the given character,
+    /// e.g. a thin space for SI style or `_` for Rust literals.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().grouping('_');
+    /// assert!(format!("{:.10}", GPoint(1234567.25).with(options)) == "1_234_567.25");
+    /// ```
+    pub const fn grouping(mut self, separator: char) -> Self {
+        self.grouping = Some(separator);
+        self
+    }
+
+    /// Prints at least `digits` digits after the decimal point, completing
+    /// with zeros what `%g` trimmed, while keeping its choice between fixed
+    /// and scientific notations.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().min_fraction_digits(2);
+    /// assert!(format!("{}", GPoint(1.5).with(options)) == "1.50");
+    /// assert!(format!("{}", GPoint(1.2345).with(options)) == "1.2345");
+    /// assert!(format!("{}", GPoint(1e10).with(options)) == "1.00e+10");
+    /// ```
+    pub const fn min_fraction_digits(mut self, digits: usize) -> Self {
+        self.min_fraction_digits = digits;
+        self
+    }
+
+    /// Prints `printf()` style exponents with at least `digits` digits
+    /// instead of 2, completing with zeros, so that columns of numbers with
+    /// exponents of any magnitude align.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().min_exponent_digits(3);
+    /// assert!(format!("{:>9}", GPoint(1.5e-5).with(options)) == " 1.5e-005");
+    /// assert!(format!("{:>9}", GPoint(2e300).with(options)) == "   2e+300");
+    /// ```
+    pub const fn min_exponent_digits(mut self, digits: usize) -> Self {
+        self.min_exponent_digits = digits;
+        self
+    }
+
+    /// Chooses how the `0` flag pads numbers, e.g. to line up with columns of
+    /// plain Rust floats.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options, ZeroPadding};
+    ///
+    /// let rust = Options::new().zero_padding(ZeroPadding::Rust);
+    /// assert!(format!("{:<08}", GPoint(-1.5).with(rust)) == "-00001.5");
+    /// assert!(format!("{:06}", GPoint(-f64::INFINITY).with(rust)) == "-00inf");
+    /// ```
+    pub const fn zero_padding(mut self, zero_padding: ZeroPadding) -> Self {
+        self.zero_padding = zero_padding;
+        self
+    }
+
+    /// Sets the precision used when the format string doesn't give one, see
+    /// also [`GPointP`](crate::GPointP).
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Options};
+    ///
+    /// let options = Options::new().precision(3);
+    /// assert!(format!("{}", GPoint(1.2345).with(options)) == "1.23");
+    /// assert!(format!("{:.2}", GPoint(1.2345).with(options)) == "1.2");
+    /// ```
+    pub const fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Chooses what to do when formatting fails, e.g. because the C library
+    /// did: numbers in log lines are better in another format than lost.
+    /// Non-finite values refused by [`NonFinite::Error`] are still refused.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, OnError, Options, Spec};
+    ///
+    /// let options = Options::new().on_error(OnError::Rust);
+    /// let spec = Spec::parse("%#.3000000000g").unwrap();
+    /// assert!(GPoint(0.1).with(options).try_to_string_with(&spec) == Ok("0.1".to_string()));
+    /// ```
+    pub const fn on_error(mut self, on_error: OnError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Selects the `LC_NUMERIC` locale the C library formats numbers in,
+    /// instead of whatever `setlocale()` selected for the whole program.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, NumericLocale, Options};
+    ///
+    /// let c = Options::new().locale(NumericLocale::C);
+    /// assert!(format!("{}", GPoint(1.5).with(c)) == "1.5");
+    /// ```
+    pub const fn locale(mut self, locale: NumericLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Selects the algorithm finding the shortest digits parsing back to
+    /// numbers, as notations like [`Notation::Go`] print without precision.
+    ///
+    /// ```
+    /// use gpoint::{GPoint, Notation, Options, Shortest};
+    ///
+    /// let go = Options::new().notation(Notation::Go);
+    /// let reference = go.shortest(Shortest::Dragon4);
+    /// assert!(format!("{}", GPoint(0.3).with(reference)) == format!("{}", GPoint(0.3).with(go)));
+    /// ```
+    pub const fn shortest(mut self, algorithm: Shortest) -> Self {
+        self.shortest = algorithm;
+        self
+    }
+
+    pub(crate) fn minus(&self) -> &'static str {
+        if self.typographic_minus {
+            MINUS_SIGN
+        } else {
+            "-"
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GPoint, Spec};
+    use std::fmt;
+    #[test]
+    fn negative_zero() {
+        for backend in [Backend::Libc, Backend::Pure, Backend::Strfromd] {
+            let options = Options::new().backend(backend);
+            let normalize = options.negative_zero(NegativeZero::Normalize);
+            let preserve = options.negative_zero(NegativeZero::Preserve);
+            let zero = GPoint(-0.);
+            assert_eq!(format!("{}", zero.with(normalize)), "0");
+            assert_eq!(format!("{}", zero.with(preserve)), "-0");
+            assert_eq!(format!("{:+}", zero.with(normalize)), "+0");
+            assert_eq!(format!("{:+}", zero.with(preserve)), "-0");
+            assert_eq!(format!("{:#}", zero.with(normalize)), "0.00000");
+            assert_eq!(format!("{:#}", zero.with(preserve)), "-0.00000");
+            assert_eq!(format!("{:5}", zero.with(normalize)), "    0");
+            assert_eq!(format!("{:5}", zero.with(preserve)), "   -0");
+            assert_eq!(format!("{:+05}", zero.with(normalize)), "+0000");
+            assert_eq!(format!("{:+05}", zero.with(preserve)), "-0000");
+            assert_eq!(format!("{:<5}", GPoint(-0f32).with(normalize)), "0    ");
+            assert_eq!(format!("{:<5}", GPoint(-0f32).with(preserve)), "-0   ");
+            assert_eq!(format!("{}", GPoint(0.).with(preserve)), "0");
+        }
+    }
+    #[test]
+    fn spellings() {
+        let options = Options::new().nan("NA").inf("Infinity");
+        for (num, res) in [
+            (42., "       42"),
+            (f64::NAN, "       NA"),
+            (f64::INFINITY, " Infinity"),
+            (-f64::INFINITY, "-Infinity"),
+        ] {
+            assert_eq!(format!("{:9}", GPoint(num).with(options)), res);
+            assert_eq!(
+                format!("{:9}", GPoint(num).with(options.consistent(true))),
+                res
+            );
+        }
+        assert_eq!(
+            format!("{:09}", GPoint(f64::NAN).with(options)),
+            "       NA"
+        );
+        assert_eq!(
+            format!("{:+}", GPoint(f64::INFINITY).with(options)),
+            "+Infinity"
+        );
+        assert_eq!(
+            format!("{}", GPoint(f64::NAN).with(Options::new().nan("null"))),
+            "null"
+        );
+    }
+    #[test]
+    fn non_finite() {
+        let null = Options::new().non_finite(NonFinite::Null);
+        let quoted = Options::new().non_finite(NonFinite::Quoted);
+        let error = Options::new().non_finite(NonFinite::Error);
+        for (num, res) in [(-f64::INFINITY, "\"-inf\""), (f64::NAN, "\"nan\"")] {
+            assert_eq!(format!("{}", GPoint(num).with(null)), "null");
+            assert_eq!(format!("{:+6}", GPoint(num).with(null)), "  null");
+            assert_eq!(format!("{}", GPoint(num).with(quoted)), res);
+            let mut s = String::new();
+            assert!(fmt::write(&mut s, format_args!("{}", GPoint(num).with(error))).is_err());
+        }
+        assert_eq!(
+            format!("{}", GPoint(f64::INFINITY).with(quoted.inf("Infinity"))),
+            "\"Infinity\""
+        );
+        for options in [null, quoted, error] {
+            assert_eq!(format!("{:+}", GPoint(1.5).with(options)), "+1.5");
+        }
+    }
+    #[test]
+    fn uppercase() {
+        let non_finite = Options::new().uppercase_non_finite(true);
+        let exponent = Options::new().uppercase_exponent(true);
+        for (num, res_non_finite, res_exponent) in [
+            (1.5e-10, "1.5e-10", "1.5E-10"),
+            (42., "42", "42"),
+            (f64::NAN, "NAN", "nan"),
+            (-f64::INFINITY, "-INF", "-inf"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(non_finite)), res_non_finite);
+            assert_eq!(format!("{}", GPoint(num).with(exponent)), res_exponent);
+        }
+        let quoted = non_finite.non_finite(NonFinite::Quoted).inf("Infinity");
+        assert_eq!(
+            format!("{:+}", GPoint(f64::INFINITY).with(quoted)),
+            "\"+INFINITY\""
+        );
+        let null = non_finite.non_finite(NonFinite::Null);
+        assert_eq!(format!("{}", GPoint(f64::NAN).with(null)), "null");
+    }
+    #[test]
+    fn unsigned_nan() {
+        let options = Options::new().unsigned_nan(true);
+        for backend in [Backend::Libc, Backend::Pure, Backend::Strfromd] {
+            let options = options.backend(backend);
+            assert_eq!(format!("{}", GPoint(-f64::NAN).with(options)), "nan");
+            assert_eq!(format!("{:+6}", GPoint(-f64::NAN).with(options)), "   nan");
+            assert_eq!(format!("{:+}", GPoint(f64::NAN).with(options)), "nan");
+            assert_eq!(format!("{:+}", GPoint(f64::INFINITY).with(options)), "+inf");
+            assert_eq!(format!("{}", GPoint(-f64::INFINITY).with(options)), "-inf");
+        }
+    }
+    #[test]
+    fn percent() {
+        let percent = |fixed, space| Options::new().notation(Notation::Percent { fixed, space });
+        for backend in [Backend::Libc, Backend::Pure, Backend::Strfromd] {
+            let general = percent(false, false).backend(backend);
+            let fixed = percent(true, false).backend(backend);
+            let spaced = percent(false, true).backend(backend);
+            assert_eq!(format!("{}", GPoint(0.5).with(general)), "50%");
+            assert_eq!(format!("{:.3}", GPoint(0.123456).with(general)), "12.3%");
+            assert_eq!(format!("{:.3}", GPoint(0.123456).with(fixed)), "12.346%");
+            assert_eq!(format!("{:#.0}", GPoint(0.5).with(fixed)), "50.%");
+            assert_eq!(format!("{}", GPoint(0.07).with(fixed)), "7.000000%");
+            assert_eq!(format!("{}", GPoint(-1.5).with(spaced)), "-150 %");
+            assert_eq!(format!("{:+08.2}", GPoint(0.1).with(fixed)), "+010.00%");
+            assert_eq!(format!("{:<8}", GPoint(1.).with(spaced)), "100 %   ");
+            assert_eq!(format!("{}", GPoint(f64::NAN).with(general)), "nan");
+            assert_eq!(format!("{}", GPoint(12345.).with(general)), "1.2345e+06%");
+        }
+    }
+    #[test]
+    fn engineering() {
+        let options = Options::new().notation(Notation::Engineering);
+        for (num, res) in [
+            (12500., "12500"),
+            (1.25e6, "1.25e+06"),
+            (1.25e7, "12.5e+06"),
+            (-1.25e8, "-125e+06"),
+            (1e8, "100e+06"),
+            (1.5e-5, "15e-06"),
+            (1.5e-4, "0.00015"),
+            (1.234567e-7, "123.457e-09"),
+            (1e100, "10e+99"),
+            (f64::INFINITY, "inf"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+        }
+        assert_eq!(format!("{:#.3}", GPoint(1e8).with(options)), "100.e+06");
+        assert_eq!(format!("{:#}", GPoint(1e7).with(options)), "10.0000e+06");
+        assert_eq!(
+            format!("{:+09.2}", GPoint(4.2e7).with(options)),
+            "+0042e+06"
+        );
+        let upper = options.uppercase_exponent(true);
+        assert_eq!(format!("{}", GPoint(1.25e7).with(upper)), "12.5E+06");
+    }
+    #[test]
+    fn exponent_char() {
+        let marker = Options::new().exponent_char('D');
+        for (options, res) in [
+            (marker, "-1.5D-10"),
+            (marker.uppercase_exponent(true), "-1.5D-10"),
+            (marker.notation(Notation::Engineering), "-150D-12"),
+            (marker.notation(Notation::DotNet), "-1.5D-10"),
+            (marker.exponent(ExponentStyle::Compact), "-1.5D-10"),
+            (marker.exponent(ExponentStyle::Latex), r"-1.5\times10^{-10}"),
+            (marker.typographic_minus(true), "\u{2212}1.5D\u{2212}10"),
+        ] {
+            assert_eq!(format!("{}", GPoint(-1.5e-10).with(options)), res);
+        }
+        for backend in [Backend::Libc, Backend::Pure, Backend::Strfromd] {
+            let options = marker.backend(backend);
+            let spec = crate::Spec::parse("%+.2e").unwrap();
+            assert_eq!(
+                GPoint(12345.).with(options).try_to_string_with(&spec),
+                Ok("+1.23D+04".to_string())
+            );
+            assert_eq!(format!("{}", GPoint(1e20).with(options)), "1D+20");
+            assert_eq!(format!("{:08}", GPoint(1e20).with(options)), "0001D+20");
+            assert_eq!(format!("{}", GPoint(f64::NAN).with(options)), "nan");
+        }
+    }
+    #[test]
+    fn si() {
+        let options = Options::new().notation(Notation::Si);
+        for backend in [Backend::Libc, Backend::Pure, Backend::Strfromd] {
+            let options = options.backend(backend);
+            for (num, res) in [
+                (0., "0"),
+                (1., "1"),
+                (999., "999"),
+                (1250., "1.25k"),
+                (-3.3e-6, "-3.3\u{b5}"),
+                (2.5e9, "2.5G"),
+                (0.1, "100m"),
+                (999999.9, "1M"),
+                (1e-31, "100e-33"),
+                (1.5e33, "1.5e+33"),
+                (f64::NAN, "nan"),
+            ] {
+                assert_eq!(format!("{}", GPoint(num).with(options)), res);
+            }
+            assert_eq!(format!("{:.2}", GPoint(12345.).with(options)), "12k");
+            assert_eq!(format!("{:#.3}", GPoint(12000.).with(options)), "12.0k");
+            assert_eq!(format!("{:#.1}", GPoint(0.1).with(options)), "100.m");
+            assert_eq!(format!("{:>7.3}", GPoint(0.33333).with(options)), "   333m");
+            assert_eq!(format!("{:6}", GPoint(3.3e-6).with(options)), "  3.3\u{b5}");
+        }
+    }
+    #[test]
+    fn latex() {
+        let latex = Options::new().exponent(ExponentStyle::Latex);
+        let siunitx = Options::new().exponent(ExponentStyle::Siunitx);
+        for (num, res_latex, res_siunitx) in [
+            (42., "42", r"\num{42}"),
+            (1.5e-3, "0.0015", r"\num{0.0015}"),
+            (-1.5e-30, r"-1.5\times10^{-30}", r"\num{-1.5e-30}"),
+            (1e100, r"1\times10^{100}", r"\num{1e100}"),
+            (f64::INFINITY, "inf", "inf"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(latex)), res_latex);
+            assert_eq!(format!("{}", GPoint(num).with(siunitx)), res_siunitx);
+        }
+        let engineering = latex.notation(Notation::Engineering);
+        assert_eq!(
+            format!("{:+}", GPoint(1.25e7).with(engineering)),
+            r"+12.5\times10^{6}"
+        );
+    }
+    #[test]
+    fn superscript() {
+        let options = Options::new().exponent(ExponentStyle::Superscript);
+        for (num, res) in [
+            (42., "42"),
+            (-1.5e-30, "-1.5\u{d7}10\u{207b}\u{b3}\u{2070}"),
+            (1e100, "1\u{d7}10\u{b9}\u{2070}\u{2070}"),
+            (2.5e-7, "2.5\u{d7}10\u{207b}\u{2077}"),
+            (1.23456e89, "1.23456\u{d7}10\u{2078}\u{2079}"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+        }
+        assert_eq!(
+            format!("{:9}", GPoint(2e-5).with(options)),
+            "   2\u{d7}10\u{207b}\u{2075}"
+        );
+    }
+    #[test]
+    fn typographic_minus() {
+        let options = Options::new().typographic_minus(true);
+        for (num, res) in [
+            (1.5, "1.5"),
+            (-1.5, "\u{2212}1.5"),
+            (1.5e-10, "1.5e\u{2212}10"),
+            (-f64::INFINITY, "\u{2212}inf"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+        }
+        assert_eq!(
+            format!("{:06}", GPoint(-1.5).with(options)),
+            "\u{2212}001.5"
+        );
+        assert_eq!(format!("{:6}", GPoint(-1.5).with(options)), "  \u{2212}1.5");
+        let superscript = options.exponent(ExponentStyle::Superscript);
+        assert_eq!(
+            format!("{}", GPoint(-1e-10).with(superscript)),
+            "\u{2212}1\u{d7}10\u{207b}\u{b9}\u{2070}"
+        );
+    }
+    #[test]
+    fn grouping() {
+        let options = Options::new().grouping('\u{2009}');
+        for (num, res) in [
+            (0.5, "0.5"),
+            (123., "123"),
+            (-1234., "-1\u{2009}234"),
+            (123456.5, "123\u{2009}456.5"),
+            (1234567.25, "1\u{2009}234\u{2009}567.25"),
+            (1e20, "1e+20"),
+        ] {
+            assert_eq!(format!("{:.9}", GPoint(num).with(options)), res);
+        }
+        let percent = Options::new().notation(Notation::Percent {
+            fixed: true,
+            space: false,
+        });
+        assert_eq!(
+            format!("{:.0}", GPoint(12.3456).with(percent.grouping(','))),
+            "1,235%"
+        );
+        assert_eq!(
+            format!("{:10.9}", GPoint(12345.).with(options.grouping('_'))),
+            "    12_345"
+        );
+    }
+    #[test]
+    fn min_fraction_digits() {
+        let options = Options::new().min_fraction_digits(3);
+        for (num, res) in [
+            (0., "0.000"),
+            (-2., "-2.000"),
+            (0.5, "0.500"),
+            (0.1234, "0.1234"),
+            (123456., "123456.000"),
+            (1.5e-10, "1.500e-10"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+        }
+        assert_eq!(format!("{}", GPoint(f64::NAN).with(options)), "nan");
+        assert_eq!(format!("{:#.2}", GPoint(1.).with(options)), "1.000");
+        let si = options.notation(Notation::Si);
+        assert_eq!(format!("{}", GPoint(1500.).with(si)), "1.500k");
+    }
+    #[test]
+    fn zero_padding() {
+        let rust = Options::new().zero_padding(ZeroPadding::Rust);
+        for (num, res) in [
+            (42., "00000042"),
+            (-1.01, "-0001.01"),
+            (f64::NAN, "00000nan"),
+            (-f64::INFINITY, "-0000inf"),
+        ] {
+            assert_eq!(format!("{:08}", GPoint(num).with(rust)), res);
+            assert_eq!(format!("{:<08}", GPoint(num).with(rust)), res);
+            assert_eq!(format!("{:^08}", GPoint(num).with(rust)), res);
+        }
+        assert_eq!(format!("{:<08}", GPoint(-1.01)), "-1.01   ");
+        assert_eq!(
+            format!(
+                "{:08}",
+                GPoint(f64::NAN).with(rust.non_finite(NonFinite::Quoted))
+            ),
+            "   \"nan\""
+        );
+    }
+    #[test]
+    fn on_error() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static FALLBACKS: AtomicUsize = AtomicUsize::new(0);
+        fn notify(error: GError) {
+            assert_ne!(error, GError::NonFinite);
+            FALLBACKS.fetch_add(1, Ordering::Relaxed);
+        }
+        // too long for any buffer
+        let spec = crate::Spec::parse("%#.3000000000g").unwrap();
+        let strict = Options::new().non_finite(NonFinite::Error);
+        for (on_error, fallback) in [
+            (OnError::Fail, false),
+            (OnError::Rust, true),
+            (OnError::RustAndNotify(notify), true),
+        ] {
+            let options = Options::new().on_error(on_error);
+            for options in [options, options.backend(Backend::Pure)] {
+                let res = GPoint(1e3).with(options).try_to_string_with(&spec);
+                assert_eq!(res.ok(), Some("1000".to_string()).filter(|_| fallback));
+                assert_eq!(GPoint(1e3).with(options).try_to_string().unwrap(), "1000");
+            }
+            assert_eq!(
+                GPoint(f64::NAN)
+                    .with(strict.on_error(on_error))
+                    .try_to_string(),
+                Err(GError::NonFinite)
+            );
+        }
+        assert_eq!(FALLBACKS.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            OnError::RustAndNotify(notify),
+            OnError::RustAndNotify(notify)
+        );
+        assert_ne!(OnError::RustAndNotify(notify), OnError::Rust);
+    }
+    #[test]
+    fn gcode() {
+        let options = Options::new().notation(Notation::GCode { trim: false });
+        let trimmed = Options::new().notation(Notation::GCode { trim: true });
+        for (num, res, trimmed_res) in [
+            (0., "0.000", "0"),
+            (-2.5, "-2.500", "-2.5"),
+            (1e-5, "0.000", "0"),
+            (1e10, "10000000000.000", "10000000000"),
+            (123.4567, "123.457", "123.457"),
+            (f64::INFINITY, "inf", "inf"),
+        ] {
+            assert_eq!(format!("{:.3}", GPoint(num).with(options)), res);
+            assert_eq!(format!("{:.3}", GPoint(num).with(trimmed)), trimmed_res);
+        }
+        assert_eq!(format!("{}", GPoint(0.1).with(options)), "0.100000");
+        assert_eq!(format!("{:#.2}", GPoint(1.).with(trimmed)), "1.00");
+    }
+    #[test]
+    fn go() {
+        let options = Options::new().notation(Notation::Go);
+        for (num, res) in [
+            (0., "0"),
+            (-0., "-0"),
+            (0.1, "0.1"),
+            (1. / 3., "0.3333333333333333"),
+            (100000., "100000"),
+            (1e6, "1e+06"),
+            (123456789., "1.23456789e+08"),
+            (1e-5, "1e-05"),
+            (5e-324, "5e-324"),
+            (f64::NAN, "NaN"),
+            (-f64::NAN, "NaN"),
+            (f64::INFINITY, "+Inf"),
+            (f64::NEG_INFINITY, "-Inf"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+        }
+        assert_eq!(format!("{:.3}", GPoint(1. / 3.).with(options)), "0.333");
+        assert_eq!(format!("{:>8}", GPoint(0.1).with(options)), "     0.1");
+        assert_eq!(
+            format!("{:6}", GPoint(f64::INFINITY).with(options)),
+            "  +Inf"
+        );
+    }
+    #[test]
+    fn dotnet() {
+        let options = Options::new().notation(Notation::DotNet);
+        for (num, res) in [
+            (-0., "-0"),
+            (0.1, "0.1"),
+            (123456789012345., "123456789012345"),
+            (1e15, "1E+15"),
+            (1234567890123456., "1.234567890123456E+15"),
+            (0.0001, "0.0001"),
+            (0.00001, "1E-05"),
+            (1e-300, "1E-300"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+        }
+        assert_eq!(
+            format!("{:.17}", GPoint(0.1).with(options)),
+            "0.10000000000000001"
+        );
+        assert_eq!(format!("{:.17}", GPoint(1e20).with(options)), "1E+20");
+    }
+    #[test]
+    fn excel() {
+        let options = Options::new().notation(Notation::Excel);
+        for (num, res) in [
+            (-0., "0"),
+            (1. / 3., "0.333333333"),
+            (-1234567.891, "-1234567.891"),
+            (123456789012., "1.23457E+11"),
+            (1e-20, "1E-20"),
+            (f64::INFINITY, "inf"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+            assert_eq!(format!("{:.2}", GPoint(num).with(options)), res);
+        }
+        let grouped = options.grouping(',');
+        assert_eq!(format!("{}", GPoint(1234.5).with(grouped)), "1,234.5");
+    }
+    #[test]
+    fn toml() {
+        let toml = Options::new().notation(Notation::Toml);
+        let json5 = Options::new().notation(Notation::Json5);
+        for (num, res, res5) in [
+            (0., "0.0", "0.0"),
+            (-0., "-0.0", "-0.0"),
+            (1., "1.0", "1.0"),
+            (0.5, "0.5", "0.5"),
+            (1e6, "1e+06", "1e+06"),
+            (-1.5e-10, "-1.5e-10", "-1.5e-10"),
+            (123456., "123456.0", "123456.0"),
+            (f64::NAN, "nan", "NaN"),
+            (-f64::NAN, "-nan", "-NaN"),
+            (f64::INFINITY, "inf", "Infinity"),
+            (f64::NEG_INFINITY, "-inf", "-Infinity"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(toml)), res);
+            assert_eq!(format!("{}", GPoint(num).with(json5)), res5);
+        }
+        for (spec, res) in [
+            ("%+.0f", "+2.0"),
+            ("%#.0f", "2.0"),
+            ("%#.0e", "2.0e+00"),
+            ("%.3e", "1.500e+00"),
+            ("%#g", "1.50000"),
+        ] {
+            let spec = Spec::parse(spec).unwrap();
+            assert_eq!(
+                GPoint(1.5).with(toml).try_to_string_with(&spec).unwrap(),
+                res
+            );
+        }
+        let grouped = toml.grouping('_');
+        assert_eq!(
+            format!("{:.10}", GPoint(1234567.).with(grouped)),
+            "1_234_567.0"
+        );
+    }
+    #[test]
+    fn yaml() {
+        let options = Options::new().notation(Notation::Yaml);
+        for (num, res) in [
+            (0., "0.0"),
+            (-0., "-0.0"),
+            (1., "1.0"),
+            (0.25, "0.25"),
+            (1e6, "1.0e+6"),
+            (1.5e-7, "1.5e-7"),
+            (-1.5e-10, "-1.5e-10"),
+            (1e100, "1.0e+100"),
+            (f64::NAN, ".nan"),
+            (-f64::NAN, ".nan"),
+            (f64::INFINITY, ".inf"),
+            (f64::NEG_INFINITY, "-.inf"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+        }
+        assert_eq!(
+            format!("{:+}", GPoint(f64::INFINITY).with(options)),
+            "+.inf"
+        );
+        let spec = Spec::parse("%#.0e").unwrap();
+        assert_eq!(
+            GPoint(3.).with(options).try_to_string_with(&spec).unwrap(),
+            "3.0e+0"
+        );
+    }
+    #[test]
+    fn xsd() {
+        let options = Options::new().notation(Notation::Xsd);
+        for (num, res) in [
+            (0., "0.0E0"),
+            (-0., "-0.0E0"),
+            (1., "1.0E0"),
+            (125., "1.25E2"),
+            (0.1, "1.0E-1"),
+            (1. / 3., "3.333333333333333E-1"),
+            (-1.5e-10, "-1.5E-10"),
+            (1e100, "1.0E100"),
+            (5e-324, "5.0E-324"),
+            (f64::MAX, "1.7976931348623157E308"),
+            (f64::NAN, "NaN"),
+            (-f64::NAN, "NaN"),
+            (f64::INFINITY, "INF"),
+            (f64::NEG_INFINITY, "-INF"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+            if num.is_finite() {
+                assert_eq!(format!("{}", GPoint(num).with(options)).parse(), Ok(num));
+            }
+        }
+        assert_eq!(format!("{:.3}", GPoint(1. / 3.).with(options)), "3.33E-1");
+        assert_eq!(format!("{:.3}", GPoint(2.).with(options)), "2.0E0");
+        assert_eq!(format!("{:.0}", GPoint(1234.).with(options)), "1.0E3");
+    }
+    #[test]
+    fn gnuplot_exponent() {
+        let options = Options::new().exponent(ExponentStyle::Gnuplot);
+        assert_eq!(format!("{}", GPoint(1.5e-3).with(options)), "0.0015");
+        assert_eq!(
+            format!("{:.2}", GPoint(-1.5e-30).with(options)),
+            r"-1.5{\times}10^{-30}"
+        );
+        assert_eq!(format!("{}", GPoint(1e6).with(options)), r"1{\times}10^{6}");
+    }
+    #[test]
+    fn compact_exponent() {
+        let options = Options::new().exponent(ExponentStyle::Compact);
+        for (num, res) in [
+            (1e6, "1e6"),
+            (-1.5e-7, "-1.5e-7"),
+            (1e100, "1e100"),
+            (12345., "12345"),
+            (f64::INFINITY, "inf"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+        }
+        let upper = options.uppercase_exponent(true).typographic_minus(true);
+        assert_eq!(
+            format!("{:8}", GPoint(2e-10).with(upper)),
+            "   2E\u{2212}10"
+        );
+        assert_eq!(format!("{:#.3}", GPoint(1e6).with(options)), "1.00e6");
+    }
+    #[test]
+    fn exponent_digits() {
+        let options = Options::new().min_exponent_digits(4);
+        assert_eq!(format!("{}", GPoint(1e10).with(options)), "1e+0010");
+        assert_eq!(format!("{}", GPoint(-1e-300).with(options)), "-1e-0300");
+        assert_eq!(format!("{}", GPoint(1.5).with(options)), "1.5");
+        let none = Options::new().min_exponent_digits(0);
+        assert_eq!(format!("{}", GPoint(1e-7).with(none)), "1e-7");
+        let wide = Options::new().min_exponent_digits(400);
+        assert_eq!(format!("{}", GPoint(1e7).with(wide)).len(), 403);
+    }
+    #[test]
+    fn exact() {
+        let options = Options::new().notation(Notation::Exact);
+        for (num, res) in [
+            (0., "0"),
+            (-2.5, "-2.5"),
+            (
+                0.1,
+                "0.1000000000000000055511151231257827021181583404541015625",
+            ),
+            (0.1f32 as f64, "0.100000001490116119384765625"),
+            (1e23, "99999999999999991611392"),
+            (f64::NAN, "nan"),
+        ] {
+            assert_eq!(format!("{}", GPoint(num).with(options)), res);
+            assert_eq!(format!("{:.3}", GPoint(num).with(options)), res);
+        }
+        assert_eq!(format!("{:#}", GPoint(1.).with(options)), "1.");
+        let smallest = format!("{}", GPoint(5e-324).with(options));
+        assert_eq!(smallest.len(), 1076);
+        assert!(smallest.starts_with("0.000") && smallest.ends_with("65625"));
+        assert_eq!(format!("{}", GPoint(f64::MAX).with(options)).len(), 309);
+        assert_eq!(
+            format!("{:12}", GPoint(1e6f32).with(options.grouping(','))),
+            "   1,000,000"
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Pure-Rust emulation of glibc's `printf("%g")`
+
+use crate::buffer::ByteWriter;
+use crate::{significant_digits, Conversion, Flags};
+use std::fmt::{self, Write};
+
+/// Formats `value` into `buf` exactly like glibc's `snprintf()` would with
+/// the given flags but without padding, returning the output length.
+///
+/// Rust's float formatting is exact and rounds ties to even, as glibc does
+/// in the default rounding mode, so only the `printf()` logic is emulated
+/// here.
+pub(crate) fn format_g(buf: &mut [u8], value: f64, flags: &Flags) -> Result<usize, fmt::Error> {
+    // Rust panics on larger precisions, which wouldn't fit anyway
+    if flags.precision.unwrap_or(0) > usize::from(u16::MAX) {
+        return Err(fmt::Error);
+    }
+    let mut out = ByteWriter::new(buf);
+    let sign = if value.is_sign_negative() {
+        "-"
+    } else if flags.plus {
+        "+"
+    } else {
+        ""
+    };
+    out.write_str(sign)?;
+    if value.is_nan() {
+        out.write_str("nan")?;
+    } else if value.is_infinite() {
+        out.write_str("inf")?;
+    } else {
+        let value = value.abs();
+        match flags.conversion {
+            Conversion::General => write_digits(&mut out, value, flags.precision, flags.alternate)?,
+            Conversion::Fixed => {
+                write!(out, "{:.*}", flags.precision.unwrap_or(6), value)?;
+                if flags.alternate && flags.precision == Some(0) {
+                    out.write_str(".")?;
+                }
+            }
+            Conversion::Scientific => {
+                let exponent = write_scientific(&mut out, flags.precision.unwrap_or(6), value)?;
+                if flags.alternate && flags.precision == Some(0) {
+                    out.write_str(".")?;
+                }
+                write_exponent(&mut out, exponent)?;
+            }
+        }
+    }
+    Ok(out.len())
+}
+
+/// Writes the mantissa of Rust's `{:e}` output for `value`, returning its
+/// exponent.
+fn write_scientific(
+    out: &mut ByteWriter<'_>,
+    precision: usize,
+    value: f64,
+) -> Result<i32, fmt::Error> {
+    let start = out.len();
+    write!(out, "{:.*e}", precision, value)?;
+    let e = out.as_bytes()[start..]
+        .iter()
+        .position(|&c| c == b'e')
+        .ok_or(fmt::Error)?;
+    let exponent = std::str::from_utf8(&out.as_bytes()[start + e + 1..])
+        .ok()
+        .and_then(|exponent| exponent.parse().ok())
+        .ok_or(fmt::Error)?;
+    out.truncate(start + e);
+    Ok(exponent)
+}
+
+/// Writes an exponent the way `printf()` does, i.e. signed and with at least
+/// two digits.
+pub(crate) fn write_exponent(out: &mut ByteWriter<'_>, exponent: i32) -> fmt::Result {
+    let sign = if exponent < 0 { '-' } else { '+' };
+    write!(out, "e{}{:02}", sign, exponent.abs())
+}
+
+/// Writes the `%g` representation of a finite, positive `value`.
+fn write_digits(
+    out: &mut ByteWriter<'_>,
+    value: f64,
+    precision: Option<usize>,
+    alternate: bool,
+) -> fmt::Result {
+    let precision = significant_digits(precision);
+    let start = out.len();
+    let exponent = write_scientific(out, precision - 1, value)?;
+
+    let fixed = exponent >= -4 && exponent < precision as i32;
+    if fixed {
+        out.truncate(start);
+        write!(
+            out,
+            "{:.*}",
+            (precision as i32 - 1 - exponent) as usize,
+            value
+        )?;
+    } else if alternate && exponent == precision as i32 && integer_digits(value)? == precision {
+        // glibc quirk: when rounding carries a number out of the fixed range,
+        // it keeps the fixed notation's count of fractional digits, i.e. none
+        out.truncate(start + 1);
+    }
+    let has_point = out.as_bytes()[start..].contains(&b'.');
+    if alternate {
+        if !has_point {
+            out.write_str(".")?;
+        }
+    } else if has_point {
+        let digits = &out.as_bytes()[start..];
+        let trimmed = digits.len() - digits.iter().rev().take_while(|&&c| c == b'0').count();
+        let trimmed = if digits[trimmed - 1] == b'.' {
+            trimmed - 1
+        } else {
+            trimmed
+        };
+        out.truncate(start + trimmed);
+    }
+    if !fixed {
+        write_exponent(out, exponent)?;
+    }
+    Ok(())
+}
+
+/// Counts the digits of the integer part of `value`.
+fn integer_digits(value: f64) -> Result<usize, fmt::Error> {
+    // the largest finite `f64` has 309 of them
+    let mut digits = [0u8; 320];
+    let mut digits = ByteWriter::new(&mut digits);
+    write!(digits, "{:.0}", value.trunc())?;
+    Ok(digits.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GPoint, Options};
+
+    #[test]
+    fn same_as_libc() {
+        let consistent = Options::new().consistent(true);
+        for num in [
+            0.,
+            -0.,
+            1.,
+            42.,
+            -1.01,
+            0.5,
+            2.5,
+            9.5,
+            99.5,
+            9.9999995,
+            99999.95,
+            9999999.5,
+            1e-4,
+            9.99999e-5,
+            0.000123456789,
+            123456.,
+            999999.5,
+            1234567.,
+            1e100,
+            -1e-300,
+            5e-324,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            std::f64::consts::PI,
+            f64::NAN,
+            -f64::NAN,
+            f64::INFINITY,
+            -f64::INFINITY,
+        ] {
+            macro_rules! check {
+                ($($fmt:literal),*) => {$(
+                    assert_eq!(
+                        format!($fmt, GPoint(num).with(consistent)),
+                        format!($fmt, GPoint(num)),
+                        "{} with {}", num, $fmt
+                    );
+                )*};
+            }
+            check!(
+                "{}",
+                "{:.0}",
+                "{:.1}",
+                "{:.3}",
+                "{:.17}",
+                "{:#}",
+                "{:#.0}",
+                "{:#.3}",
+                "{:+}",
+                "{:12}",
+                "{:<12}",
+                "{:012}",
+                "{:+012.4}",
+                "{:<#12.2}"
+            );
+        }
+    }
+}
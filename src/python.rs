@@ -0,0 +1,99 @@
+//! The entry point of the `gpoint` Python module, `python/gpoint.py`, which
+//! loads the C ABI with `ctypes`
+
+use crate::capi::format_c;
+use crate::{GConfig, Notation};
+use libc::{c_char, c_int, size_t};
+use std::ffi::CStr;
+
+/// Formats `value` into `buf` like [`gpoint_format_g()`](crate::gpoint_format_g),
+/// in one of the compatibility modes, by name, on top of the options of
+/// [`GConfig`]: `printf` for the C library's output, `pure` for glibc's on
+/// any platform, `exact` for every digit, `go`, `dotnet` and `excel` for the
+/// output of those, and `gcode` for CNC controllers. A null `mode` means
+/// `printf`.
+///
+/// Returns -1 for unknown modes, as for invalid specifications. It is
+/// exported with the `python` feature, for `python/gpoint.py`:
+///
+/// ```c
+/// int gpoint_gformat(double value, const char *spec, const char *mode, char *buf, size_t size);
+/// ```
+///
+/// # Safety
+///
+/// `mode` must be null or point to a 0-terminated string, and the other
+/// arguments are as for [`gpoint_format_g()`](crate::gpoint_format_g).
+#[no_mangle]
+pub unsafe extern "C" fn gpoint_gformat(
+    value: f64,
+    spec: *const c_char,
+    mode: *const c_char,
+    buf: *mut c_char,
+    size: size_t,
+) -> c_int {
+    let options = GConfig::get();
+    let mode = if mode.is_null() {
+        "printf"
+    } else {
+        match CStr::from_ptr(mode).to_str() {
+            Ok(mode) => mode,
+            Err(_) => return -1,
+        }
+    };
+    let options = match mode {
+        "printf" => options,
+        "pure" => options.consistent(true),
+        "exact" => options.notation(Notation::Exact),
+        "go" => options.notation(Notation::Go),
+        "dotnet" => options.notation(Notation::DotNet),
+        "excel" => options.notation(Notation::Excel),
+        "gcode" => options.notation(Notation::GCode { trim: true }),
+        _ => return -1,
+    };
+    format_c(value, spec, options, buf, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn gformat() {
+        let format = |value, spec: &[u8], mode: &[u8]| {
+            let mut buf = [0 as c_char; 64];
+            let len = unsafe {
+                gpoint_gformat(
+                    value,
+                    spec.as_ptr() as *const c_char,
+                    mode.as_ptr() as *const c_char,
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                )
+            };
+            let output = unsafe { CStr::from_ptr(buf.as_ptr()) };
+            (len, output.to_str().unwrap().to_owned())
+        };
+        for (mode, res) in [
+            ("printf", "1.23457e+06"),
+            ("pure", "1.23457e+06"),
+            ("exact", "1234567.125"),
+            ("go", "1.234567125e+06"),
+            ("dotnet", "1234567.125"),
+            ("excel", "1234567.125"),
+            ("gcode", "1234567.125"),
+        ] {
+            let mode = format!("{}\0", mode);
+            assert_eq!(
+                format(1234567.125, b"%g\0", mode.as_bytes()),
+                (res.len() as c_int, res.to_string())
+            );
+        }
+        assert_eq!(format(f64::INFINITY, b"%g\0", b"go\0").1, "+Inf");
+        assert_eq!(format(1.5, b"%.3f\0", b"gcode\0").1, "1.5");
+        assert_eq!(format(1., b"%g\0", b"fortran\0").0, -1);
+        let mut buf = [0 as c_char; 8];
+        let len =
+            unsafe { gpoint_gformat(0.5, std::ptr::null(), std::ptr::null(), buf.as_mut_ptr(), 8) };
+        assert_eq!(len, 3);
+    }
+}
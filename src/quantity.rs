@@ -0,0 +1,130 @@
+//! Physical quantities, such as the ones of the `uom` crate
+
+use crate::{formatted, Flags, GConfig, Notation, Options};
+use std::fmt::{self, Write};
+
+/// A number followed by the abbreviation of its unit, e.g. `12.3 m`, with
+/// `%g` and the flags and precision of the format string, and optionally
+/// rescaled with an SI prefix, e.g. `1.5 km`.
+///
+/// For `uom` quantities, give the value in some unit and that unit's
+/// abbreviation, e.g. `GQuantity::new(length.get::<meter>(),
+/// meter::abbreviation())`. The width and alignment apply to the whole
+/// quantity.
+///
+/// ```
+/// use gpoint::GQuantity;
+///
+/// assert!(format!("{}", GQuantity::new(12.3, "m")) == "12.3 m");
+/// assert!(format!("{:.2}", GQuantity::new(1530., "m").si_prefix(true)) == "1.5 km");
+/// assert!(format!("{:>9}", GQuantity::new(4.7e-6, "F").si_prefix(true)) == "   4.7 µF");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct GQuantity<'a> {
+    value: f64,
+    unit: &'a str,
+    si_prefix: bool,
+    options: Options,
+}
+
+impl<'a> GQuantity<'a> {
+    /// `value` expressed in `unit`, formatted with the default [`Options`].
+    pub fn new(value: impl Into<f64>, unit: &'a str) -> Self {
+        GQuantity {
+            value: value.into(),
+            unit,
+            si_prefix: false,
+            options: GConfig::get(),
+        }
+    }
+
+    /// Rescales the value with an SI prefix added to the unit, as
+    /// [`Notation::Si`] does, which is off by default.
+    pub fn si_prefix(mut self, si_prefix: bool) -> Self {
+        self.si_prefix = si_prefix;
+        self
+    }
+
+    /// Sets the [`Options`] the value is formatted with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl fmt::Display for GQuantity<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = Flags::from_formatter(f);
+        flags.precision = flags.precision.or(self.options.precision);
+        // padding is applied to the whole quantity below
+        flags.width = None;
+        flags.zero = false;
+        let options = if self.si_prefix {
+            self.options.notation(Notation::Si)
+        } else {
+            self.options
+        };
+        let value = self.value;
+        let text = formatted(value, &flags, &options, |numstr| {
+            // the prefix is the letter `Notation::Si` appends to finite values
+            let number = if self.si_prefix && value.is_finite() {
+                numstr.trim_end_matches(char::is_alphabetic)
+            } else {
+                numstr
+            };
+            let mut text = String::with_capacity(numstr.len() + self.unit.len() + 1);
+            text.push_str(number);
+            text.push(' ');
+            text.push_str(&numstr[number.len()..]);
+            text.push_str(self.unit);
+            text
+        })?;
+
+        let padding = f.width().unwrap_or(0).saturating_sub(text.chars().count());
+        let (before, after) = match f.align() {
+            Some(fmt::Alignment::Left) => (0, padding),
+            Some(fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+            _ => (padding, 0),
+        };
+        for _ in 0..before {
+            f.write_char(f.fill())?;
+        }
+        f.write_str(&text)?;
+        for _ in 0..after {
+            f.write_char(f.fill())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn quantity() {
+        for (value, unit, prefix, res) in [
+            (12.3, "m", false, "12.3 m"),
+            (1.5e-5, "s", false, "1.5e-05 s"),
+            (1530., "m", true, "1.53 km"),
+            (-3.3e-6, "A", true, "-3.3 \u{b5}A"),
+            (0., "V", true, "0 V"),
+            (999., "g", true, "999 g"),
+            (1e40, "J", true, "10e+39 J"),
+            (f64::NAN, "m", true, "nan m"),
+            (-f64::INFINITY, "Hz", true, "-inf Hz"),
+        ] {
+            let quantity = GQuantity::new(value, unit).si_prefix(prefix);
+            assert_eq!(format!("{}", quantity), res);
+        }
+        let quantity = GQuantity::new(1500f32, "W").si_prefix(true);
+        assert_eq!(format!("{:#.3}", quantity), "1.50 kW");
+        assert_eq!(format!("{:+}", quantity), "+1.5 kW");
+        assert_eq!(format!("{:<8}|", quantity), "1.5 kW  |");
+        assert_eq!(format!("{:*^10}", quantity), "**1.5 kW**");
+        let options = Options::new().precision(2);
+        assert_eq!(
+            format!("{}", GQuantity::new(1. / 3., "m").with(options)),
+            "0.33 m"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! Intervals of numbers
+
+use crate::{formatted, significant_digits, Flags, GConfig};
+use std::fmt;
+
+/// An interval displayed as `[a, b]`, e.g. a confidence interval or an axis
+/// range, with a precision shared by both ends and raised above the one of
+/// the format string until they can be told apart.
+///
+/// ```
+/// use gpoint::GRange;
+///
+/// assert!(format!("{}", GRange(0.5, 2.)) == "[0.5, 2]");
+/// assert!(format!("{}", GRange(1.0000001, 1.0000002)) == "[1.0000001, 1.0000002]");
+/// assert!(format!("{:.2}", GRange(1.23, 1.24)) == "[1.23, 1.24]");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GRange(
+    /// The lower end
+    pub f64,
+    /// The upper end
+    pub f64,
+);
+
+impl fmt::Display for GRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = GConfig::get();
+        let mut flags = Flags::from_formatter(f);
+        flags.precision = flags.precision.or(options.precision);
+        let format = |flags: &Flags, value| formatted(value, flags, &options, str::to_owned);
+        let (mut low, mut high) = (String::new(), String::new());
+        // 17 significant digits tell any two `f64` apart
+        for precision in significant_digits(flags.precision)..=17.max(flags.precision.unwrap_or(0))
+        {
+            flags.precision = Some(precision);
+            low = format(&flags, self.0)?;
+            high = format(&flags, self.1)?;
+            if low != high || self.0 == self.1 {
+                break;
+            }
+        }
+        write!(f, "[{}, {}]", low, high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn range() {
+        for (low, high, res) in [
+            (0., 1., "[0, 1]"),
+            (-1.5, 1.5, "[-1.5, 1.5]"),
+            (1.234567, 1.234568, "[1.234567, 1.234568]"),
+            (1.2345671, 1.2345679, "[1.234567, 1.234568]"),
+            (1., 1., "[1, 1]"),
+            (1., 1. + f64::EPSILON, "[1, 1.0000000000000002]"),
+            (f64::NEG_INFINITY, f64::NAN, "[-inf, nan]"),
+        ] {
+            assert_eq!(format!("{}", GRange(low, high)), res);
+        }
+        assert_eq!(format!("{:6.1}", GRange(1., 2.)), "[     1,      2]");
+        // raised from the precision of the options
+        let _guard = crate::ConfigGuard::new(crate::Options::new().precision(3));
+        assert_eq!(format!("{}", GRange(1. / 3., 2. / 3.)), "[0.333, 0.667]");
+        assert_eq!(format!("{}", GRange(1.2341, 1.2342)), "[1.2341, 1.2342]");
+    }
+}
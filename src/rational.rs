@@ -0,0 +1,234 @@
+//! Exact rational numbers, such as the ones of the `num-rational` crate
+
+use crate::buffer::ByteWriter;
+use crate::decimal::{digit_count, fmt_with, round, write_digits, Rounding};
+use crate::{Flags, GConfig, GError, GPoint, GPointWith, Options};
+use std::fmt;
+
+/// An exact fraction, e.g. `*ratio.numer()` and `*ratio.denom()` of a
+/// `num_rational::Ratio<i128>`, formatted as `printf("%g")` would format
+/// the real number it stands for, to check what a float pipeline prints
+/// against an exact reference.
+///
+/// The digits come from the long division of the numerator by the
+/// denominator, rounded to the precision half to even, as C libraries
+/// round exact binary values: `%g` and the `#`, `+`, width and alignment
+/// flags are supported.
+///
+/// ```
+/// use gpoint::{GPoint, GRational};
+///
+/// assert!(format!("{}", GPoint(GRational::new(1, 3))) == "0.333333");
+/// assert!(format!("{:.20}", GPoint(GRational::new(-22, 7))) == "-3.1428571428571428571");
+/// assert!(format!("{:.2}", GPoint(GRational::new(1, 8))) == "0.12");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GRational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl GRational {
+    /// The number `numerator / denominator`, which needn't be reduced.
+    ///
+    /// Panics if the denominator is zero.
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        assert!(denominator != 0, "zero denominator");
+        GRational {
+            numerator,
+            denominator,
+        }
+    }
+
+    fn is_negative(self) -> bool {
+        self.numerator != 0 && (self.numerator < 0) != (self.denominator < 0)
+    }
+}
+
+/// The decimal digits of a quotient, those of its integer part, then those
+/// of the long division of the remainder.
+struct Quotient {
+    integer: Vec<u8>,
+    next: usize,
+    remainder: u128,
+    denominator: u128,
+}
+
+impl Quotient {
+    fn next_digit(&mut self) -> u8 {
+        if let Some(&digit) = self.integer.get(self.next) {
+            self.next += 1;
+            return digit;
+        }
+        // digit = 10 × remainder / denominator, without overflowing since
+        // both sums stay below 2 × denominator
+        let mut digit = b'0';
+        let mut product: u128 = 0;
+        for _ in 0..10 {
+            let (sum, carry) = product.overflowing_add(self.remainder);
+            if carry || sum >= self.denominator {
+                product = sum.wrapping_sub(self.denominator);
+                digit += 1;
+            } else {
+                product = sum;
+            }
+        }
+        self.remainder = product;
+        digit
+    }
+
+    /// Whether all the digits not yet returned are zeros.
+    fn rest_is_zero(&self) -> bool {
+        self.remainder == 0 && self.integer[self.next..].iter().all(|&digit| digit == b'0')
+    }
+}
+
+/// Writes `value` scaled by `10^scale` as `printf()` would with `flags`,
+/// without padding, into `out`.
+fn write_g(
+    out: &mut ByteWriter<'_>,
+    value: GRational,
+    flags: &Flags,
+    scale: i32,
+) -> Result<(), GError> {
+    let numerator = value.numerator.unsigned_abs();
+    let denominator = value.denominator.unsigned_abs();
+    let integer = numerator / denominator;
+    let mut quotient = Quotient {
+        integer: if integer == 0 {
+            Vec::new()
+        } else {
+            integer.to_string().into_bytes()
+        },
+        next: 0,
+        remainder: numerator % denominator,
+        denominator,
+    };
+    let mut digits = Vec::new();
+    let mut exponent = quotient.integer.len() as i64 - 1;
+    if numerator != 0 {
+        let mut first = quotient.next_digit();
+        while first == b'0' {
+            first = quotient.next_digit();
+            exponent -= 1;
+        }
+        exponent += i64::from(scale);
+        // the digits kept, and the next one to round them
+        let count = digit_count(flags, exponent);
+        digits.push(first);
+        while (digits.len() as i64) < count + 1 {
+            digits.push(quotient.next_digit());
+        }
+        let inexact = !quotient.rest_is_zero();
+        round(
+            &mut digits,
+            &mut exponent,
+            count,
+            inexact,
+            Rounding::Nearest,
+        );
+    } else {
+        exponent = 0;
+    }
+    write_digits(out, value.is_negative(), &digits, exponent, flags)
+}
+
+fn fmt_rational(f: &mut fmt::Formatter<'_>, value: GRational, options: &Options) -> fmt::Result {
+    fmt_with(f, value.is_negative(), options, |out, flags, scale| {
+        write_g(out, value, flags, scale)
+    })
+}
+
+impl fmt::Display for GPoint<GRational> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_rational(f, self.0, &GConfig::get())
+    }
+}
+
+impl fmt::Display for GPointWith<GRational> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_rational(f, self.value, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::tests::formatted_notations;
+    #[test]
+    fn rational() {
+        for (numerator, denominator, precision, res) in [
+            (1, 3, None, "0.333333"),
+            (2, 3, Some(3), "0.667"),
+            (-2, -3, Some(3), "0.667"),
+            (2, -3, Some(3), "-0.667"),
+            (1, 8, Some(2), "0.12"),
+            (3, 8, Some(2), "0.38"),
+            (95, 100, Some(1), "1"),
+            (1, 7, Some(20), "0.14285714285714285714"),
+            (1, 10_000, None, "0.0001"),
+            (1, 100_000, None, "1e-05"),
+            (123_456_789, 1, None, "1.23457e+08"),
+            (9_999_995, 10, None, "1e+06"),
+            (9_999_985, 10, None, "999998"),
+            (9_999_995, 1, None, "1e+07"),
+            (0, -5, None, "0"),
+            (10_i128.pow(30), 1, None, "1e+30"),
+            (1, 10_i128.pow(30), None, "1e-30"),
+            (1, i128::MIN, Some(3), "-5.88e-39"),
+            (i128::MAX, i128::MAX - 1, Some(38), "1"),
+            (
+                i128::MAX,
+                1,
+                Some(39),
+                "170141183460469231731687303715884105727",
+            ),
+        ] {
+            let num = GPoint(GRational::new(numerator, denominator));
+            let formatted = match precision {
+                Some(p) => format!("{:.*}", p, num),
+                None => format!("{}", num),
+            };
+            assert_eq!(formatted, res, "{:?}", num);
+        }
+        let num = GPoint(GRational::new(5, 4));
+        assert_eq!(format!("{:#}", num), "1.25000");
+        assert_eq!(format!("{:+08.2}|", num), "+00001.2|");
+        let grouped = Options::new().grouping(',').precision(10);
+        assert_eq!(
+            format!("{}", GPoint(GRational::new(-1234567, 4)).with(grouped)),
+            "-308,641.75"
+        );
+        // the same as floats in every notation
+        for (numerator, denominator) in [(1, 2), (-1, 8), (1250, 1), (10_000_000_000, 1)] {
+            let rational = GPoint(GRational::new(numerator, denominator));
+            let float = numerator as f64 / denominator as f64;
+            for (notation, precision, rational, float) in formatted_notations(rational, float) {
+                assert_eq!(rational, float, "{:?} {:?}", notation, precision);
+            }
+        }
+        let percent = Options::new().notation(crate::Notation::Percent {
+            fixed: false,
+            space: false,
+        });
+        assert_eq!(
+            format!("{}", GPoint(GRational::new(1, 2)).with(percent)),
+            "50%"
+        );
+        // the same as floats for dyadic fractions, which they hold exactly
+        for numerator in [1, 3, 5, 7, 999, 123_456_789, (1 << 53) - 1] {
+            for shift in [0, 1, 3, 10, 30, 60, 100] {
+                let rational = GRational::new(numerator, 1 << shift);
+                let float = numerator as f64 / 2f64.powi(shift);
+                for precision in [1, 2, 3, 6, 17, 40] {
+                    assert_eq!(
+                        format!("{:.*}", precision, GPoint(rational)),
+                        format!("{:.*}", precision, GPoint(float)),
+                        "{:?}",
+                        rational
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Streaming numbers out of `io::Read` sources
+
+use crate::locale::LocaleGuard;
+use crate::NumericLocale;
+use std::io::{self, BufRead, BufReader, Read};
+
+/// An iterator over the numbers of any `io::Read`, separated by whitespace
+/// or commas, parsed with the C library's `strtod()` like C programs read
+/// them: `inf`, `nan`, `nan(...)` and hexadecimal floats such as `0x1p-3`
+/// are accepted, out of range numbers become infinite or zero.
+///
+/// Input is read in chunks, never as a whole. Tokens which aren't numbers
+/// are reported as `io::ErrorKind::InvalidData` errors, then iteration goes
+/// on with the next ones.
+///
+/// ```
+/// use gpoint::GReader;
+///
+/// let input = "1.5, -2e3\n0x1p-2\tinf";
+/// let numbers: Vec<f64> = GReader::new(input.as_bytes()).collect::<Result<_, _>>()?;
+/// assert!(numbers == [1.5, -2000., 0.25, f64::INFINITY]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct GReader<R: Read> {
+    inner: BufReader<R>,
+    token: Vec<u8>,
+    locale: NumericLocale,
+}
+
+impl<R: Read> GReader<R> {
+    /// Reads numbers from `inner`, in the locale selected for the whole
+    /// program.
+    pub fn new(inner: R) -> Self {
+        GReader {
+            inner: BufReader::new(inner),
+            token: Vec::new(),
+            locale: NumericLocale::System,
+        }
+    }
+
+    /// Selects the `LC_NUMERIC` locale numbers are parsed in, e.g.
+    /// [`NumericLocale::C`] for a decimal point whatever `setlocale()` did.
+    pub fn locale(mut self, locale: NumericLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Returns the underlying reader. Input read ahead but not parsed yet is
+    /// lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+
+    /// Reads the next token into `self.token`, returning `false` at the end
+    /// of the input.
+    fn read_token(&mut self) -> io::Result<bool> {
+        self.token.clear();
+        loop {
+            let buf = match self.inner.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if buf.is_empty() {
+                return Ok(!self.token.is_empty());
+            }
+            let skipped = if self.token.is_empty() {
+                buf.iter().take_while(|&&c| is_separator(c)).count()
+            } else {
+                0
+            };
+            let len = buf[skipped..]
+                .iter()
+                .take_while(|&&c| !is_separator(c))
+                .count();
+            self.token.extend_from_slice(&buf[skipped..skipped + len]);
+            let end = skipped + len < buf.len();
+            self.inner.consume(skipped + len);
+            if end && !self.token.is_empty() {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn parse_token(&mut self) -> io::Result<f64> {
+        self.token.push(0);
+        // the locale is restored once parsed
+        let value = LocaleGuard::new(self.locale).map(|_locale| strtod(&self.token));
+        self.token.pop();
+        let value = value.map_err(io::Error::other)?;
+        value.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("not a number: {:?}", String::from_utf8_lossy(&self.token)),
+            )
+        })
+    }
+}
+
+/// Parses the whole of `token`, ending with a 0, with `strtod()`, or returns
+/// `None` if it isn't a number.
+#[cfg(not(gpoint_no_libc))]
+fn strtod(token: &[u8]) -> Option<f64> {
+    use std::os::raw::c_char;
+    let start = token.as_ptr() as *const c_char;
+    let mut end = start as *mut c_char;
+    let value = unsafe { libc::strtod(start, &mut end) };
+    (end as usize - start as usize == token.len() - 1).then_some(value)
+}
+
+/// Parses the whole of `token`, ending with a 0, as `strtod()` does in the
+/// `C` locale, without a C library.
+#[cfg(gpoint_no_libc)]
+fn strtod(token: &[u8]) -> Option<f64> {
+    let token = std::str::from_utf8(&token[..token.len() - 1]).ok()?;
+    crate::parse_c(token).ok().map(|literal| literal.value)
+}
+
+impl<R: Read> Iterator for GReader<R> {
+    type Item = io::Result<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_token() {
+            Ok(true) => Some(self.parse_token()),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+fn is_separator(c: u8) -> bool {
+    c.is_ascii_whitespace() || c == b','
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn numbers() {
+        // tokens straddling the reader's chunks
+        let input = "25 1.5,,-2E3\n\tINF -infinity NAN 0x1.8p1 1e400 -1e-400 abc 7,";
+        let numbers: Vec<_> = GReader::new(io::Read::chain(&b"0."[..], input.as_bytes()))
+            .locale(NumericLocale::C)
+            .collect();
+        assert_eq!(numbers.len(), 11);
+        let error = numbers[9].as_ref().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "not a number: \"abc\"");
+        let numbers: Vec<_> = numbers.into_iter().filter_map(Result::ok).collect();
+        assert_eq!(numbers[..3], [0.25, 1.5, -2000.]);
+        assert_eq!(numbers[3..5], [f64::INFINITY, f64::NEG_INFINITY]);
+        assert!(numbers[5].is_nan());
+        assert_eq!(numbers[6..], [3., f64::INFINITY, -0., 7.]);
+        assert_eq!(GReader::new(&b" ,\n"[..]).count(), 0);
+    }
+}
@@ -0,0 +1,294 @@
+//! Rewriting of the backends' output according to the `Options`
+
+use crate::buffer::ByteWriter;
+use crate::{ExponentStyle, Flags, GError, NonFinite, Notation, Options};
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+
+/// Rewrites the `len` first bytes of `numstr`, the output of a backend for
+/// `value`, as requested by `options`, using `digits` as scratch space for
+/// at least `len` bytes. Returns the new length.
+pub(crate) fn rewrite(
+    numstr: &mut [u8],
+    digits: &mut [u8],
+    len: usize,
+    value: f64,
+    flags: &Flags,
+    options: &Options,
+) -> Result<usize, GError> {
+    if value.is_finite() {
+        let mut parts = Parts::parse(&numstr[..len], digits, flags.alternate)?;
+        let mut suffix = "";
+        // the options the parts are written with
+        let mut written = *options;
+        match options.notation {
+            Notation::General => (),
+            Notation::Percent { space, .. } => suffix = if space { " %" } else { "%" },
+            Notation::Engineering => parts.engineering(),
+            // `%f` with more digits than any expansion has
+            Notation::Exact => parts.trim(),
+            Notation::Go | Notation::DotNet | Notation::Excel => (),
+            Notation::Toml | Notation::Json5 | Notation::Yaml => {
+                // a fraction digit, unless an exponent makes it a float, but
+                // for YAML's canonical exponent form
+                if parts.exponent.is_none() || parts.dot || options.notation == Notation::Yaml {
+                    parts.dot = true;
+                    written.min_fraction_digits = written.min_fraction_digits.max(1);
+                }
+                // whatever the locale's
+                parts.radix = b".";
+                if options.notation == Notation::Yaml {
+                    written.min_exponent_digits = 1;
+                }
+            }
+            Notation::Xsd => {
+                // backends were asked for `%e`: as few fraction digits as
+                // possible, but at least one
+                parts.trim();
+                parts.dot = true;
+                parts.radix = b".";
+                written.min_fraction_digits = written.min_fraction_digits.max(1);
+                written.exponent = ExponentStyle::Compact;
+                written.uppercase_exponent = true;
+            }
+            Notation::GCode { trim } => {
+                if trim && !flags.alternate {
+                    parts.trim();
+                }
+            }
+            Notation::Si => {
+                // backends were asked for `%e` with the right precision: only
+                // drop the trailing zeros `%g` wouldn't print
+                parts.engineering();
+                if let Some(prefix) = parts.exponent.and_then(si_prefix) {
+                    parts.exponent = None;
+                    suffix = prefix;
+                }
+                if !flags.alternate {
+                    parts.trim();
+                }
+            }
+        }
+        let mut out = ByteWriter::new(numstr);
+        if written.exponent == ExponentStyle::Siunitx {
+            out.write_str("\\num{")?;
+        }
+        parts.write(&mut out, &written)?;
+        out.write_str(suffix)?;
+        if written.exponent == ExponentStyle::Siunitx {
+            out.write_str("}")?;
+        }
+        return Ok(out.len());
+    }
+    // backends spell non-finite values as an optional sign and `inf` or `nan`
+    let (nan, inf, signed_nan, always_signed_inf) = match options.notation {
+        Notation::Go => ("NaN", "Inf", false, true),
+        Notation::DotNet => ("NaN", "Infinity", false, false),
+        Notation::Toml => ("nan", "inf", !options.unsigned_nan, false),
+        Notation::Json5 => ("NaN", "Infinity", !options.unsigned_nan, false),
+        Notation::Yaml => (".nan", ".inf", false, false),
+        Notation::Xsd => ("NaN", "INF", false, false),
+        _ => (options.nan, options.inf, !options.unsigned_nan, false),
+    };
+    let sign = match numstr[0] {
+        _ if value.is_nan() && !signed_nan => "",
+        b'-' => options.minus(),
+        b'+' => "+",
+        _ if always_signed_inf && value.is_infinite() => "+",
+        _ => "",
+    };
+    let spelling = if value.is_nan() { nan } else { inf };
+    let mut out = ByteWriter::new(numstr);
+    let start = match options.non_finite {
+        NonFinite::AsIs => {
+            write!(out, "{}{}", sign, spelling)?;
+            sign.len()
+        }
+        NonFinite::Null => {
+            out.write_str("null")?;
+            return Ok(out.len());
+        }
+        NonFinite::Quoted => {
+            write!(out, "\"{}{}\"", sign, spelling)?;
+            sign.len() + 1
+        }
+        NonFinite::Error => return Err(GError::NonFinite),
+    };
+    let len = out.len();
+    if options.uppercase_non_finite {
+        numstr[start..start + spelling.len()].make_ascii_uppercase();
+    }
+    Ok(len)
+}
+
+/// A finite number as printed by a backend, split into its parts.
+struct Parts<'a> {
+    /// `-`, `+` or nothing.
+    sign: &'static str,
+    /// The digits of the mantissa, without the decimal point.
+    digits: &'a [u8],
+    /// How many of the digits are before the decimal point. May be more than
+    /// there are digits, which are then completed with zeros.
+    point: usize,
+    /// The decimal point printed by the backend, which depends on the locale,
+    /// or nothing if it printed none.
+    radix: &'a [u8],
+    /// Whether the decimal point is printed even without digits after it, as
+    /// requested by the `#` flag.
+    dot: bool,
+    exponent: Option<i32>,
+}
+
+impl<'a> Parts<'a> {
+    /// Splits `text`, failing with [`GError::Backend`] if its exponent isn't
+    /// an `i32`.
+    fn parse(text: &[u8], digits: &'a mut [u8], alternate: bool) -> Result<Self, GError> {
+        let (sign, text) = match text.first() {
+            Some(b'-') => ("-", &text[1..]),
+            Some(b'+') => ("+", &text[1..]),
+            _ => ("", text),
+        };
+        let (mantissa, exponent) = match text.iter().position(|&c| c == b'e') {
+            Some(e) => (
+                &text[..e],
+                Some(
+                    std::str::from_utf8(&text[e + 1..])
+                        .ok()
+                        .and_then(|exponent| exponent.parse().ok())
+                        .ok_or(GError::Backend)?,
+                ),
+            ),
+            None => (text, None),
+        };
+        let point = mantissa
+            .iter()
+            .position(|c| !c.is_ascii_digit())
+            .unwrap_or(mantissa.len());
+        let radix_len = mantissa[point..]
+            .iter()
+            .take_while(|c| !c.is_ascii_digit())
+            .count();
+        let (radix, fraction) = mantissa[point..].split_at(radix_len);
+        // the digits and the decimal point fit in the scratch space, as they
+        // did in the backend's output
+        let (digits, rest) = digits.split_at_mut(point + fraction.len());
+        digits[..point].copy_from_slice(&mantissa[..point]);
+        digits[point..].copy_from_slice(fraction);
+        rest[..radix.len()].copy_from_slice(radix);
+        Ok(Parts {
+            sign,
+            digits,
+            point,
+            radix: &rest[..radix.len()],
+            dot: alternate,
+            exponent,
+        })
+    }
+
+    /// Makes the exponent a multiple of 3, moving the decimal point.
+    fn engineering(&mut self) {
+        if let Some(exponent) = self.exponent {
+            let shift = exponent.rem_euclid(3);
+            self.point += shift as usize;
+            self.exponent = Some(exponent - shift);
+        }
+    }
+
+    /// Removes trailing zeros after the decimal point.
+    fn trim(&mut self) {
+        while self.digits.len() > self.point && self.digits.last() == Some(&b'0') {
+            self.digits = &self.digits[..self.digits.len() - 1];
+        }
+    }
+
+    fn write(&self, out: &mut ByteWriter<'_>, options: &Options) -> fmt::Result {
+        out.write_str(if self.sign == "-" {
+            options.minus()
+        } else {
+            self.sign
+        })?;
+        let integer = &self.digits[..self.point.min(self.digits.len())];
+        for i in 0..self.point {
+            if let Some(separator) = options
+                .grouping
+                .filter(|_| i > 0 && (self.point - i).is_multiple_of(3))
+            {
+                out.write_char(separator)?;
+            }
+            out.write_char(integer.get(i).map_or('0', |&digit| digit as char))?;
+        }
+        let fraction = &self.digits[integer.len()..];
+        if self.dot || !fraction.is_empty() || options.min_fraction_digits > 0 {
+            let radix = std::str::from_utf8(self.radix).map_err(|_| fmt::Error)?;
+            out.write_str(if radix.is_empty() { "." } else { radix })?;
+            out.write_str(std::str::from_utf8(fraction).map_err(|_| fmt::Error)?)?;
+            for _ in fraction.len()..options.min_fraction_digits {
+                out.write_char('0')?;
+            }
+        }
+        if let Some(exponent) = self.exponent {
+            match options.exponent {
+                ExponentStyle::Printf => {
+                    let uppercase = options.uppercase_exponent
+                        || matches!(options.notation, Notation::DotNet | Notation::Excel);
+                    let e = match options.exponent_char {
+                        Some(marker) => marker,
+                        None if uppercase => 'E',
+                        None => 'e',
+                    };
+                    let sign = if exponent < 0 { options.minus() } else { "+" };
+                    write!(
+                        out,
+                        "{}{}{:0digits$}",
+                        e,
+                        sign,
+                        exponent.abs(),
+                        digits = options.min_exponent_digits
+                    )?;
+                }
+                ExponentStyle::Compact => {
+                    let e = match options.exponent_char {
+                        Some(marker) => marker,
+                        None if options.uppercase_exponent => 'E',
+                        None => 'e',
+                    };
+                    let sign = if exponent < 0 { options.minus() } else { "" };
+                    write!(out, "{}{}{}", e, sign, exponent.abs())?;
+                }
+                ExponentStyle::Gnuplot => write!(out, "{{\\times}}10^{{{}}}", exponent)?,
+                ExponentStyle::Latex => write!(out, "\\times10^{{{}}}", exponent)?,
+                ExponentStyle::Siunitx => write!(out, "e{}", exponent)?,
+                ExponentStyle::Superscript => {
+                    out.write_str("\u{d7}10")?;
+                    if exponent < 0 {
+                        out.write_char('\u{207b}')?;
+                    }
+                    write_superscript(out, exponent.unsigned_abs())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the SI prefix for a power of ten multiple of 3.
+fn si_prefix(exponent: i32) -> Option<&'static str> {
+    const PREFIXES: [&str; 21] = [
+        "q", "r", "y", "z", "a", "f", "p", "n", "\u{b5}", "m", "", "k", "M", "G", "T", "P", "E",
+        "Z", "Y", "R", "Q",
+    ];
+    PREFIXES
+        .get(usize::try_from(exponent / 3 + 10).ok()?)
+        .copied()
+}
+
+fn write_superscript(out: &mut ByteWriter<'_>, number: u32) -> fmt::Result {
+    const DIGITS: [char; 10] = [
+        '\u{2070}', '\u{b9}', '\u{b2}', '\u{b3}', '\u{2074}', '\u{2075}', '\u{2076}', '\u{2077}',
+        '\u{2078}', '\u{2079}',
+    ];
+    if number >= 10 {
+        write_superscript(out, number / 10)?;
+    }
+    out.write_char(DIGITS[(number % 10) as usize])
+}
@@ -0,0 +1,184 @@
+//! Control of the floating point rounding mode honored by `printf()`
+
+use std::os::raw::c_int;
+
+/// A floating point rounding mode, as set by C's `fesetround()`.
+///
+/// `printf()` rounds the digits it prints according to the current rounding
+/// mode of the calling thread, which other code may have changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round to nearest, ties to even: the default mode.
+    ToNearest,
+    /// Round toward negative infinity.
+    Downward,
+    /// Round toward positive infinity.
+    Upward,
+    /// Round toward zero.
+    TowardZero,
+}
+
+#[cfg(all(not(windows), any(target_arch = "x86", target_arch = "x86_64")))]
+const MODES: Option<[c_int; 4]> = Some([0, 0x400, 0x800, 0xc00]);
+#[cfg(all(not(windows), any(target_arch = "arm", target_arch = "aarch64")))]
+const MODES: Option<[c_int; 4]> = Some([0, 0x800000, 0x400000, 0xc00000]);
+#[cfg(all(not(windows), any(target_arch = "powerpc", target_arch = "powerpc64")))]
+const MODES: Option<[c_int; 4]> = Some([0, 3, 2, 1]);
+#[cfg(all(not(windows), any(target_arch = "riscv32", target_arch = "riscv64")))]
+const MODES: Option<[c_int; 4]> = Some([0, 2, 3, 1]);
+#[cfg(windows)]
+const MODES: Option<[c_int; 4]> = Some([0, 0x100, 0x200, 0x300]);
+#[cfg(not(any(
+    windows,
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+)))]
+const MODES: Option<[c_int; 4]> = None;
+
+#[cfg(not(gpoint_no_libc))]
+#[cfg_attr(all(unix, not(target_vendor = "apple")), link(name = "m"))]
+extern "C" {
+    fn fegetround() -> c_int;
+    fn fesetround(round: c_int) -> c_int;
+}
+
+/// Without a C library, only the default mode exists.
+#[cfg(gpoint_no_libc)]
+unsafe fn fegetround() -> c_int {
+    0
+}
+
+#[cfg(gpoint_no_libc)]
+unsafe fn fesetround(_round: c_int) -> c_int {
+    -1
+}
+
+impl RoundingMode {
+    /// Returns the current rounding mode of the calling thread, if known.
+    pub fn current() -> Option<Self> {
+        let modes = MODES?;
+        let current = unsafe { fegetround() };
+        [
+            RoundingMode::ToNearest,
+            RoundingMode::Downward,
+            RoundingMode::Upward,
+            RoundingMode::TowardZero,
+        ]
+        .iter()
+        .copied()
+        .find(|&mode| modes[mode as usize] == current)
+    }
+
+    fn to_c(self) -> Option<c_int> {
+        Some(MODES?[self as usize])
+    }
+}
+
+/// Sets the rounding mode of the calling thread for as long as it lives,
+/// restoring the previous one when dropped.
+///
+/// ```
+/// use gpoint::{GPoint, RoundingGuard, RoundingMode};
+///
+/// if let Some(_guard) = RoundingGuard::new(RoundingMode::Upward) {
+///     assert!(format!("{:.3}", GPoint(1.0001)) == "1.01");
+/// }
+/// ```
+#[derive(Debug)]
+#[must_use = "the previous rounding mode is restored when the guard is dropped"]
+pub struct RoundingGuard {
+    previous: c_int,
+}
+
+impl RoundingGuard {
+    /// Sets the given rounding mode, returning `None` if it isn't supported
+    /// on this platform.
+    pub fn new(mode: RoundingMode) -> Option<Self> {
+        let mode = mode.to_c()?;
+        let previous = unsafe { fegetround() };
+        if unsafe { fesetround(mode) } != 0 {
+            return None;
+        }
+        Some(RoundingGuard { previous })
+    }
+}
+
+impl Drop for RoundingGuard {
+    fn drop(&mut self) {
+        unsafe { fesetround(self.previous) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Backend, GPoint, Options, Spec};
+    #[test]
+    fn guard() {
+        assert_eq!(RoundingMode::current(), Some(RoundingMode::ToNearest));
+        {
+            let _guard = RoundingGuard::new(RoundingMode::TowardZero).unwrap();
+            assert_eq!(RoundingMode::current(), Some(RoundingMode::TowardZero));
+            {
+                let _guard = RoundingGuard::new(RoundingMode::Downward).unwrap();
+                assert_eq!(RoundingMode::current(), Some(RoundingMode::Downward));
+            }
+            assert_eq!(RoundingMode::current(), Some(RoundingMode::TowardZero));
+        }
+        assert_eq!(RoundingMode::current(), Some(RoundingMode::ToNearest));
+    }
+    #[test]
+    fn option() {
+        let nearest = Options::new().rounding(RoundingMode::ToNearest);
+        for (mode, res) in [
+            (RoundingMode::ToNearest, "1"),
+            (RoundingMode::Downward, "1"),
+            (RoundingMode::Upward, "1.01"),
+            (RoundingMode::TowardZero, "1"),
+        ] {
+            let _guard = RoundingGuard::new(mode).unwrap();
+            assert_eq!(format!("{:.3}", GPoint(1.0001)), res);
+            assert_eq!(format!("{:.3}", GPoint(1.0001).with(nearest)), "1");
+            assert_eq!(RoundingMode::current(), Some(mode));
+        }
+    }
+    #[test]
+    fn deterministic() {
+        let pure = Options::new().backend(Backend::Pure);
+        let upward = Options::new().rounding(RoundingMode::Upward);
+        for mode in [
+            RoundingMode::ToNearest,
+            RoundingMode::Downward,
+            RoundingMode::Upward,
+            RoundingMode::TowardZero,
+        ] {
+            let _guard = RoundingGuard::new(mode).unwrap();
+            for options in [
+                Options::new(),
+                upward,
+                Options::new().backend(Backend::Strfromd),
+            ] {
+                let options = options.deterministic_rounding(true);
+                for &num in crate::testing::corpus() {
+                    for spec in ["%.3g", "%.17g", "%#.1e", "%.2f"] {
+                        let spec = Spec::parse(spec).unwrap();
+                        assert_eq!(
+                            GPoint(num).with(options).try_to_string_with(&spec),
+                            GPoint(num).with(pure).try_to_string_with(&spec),
+                            "{:?} {:?}",
+                            mode,
+                            spec
+                        );
+                    }
+                }
+            }
+            assert_eq!(RoundingMode::current(), Some(mode));
+        }
+    }
+}
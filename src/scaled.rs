@@ -0,0 +1,146 @@
+//! Columns of numbers sharing a power of ten
+
+use crate::{fmt_g, formatted, layout, Flags, GConfig, Options};
+use std::fmt;
+
+/// Numbers displayed as a common power of ten, followed by the residuals
+/// once it is factored out, like matplotlib's offset text on axes: the power
+/// of ten is the one the largest number is printed with when `%g` uses the
+/// scientific notation for it, e.g. `×1e-06 1.2 3.45 0.5`, else there is no
+/// header. Residuals are displayed with the precision and width given in the
+/// format string, separated by spaces.
+///
+/// ```
+/// use gpoint::GScaled;
+///
+/// let scaled = GScaled::new(&[1.2e-6, 3.45e-6, 5e-7]);
+/// assert!(format!("{}", scaled) == "\u{d7}1e-06 1.2 3.45 0.5");
+/// assert!(scaled.exponent(None) == -6);
+/// assert!(format!("{:.2}", GScaled::new(&[12., 0.5])) == "12 0.5");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct GScaled<'a> {
+    values: &'a [f64],
+    options: Options,
+}
+
+impl<'a> GScaled<'a> {
+    /// Scales `values`, displayed with the default [`Options`].
+    pub fn new(values: &'a [f64]) -> Self {
+        GScaled {
+            values,
+            options: GConfig::get(),
+        }
+    }
+
+    /// Sets the [`Options`] the power of ten and the residuals are displayed
+    /// with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The power of ten factored out with the given precision, 0 when the
+    /// numbers are displayed as they are. Non-finite numbers are ignored.
+    pub fn exponent(&self, precision: Option<usize>) -> i32 {
+        let largest = self
+            .values
+            .iter()
+            .map(|value| value.abs())
+            .filter(|value| value.is_finite())
+            .fold(0., f64::max);
+        match layout(largest, precision.or(self.options.precision)) {
+            // the powers of ten of subnormals overflow
+            Some(layout) if layout.scientific && layout.exponent > f64::MIN_10_EXP => {
+                layout.exponent
+            }
+            _ => 0,
+        }
+    }
+
+    /// The residuals of `values` once the power of ten `exponent` is factored
+    /// out, each rounded once: powers of ten are exact up to `1e22`.
+    fn residuals(&self, exponent: i32) -> impl Iterator<Item = f64> + 'a {
+        let scale = 10f64.powi(exponent.abs());
+        self.values.iter().map(move |&value| {
+            if exponent < 0 {
+                value * scale
+            } else {
+                value / scale
+            }
+        })
+    }
+}
+
+impl fmt::Display for GScaled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exponent = self.exponent(f.precision());
+        let mut separator = "";
+        if exponent != 0 {
+            f.write_str("\u{d7}")?;
+            // as a power of ten, without the width and precision of residuals
+            let header = Flags {
+                precision: Some(1),
+                ..Flags::default()
+            };
+            formatted(10f64.powi(exponent), &header, &self.options, |power| {
+                f.write_str(power)
+            })??;
+            separator = " ";
+        }
+        for residual in self.residuals(exponent) {
+            f.write_str(separator)?;
+            fmt_g(f, residual, &self.options)?;
+            separator = " ";
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExponentStyle;
+    #[test]
+    fn scaled() {
+        for (values, precision, res) in [
+            (
+                &[1.2e-6, 3.45e-6, -5e-7][..],
+                None,
+                "\u{d7}1e-06 1.2 3.45 -0.5",
+            ),
+            (&[1.5e9, 2e9, 250e6], None, "\u{d7}1e+09 1.5 2 0.25"),
+            (&[1.5e9, 2e9], Some(10), "1500000000 2000000000"),
+            (&[1234567., 1.], None, "\u{d7}1e+06 1.23457 1e-06"),
+            (&[123456.7, 1.], None, "123457 1"),
+            (&[5e-324], None, "4.94066e-324"),
+            (&[0.5, -12.25], None, "0.5 -12.25"),
+            (
+                &[1e-6, f64::NAN, f64::INFINITY],
+                None,
+                "\u{d7}1e-06 1 nan inf",
+            ),
+            (&[999999.7], None, "\u{d7}1e+06 1"),
+            (&[0., -0.], None, "0 -0"),
+            (&[], None, ""),
+        ] {
+            let scaled = GScaled::new(values);
+            let formatted = match precision {
+                Some(p) => format!("{:.*}", p, scaled),
+                None => format!("{}", scaled),
+            };
+            assert_eq!(formatted, res, "{:?}", values);
+        }
+        assert_eq!(
+            format!("{:6.2}", GScaled::new(&[3.3e-8, 1.25e-8])),
+            "\u{d7}1e-08    3.3    1.2"
+        );
+        let superscript = Options::new().exponent(ExponentStyle::Superscript);
+        assert_eq!(
+            format!("{}", GScaled::new(&[4.5e-12]).with(superscript)),
+            "\u{d7}1\u{d7}10\u{207b}\u{b9}\u{b2} 4.5"
+        );
+        assert_eq!(GScaled::new(&[1e300, -f64::INFINITY]).exponent(None), 300);
+        assert_eq!(GScaled::new(&[1e300]).exponent(Some(400)), 0);
+    }
+}
@@ -0,0 +1,66 @@
+//! Durations, as fractional seconds
+
+use crate::{fmt_g, GConfig};
+use std::fmt;
+use std::time::Duration;
+
+/// A `Duration` displayed as fractional seconds with `%g`, e.g. `1.5e-05` or
+/// `12.3`, as C benchmarks print `(double)elapsed / CLOCKS_PER_SEC`, so that
+/// timings of both implementations can be compared as text.
+///
+/// It converts into `f64`, so `GPoint(GSecs(duration))` works too, e.g. to
+/// attach [`Options`](crate::Options).
+///
+/// ```
+/// use gpoint::{GPoint, GSecs, Options};
+/// use std::time::Duration;
+///
+/// assert!(format!("{}", GSecs(Duration::from_micros(15))) == "1.5e-05");
+/// assert!(format!("{:.3}", GSecs(Duration::from_millis(12_345))) == "12.3");
+/// let options = Options::new().precision(2);
+/// assert!(format!("{}", GPoint(GSecs(Duration::from_secs(90))).with(options)) == "90");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GSecs(
+    /// Your duration you want to `Display`
+    pub Duration,
+);
+
+impl From<GSecs> for f64 {
+    /// The number of seconds, rounded to nearest for durations over about
+    /// 104 days, whose nanoseconds don't all fit in an `f64`.
+    fn from(secs: GSecs) -> f64 {
+        secs.0.as_secs_f64()
+    }
+}
+
+impl fmt::Display for GSecs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_g(f, (*self).into(), &GConfig::get())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GPoint;
+    #[test]
+    fn secs() {
+        for (duration, res) in [
+            (Duration::ZERO, "0"),
+            (Duration::from_nanos(1), "1e-09"),
+            (Duration::from_micros(15), "1.5e-05"),
+            (Duration::from_millis(1), "0.001"),
+            (Duration::from_millis(12_300), "12.3"),
+            (Duration::from_secs(1_000_000), "1e+06"),
+            (Duration::MAX, "1.84467e+19"),
+        ] {
+            assert_eq!(format!("{}", GSecs(duration)), res);
+            assert_eq!(format!("{}", GPoint(GSecs(duration))), res);
+        }
+        let duration = GSecs(Duration::new(1, 250_000_000));
+        assert_eq!(format!("{:8.2}|", duration), "     1.2|");
+        assert_eq!(format!("{:#}", duration), "1.25000");
+        assert_eq!(f64::from(GSecs(Duration::new(3, 1))), 3.000000001);
+    }
+}
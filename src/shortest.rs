@@ -0,0 +1,342 @@
+//! Shortest round-trip digits, as other languages print floats
+
+use crate::buffer::ByteWriter;
+use crate::pure::write_exponent;
+use crate::{Flags, Shortest};
+use std::cmp::Ordering;
+use std::fmt::{self, Write};
+
+/// The most significant digits a shortest round-trip `f64` has.
+const MAX_DIGITS: usize = 17;
+
+/// Formats a finite `value` into `buf` with the fewest significant digits
+/// parsing back to it, in the fixed notation for decimal exponents from -4
+/// up to `max_exponent` excluded, like `%g` does with its precision, or in
+/// the scientific one. Returns the output length.
+///
+/// Among the shortest candidates, the closest to the exact value is chosen,
+/// as Rust's `{}` and `{:e}` do.
+pub(crate) fn format_g(
+    buf: &mut [u8],
+    value: f64,
+    flags: &Flags,
+    max_exponent: i32,
+    algorithm: Shortest,
+) -> Result<usize, fmt::Error> {
+    let mut out = ByteWriter::new(buf);
+    if value.is_sign_negative() {
+        out.write_char('-')?;
+    } else if flags.plus {
+        out.write_char('+')?;
+    }
+    let mut digits = [0u8; MAX_DIGITS];
+    let (len, exponent) = match algorithm {
+        Shortest::Grisu => grisu(value.abs(), &mut digits)?,
+        Shortest::Dragon4 => dragon4(value.abs(), &mut digits),
+    };
+    let digits = std::str::from_utf8(&digits[..len]).map_err(|_| fmt::Error)?;
+
+    let fixed = exponent >= -4 && exponent < max_exponent;
+    if !fixed {
+        out.write_str(&digits[..1])?;
+        if len > 1 || flags.alternate {
+            out.write_char('.')?;
+        }
+        out.write_str(&digits[1..])?;
+        write_exponent(&mut out, exponent)?;
+    } else if exponent < 0 {
+        out.write_str("0.")?;
+        for _ in 0..-exponent - 1 {
+            out.write_char('0')?;
+        }
+        out.write_str(digits)?;
+    } else {
+        let integer = exponent as usize + 1;
+        out.write_str(&digits[..integer.min(len)])?;
+        for _ in len..integer {
+            out.write_char('0')?;
+        }
+        if len > integer || flags.alternate {
+            out.write_char('.')?;
+        }
+        out.write_str(digits.get(integer..).unwrap_or(""))?;
+    }
+    Ok(out.len())
+}
+
+/// Writes the shortest digits of a finite, positive `value` with Rust's own
+/// algorithm, Grisu falling back to Dragon4 for the few numbers it can't
+/// handle, returning their count and the decimal exponent of the first one.
+fn grisu(value: f64, digits: &mut [u8; MAX_DIGITS]) -> Result<(usize, i32), fmt::Error> {
+    let mut text = [0u8; MAX_DIGITS + 8];
+    let mut text = ByteWriter::new(&mut text);
+    write!(text, "{:e}", value)?;
+    let text = text.as_bytes();
+    let e = text.iter().position(|&c| c == b'e').ok_or(fmt::Error)?;
+    let exponent = std::str::from_utf8(&text[e + 1..])
+        .ok()
+        .and_then(|exponent| exponent.parse().ok())
+        .ok_or(fmt::Error)?;
+    let mut len = 0;
+    for &c in text[..e].iter().filter(|c| c.is_ascii_digit()) {
+        digits[len] = c;
+        len += 1;
+    }
+    Ok((len, exponent))
+}
+
+/// Writes the shortest digits of a finite, positive `value` with Steele and
+/// White's Dragon4, in its free-format variant by Burger and Dybvig, returning
+/// their count and the decimal exponent of the first one.
+///
+/// The value and the half-gaps to its neighbours are exact big integers
+/// ratios, making this slow but simple enough to be trusted as a reference.
+fn dragon4(value: f64, digits: &mut [u8; MAX_DIGITS]) -> (usize, i32) {
+    if value == 0. {
+        digits[0] = b'0';
+        return (1, 0);
+    }
+    let bits = value.to_bits();
+    let biased = (bits >> 52) as i32 & 0x7ff;
+    let fraction = bits & ((1 << 52) - 1);
+    let (mantissa, exp) = if biased == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | 1 << 52, biased - 1075)
+    };
+    // numbers parsing back to `value` may end on the gaps when it is even,
+    // since parsing rounds ties to even
+    let inclusive = mantissa % 2 == 0;
+    // the gap below powers of two is half the one above
+    let closer = fraction == 0 && biased > 1;
+
+    // value = r / s, half-gaps = plus / s and minus / s
+    let shift = if closer { 2 } else { 1 };
+    let mut r = Big::from(mantissa);
+    r.mul_pow2(shift);
+    let mut s = Big::from(1);
+    s.mul_pow2(shift);
+    let mut plus = Big::from(if closer { 2 } else { 1 });
+    let mut minus = Big::from(1);
+    if exp >= 0 {
+        r.mul_pow2(exp as usize);
+        plus.mul_pow2(exp as usize);
+        minus.mul_pow2(exp as usize);
+    } else {
+        s.mul_pow2(-exp as usize);
+    }
+
+    // scale to 10^(k - 1) <= value < 10^(k + 1), then decide which
+    let log2 = exp + 63 - mantissa.leading_zeros() as i32;
+    let mut k = (log2 as f64 * std::f64::consts::LOG10_2).floor() as i32 + 1;
+    if k >= 0 {
+        s.mul_pow10(k as usize);
+    } else {
+        r.mul_pow10(-k as usize);
+        plus.mul_pow10(-k as usize);
+        minus.mul_pow10(-k as usize);
+    }
+    let high = |r: &Big, plus: &Big| match r.add(plus).cmp(&s) {
+        Ordering::Greater => true,
+        Ordering::Equal => inclusive,
+        Ordering::Less => false,
+    };
+    if high(&r, &plus) {
+        k += 1;
+    } else {
+        r.mul_small(10);
+        plus.mul_small(10);
+        minus.mul_small(10);
+    }
+
+    let mut len = 0;
+    loop {
+        let mut digit = 0;
+        while r >= s {
+            r.sub_assign(&s);
+            digit += 1;
+        }
+        let down = match r.cmp(&minus) {
+            Ordering::Less => true,
+            Ordering::Equal => inclusive,
+            Ordering::Greater => false,
+        };
+        let up = high(&r, &plus);
+        digits[len] = b'0' + digit;
+        len += 1;
+        if down || up {
+            // the closest of both candidates, ties up as Rust does
+            let mut twice = r;
+            twice.mul_pow2(1);
+            if up && (!down || twice >= s) {
+                round_up(digits, &mut len, &mut k);
+            }
+            return (len, k - 1);
+        }
+        r.mul_small(10);
+        plus.mul_small(10);
+        minus.mul_small(10);
+    }
+}
+
+/// Adds one to the last digit, carrying into the previous ones.
+fn round_up(digits: &mut [u8; MAX_DIGITS], len: &mut usize, k: &mut i32) {
+    while *len > 0 && digits[*len - 1] == b'9' {
+        *len -= 1;
+    }
+    if *len == 0 {
+        digits[0] = b'1';
+        *len = 1;
+        *k += 1;
+    } else {
+        digits[*len - 1] += 1;
+    }
+}
+
+/// Limbs of 32 bits are enough for the largest ratios, powers of ten and
+/// subnormal scales included.
+const LIMBS: usize = 40;
+
+/// An unsigned big integer, least significant limb first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Big([u32; LIMBS]);
+
+impl From<u64> for Big {
+    fn from(value: u64) -> Self {
+        let mut big = Big([0; LIMBS]);
+        big.0[0] = value as u32;
+        big.0[1] = (value >> 32) as u32;
+        big
+    }
+}
+
+impl Big {
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0;
+        for limb in self.0.iter_mut() {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+    }
+
+    fn mul_pow2(&mut self, n: usize) {
+        let (limbs, bits) = (n / 32, n % 32);
+        self.0.copy_within(..LIMBS - limbs, limbs);
+        self.0[..limbs].iter_mut().for_each(|limb| *limb = 0);
+        if bits > 0 {
+            for i in (0..LIMBS).rev() {
+                let below = if i > 0 {
+                    self.0[i - 1] >> (32 - bits)
+                } else {
+                    0
+                };
+                self.0[i] = self.0[i] << bits | below;
+            }
+        }
+    }
+
+    fn mul_pow10(&mut self, mut n: usize) {
+        while n >= 9 {
+            self.mul_small(1_000_000_000);
+            n -= 9;
+        }
+        self.mul_small(10u32.pow(n as u32));
+    }
+
+    fn add(&self, other: &Big) -> Big {
+        let mut sum = *self;
+        let mut carry = 0;
+        for (limb, &other) in sum.0.iter_mut().zip(&other.0) {
+            let total = u64::from(*limb) + u64::from(other) + carry;
+            *limb = total as u32;
+            carry = total >> 32;
+        }
+        sum
+    }
+
+    fn sub_assign(&mut self, other: &Big) {
+        let mut borrow = 0;
+        for (limb, &other) in self.0.iter_mut().zip(&other.0) {
+            let (difference, under) = limb.overflowing_sub(other);
+            let (difference, under_borrow) = difference.overflowing_sub(borrow);
+            *limb = difference;
+            borrow = u32::from(under || under_borrow);
+        }
+    }
+}
+
+impl PartialOrd for Big {
+    fn partial_cmp(&self, other: &Big) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Big {
+    fn cmp(&self, other: &Big) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn shortest() {
+        let mut flags = Flags::default();
+        for (num, max_exponent, res) in [
+            (0., 6, "0"),
+            (-0., 6, "-0"),
+            (0.1, 6, "0.1"),
+            (1. / 3., 6, "0.3333333333333333"),
+            (123456., 6, "123456"),
+            (1234567., 6, "1.234567e+06"),
+            (1e21, 21, "1e+21"),
+            (1e20, 21, "100000000000000000000"),
+            (0.0001, 6, "0.0001"),
+            (0.00001234, 6, "1.234e-05"),
+            (5e-324, 6, "5e-324"),
+            (f64::MAX, 6, "1.7976931348623157e+308"),
+        ] {
+            for algorithm in [Shortest::Grisu, Shortest::Dragon4] {
+                let mut buf = [0; 32];
+                let len = format_g(&mut buf, num, &flags, max_exponent, algorithm).unwrap();
+                assert_eq!(std::str::from_utf8(&buf[..len]).unwrap(), res);
+            }
+        }
+        flags.plus = true;
+        flags.alternate = true;
+        let mut buf = [0; 32];
+        let len = format_g(&mut buf, 1e10, &flags, 6, Shortest::Dragon4).unwrap();
+        assert_eq!(&buf[..len], b"+1.e+10");
+    }
+    #[test]
+    fn dragon4_same_as_grisu() {
+        let mut bits = 0x9e37_79b9_7f4a_7c15u64;
+        let random = std::iter::from_fn(|| {
+            // xorshift
+            bits ^= bits << 13;
+            bits ^= bits >> 7;
+            bits ^= bits << 17;
+            Some(f64::from_bits(bits))
+        });
+        let special = [f64::MIN_POSITIVE, 2f64.powi(-1022) * 0.5, 9007199254740993.];
+        for num in crate::testing::corpus()
+            .iter()
+            .copied()
+            .chain(special)
+            .chain(random.take(5000))
+            .filter(|num| num.is_finite())
+        {
+            let (mut grisu_digits, mut dragon_digits) = ([0; MAX_DIGITS], [0; MAX_DIGITS]);
+            let (len, exponent) = grisu(num.abs(), &mut grisu_digits).unwrap();
+            assert_eq!(
+                dragon4(num.abs(), &mut dragon_digits),
+                (len, exponent),
+                "{:e}",
+                num
+            );
+            assert_eq!(grisu_digits[..len], dragon_digits[..len], "{:e}", num);
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! `printf()` conversion specifications given at runtime
+
+use crate::{formatted, Backend, Conversion, Flags, GConfig, GError, Options};
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+/// A `printf()` floating point conversion specification such as `%10.4g`,
+/// for when the format comes from a C program or a command line rather than
+/// a Rust format string.
+///
+/// ```
+/// use gpoint::Spec;
+///
+/// let spec = Spec::parse("%-+10.4e").unwrap();
+/// assert!(spec.format(1234.5) == "+1.2345e+03");
+/// assert!(Spec::parse("%d").is_err());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Spec {
+    pub(crate) flags: Flags,
+    pub(crate) uppercase: bool,
+    backend: Option<Backend>,
+}
+
+impl Spec {
+    pub(crate) const fn new(flags: Flags) -> Self {
+        Spec {
+            flags,
+            uppercase: false,
+            backend: None,
+        }
+    }
+
+    /// `%.<precision>g`, in constant expressions too.
+    ///
+    /// ```
+    /// use gpoint::{max_formatted_len, Spec};
+    ///
+    /// const LEN: usize = max_formatted_len(&Spec::general(3));
+    /// let buf = [0u8; LEN];
+    /// ```
+    pub const fn general(precision: usize) -> Self {
+        let mut flags = Flags::new();
+        flags.precision = Some(precision);
+        Spec::new(flags)
+    }
+
+    /// Parses a conversion specification: `%`, then any of the `-+ #0` flags,
+    /// an optional width, an optional precision and one of the `g`, `G`, `e`,
+    /// `E`, `f` or `F` conversions.
+    pub fn parse(spec: &str) -> Result<Self, GError> {
+        parse(spec).ok_or(GError::InvalidSpec)
+    }
+
+    /// Formats `value` as `printf()` would with this specification, or
+    /// returns an empty string if that fails, see [`Spec::try_format()`].
+    pub fn format(&self, value: f64) -> String {
+        self.try_format(value).unwrap_or_default()
+    }
+
+    /// Formats `value` as `printf()` would with this specification.
+    ///
+    /// ```
+    /// use gpoint::Spec;
+    ///
+    /// let spec = Spec::parse("%.500f").unwrap();
+    /// assert!(spec.try_format(1.).unwrap().len() == 502);
+    /// ```
+    pub fn try_format(&self, value: f64) -> Result<String, GError> {
+        let options = self.apply(GConfig::get());
+        formatted(value, &self.flags_with(&options), &options, str::to_owned)
+    }
+
+    /// Writes `value` into `out` as `printf()` would with this
+    /// specification.
+    ///
+    /// ```
+    /// use gpoint::Spec;
+    ///
+    /// let mut out = String::from("x = ");
+    /// Spec::parse("%.3e").unwrap().write_to(1234.5, &mut out).unwrap();
+    /// assert!(out == "x = 1.234e+03");
+    /// ```
+    pub fn write_to(&self, value: f64, out: &mut impl fmt::Write) -> Result<(), GError> {
+        let options = self.apply(GConfig::get());
+        formatted(value, &self.flags_with(&options), &options, |numstr| {
+            out.write_str(numstr)
+        })?
+        .map_err(|_| GError::Write)
+    }
+
+    /// Selects the engine formatting numbers with this specification,
+    /// instead of the one of the [`Options`].
+    ///
+    /// ```
+    /// use gpoint::{Backend, Spec};
+    ///
+    /// let spec = Spec::parse("%.3g").unwrap().backend(Backend::Pure);
+    /// assert!(spec.format(1.23456) == "1.23");
+    /// ```
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// The precision, if any.
+    pub fn precision(&self) -> Option<usize> {
+        self.flags.precision
+    }
+
+    /// The conversion letter, e.g. `g` or `E`.
+    pub fn conversion(&self) -> char {
+        let letter = self.flags.conversion.letter();
+        if self.uppercase {
+            letter.to_ascii_uppercase()
+        } else {
+            letter
+        }
+    }
+
+    /// Whether the `#` flag is given.
+    pub fn alternate(&self) -> bool {
+        self.flags.alternate
+    }
+
+    /// Whether the `+` flag is given.
+    pub fn plus(&self) -> bool {
+        self.flags.plus
+    }
+
+    /// The flags of this specification, with the precision of `options` when
+    /// it gives none, as for `Display`.
+    pub(crate) fn flags_with(&self, options: &Options) -> Flags {
+        Flags {
+            precision: self.flags.precision.or(options.precision),
+            ..self.flags
+        }
+    }
+
+    /// Adds the effects of an uppercase conversion and of the selected
+    /// backend to `options`.
+    pub(crate) fn apply(&self, options: Options) -> Options {
+        let options = match self.backend {
+            Some(backend) => options.backend(backend),
+            None => options,
+        };
+        options
+            .uppercase_exponent(options.uppercase_exponent || self.uppercase)
+            .uppercase_non_finite(options.uppercase_non_finite || self.uppercase)
+    }
+}
+
+impl FromStr for Spec {
+    type Err = GError;
+
+    fn from_str(spec: &str) -> Result<Self, GError> {
+        Spec::parse(spec)
+    }
+}
+
+impl Default for Spec {
+    /// `%g`
+    fn default() -> Self {
+        Spec::new(Flags::default())
+    }
+}
+
+impl fmt::Display for Spec {
+    /// Writes the specification back in the `printf()` syntax, e.g. to give
+    /// it to a C function.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("%")?;
+        for (flag, set) in [
+            ('-', self.flags.left),
+            ('+', self.flags.plus),
+            (' ', self.flags.space),
+            ('#', self.flags.alternate),
+            ('0', self.flags.zero),
+        ] {
+            if set {
+                f.write_char(flag)?;
+            }
+        }
+        if let Some(width) = self.flags.width {
+            write!(f, "{}", width)?;
+        }
+        if let Some(precision) = self.flags.precision {
+            write!(f, ".{}", precision)?;
+        }
+        f.write_char(self.conversion())
+    }
+}
+
+/// An upper bound on the length in bytes of any number formatted with
+/// `spec`, whatever the backend or the `LC_NUMERIC` locale, with the default
+/// [`Options`], so that fixed buffers can be sized without guessing.
+///
+/// ```
+/// use gpoint::{max_formatted_len, Spec};
+///
+/// let spec = Spec::parse("%.3g").unwrap();
+/// assert!(max_formatted_len(&spec) == 13);
+/// assert!(spec.format(-1.234e-300) == "-1.23e-300");
+/// ```
+pub const fn max_formatted_len(spec: &Spec) -> usize {
+    let precision = match spec.flags.precision {
+        Some(precision) => precision,
+        None => 6,
+    };
+    let digits = match spec.flags.conversion {
+        // the precision in significant digits, after `0.000` or before an
+        // exponent such as `e-308`
+        Conversion::General => {
+            let precision = if precision == 0 { 1 } else { precision };
+            precision.saturating_add(5)
+        }
+        Conversion::Scientific => precision.saturating_add(6),
+        // the integer digits of the largest `f64`
+        Conversion::Fixed => precision.saturating_add(309),
+    };
+    // a sign, and a decimal point of up to 4 bytes in some locales
+    let len = digits.saturating_add(5);
+    match spec.flags.width {
+        Some(width) if width > len => width,
+        _ => len,
+    }
+}
+
+fn parse(spec: &str) -> Option<Spec> {
+    let mut flags = Flags::default();
+    let mut rest = spec.strip_prefix('%')?;
+    loop {
+        match rest.as_bytes().first()? {
+            b'-' => flags.left = true,
+            b'+' => flags.plus = true,
+            b' ' => flags.space = true,
+            b'#' => flags.alternate = true,
+            b'0' => flags.zero = true,
+            _ => break,
+        }
+        rest = &rest[1..];
+    }
+    let (width, rest) = number(rest)?;
+    flags.width = width;
+    let rest = match rest.strip_prefix('.') {
+        Some(rest) => {
+            let (precision, rest) = number(rest)?;
+            flags.precision = Some(precision.unwrap_or(0));
+            rest
+        }
+        None => rest,
+    };
+    let (conversion, uppercase) = match rest {
+        "g" => (Conversion::General, false),
+        "G" => (Conversion::General, true),
+        "e" => (Conversion::Scientific, false),
+        "E" => (Conversion::Scientific, true),
+        "f" => (Conversion::Fixed, false),
+        "F" => (Conversion::Fixed, true),
+        _ => return None,
+    };
+    flags.conversion = conversion;
+    Some(Spec {
+        uppercase,
+        ..Spec::new(flags)
+    })
+}
+
+/// Splits the decimal number at the start of `text`, if any, or returns
+/// `None` if it overflows.
+fn number(text: &str) -> Option<(Option<usize>, &str)> {
+    let end = text
+        .bytes()
+        .position(|c| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    match end {
+        0 => Some((None, text)),
+        _ => Some((Some(text[..end].parse().ok()?), &text[end..])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn max_len() {
+        for spec in [
+            "%g", "%.0g", "%#.17g", "%+.3e", "%.0f", "%f", "%30.2f", "% #G",
+        ] {
+            let spec = Spec::parse(spec).unwrap();
+            let longest = crate::testing::corpus()
+                .iter()
+                .map(|&num| spec.try_format(num).unwrap().len())
+                .max()
+                .unwrap();
+            assert!(longest <= max_formatted_len(&spec), "{}", spec);
+            assert!(longest + 8 > max_formatted_len(&spec), "{}", spec);
+        }
+        const LEN: usize = max_formatted_len(&Spec::general(6));
+        assert_eq!(LEN, 16);
+        assert_eq!(max_formatted_len(&Spec::parse("%40.0f").unwrap()), 314);
+    }
+    #[test]
+    fn options_precision() {
+        // used when the specification gives none, everywhere
+        let options = Options::new().precision(3);
+        let _guard = crate::ConfigGuard::new(options);
+        let mut buffer = crate::Buffer::new();
+        for (spec, res) in [("%g", "0.333"), ("%.5g", "0.33333"), ("%e", "3.333e-01")] {
+            let spec = Spec::parse(spec).unwrap();
+            let with = crate::GPoint(1. / 3.).with(options);
+            assert_eq!(spec.try_format(1. / 3.).unwrap(), res);
+            assert_eq!(with.with_formatted(&spec, str::to_owned).unwrap(), res);
+            assert_eq!(buffer.format(1. / 3., &spec).unwrap(), res);
+            let mut out = String::new();
+            spec.write_to(1. / 3., &mut out).unwrap();
+            assert_eq!(out, res);
+        }
+    }
+    #[test]
+    fn parse() {
+        for (spec, num, res) in [
+            ("%g", 42., "42"),
+            ("%10.4g", 1.23456, "     1.235"),
+            ("%-10.4g", 1.23456, "1.235     "),
+            ("%+.3e", 1234.5, "+1.234e+03"),
+            ("%010.2f", -1.23456, "-000001.23"),
+            ("%#.0f", 3., "3."),
+            ("%.f", 2.5, "2"),
+            ("% g", 42., " 42"),
+            ("% g", -42., "-42"),
+            ("%+ g", 42., "+42"),
+            ("%G", 1e-10, "1E-10"),
+            ("%E", f64::INFINITY, "INF"),
+            ("%F", f64::NAN, "NAN"),
+        ] {
+            let parsed = Spec::parse(spec).unwrap();
+            assert_eq!(parsed.format(num), res);
+            let spec = if spec == "%.f" { "%.0f" } else { spec };
+            assert_eq!(parsed.to_string(), spec);
+        }
+        for spec in [
+            "",
+            "g",
+            "%",
+            "%d",
+            "%10",
+            "%.3",
+            "%gg",
+            "%10.4g ",
+            "%.99999999999999999999g",
+            "%99999999999999999999g",
+        ] {
+            assert_eq!(
+                spec.parse::<Spec>().unwrap_err(),
+                GError::InvalidSpec,
+                "{}",
+                spec
+            );
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Summaries of slices of numbers
+
+use crate::{fmt_g, GConfig, Options};
+use std::fmt;
+
+/// The minimum, maximum, mean and standard deviation of numbers, displayed
+/// as `min=0.1 max=3.5 mean=1.27 stddev=1.93`, all with the precision and
+/// width given in the format string.
+///
+/// ```
+/// use gpoint::GStats;
+///
+/// let stats = GStats::new(&[0.1, 3.5, 0.2]);
+/// assert!(format!("{:.3}", stats) == "min=0.1 max=3.5 mean=1.27 stddev=1.93");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GStats {
+    /// How many numbers were summarized.
+    pub count: usize,
+    /// The smallest number, NaN when there are none; NaNs are ignored.
+    pub min: f64,
+    /// The largest number, NaN when there are none; NaNs are ignored.
+    pub max: f64,
+    /// The arithmetic mean, NaN when there are no numbers.
+    pub mean: f64,
+    /// The sample standard deviation, NaN with less than two numbers.
+    pub stddev: f64,
+    options: Options,
+}
+
+impl GStats {
+    /// Summarizes `values`.
+    pub fn new(values: &[f64]) -> Self {
+        let count = values.len();
+        let min = values.iter().copied().fold(f64::NAN, f64::min);
+        let max = values.iter().copied().fold(f64::NAN, f64::max);
+        // 0/0 gives a negative NaN on some platforms
+        let mean = match count {
+            0 => f64::NAN,
+            _ => values.iter().sum::<f64>() / count as f64,
+        };
+        // two passes are more accurate than accumulating squares
+        let squares: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+        let stddev = (squares / (count as f64 - 1.)).sqrt();
+        GStats {
+            count,
+            min,
+            max,
+            mean,
+            stddev: if count < 2 { f64::NAN } else { stddev },
+            options: GConfig::get(),
+        }
+    }
+
+    /// Sets the [`Options`] the numbers are displayed with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl fmt::Display for GStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in [
+            ("min=", self.min),
+            (" max=", self.max),
+            (" mean=", self.mean),
+            (" stddev=", self.stddev),
+        ] {
+            f.write_str(name)?;
+            fmt_g(f, value, &self.options)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn stats() {
+        for (values, res) in [
+            (&[1., 2., 3., 4.][..], "min=1 max=4 mean=2.5 stddev=1.29099"),
+            (&[-1.5], "min=-1.5 max=-1.5 mean=-1.5 stddev=nan"),
+            (&[], "min=nan max=nan mean=nan stddev=nan"),
+            (&[2., f64::NAN, 1.], "min=1 max=2 mean=nan stddev=nan"),
+        ] {
+            assert_eq!(format!("{}", GStats::new(values)), res);
+        }
+        let stats = GStats::new(&[1e-3, 2e-3]).with(Options::new().typographic_minus(true));
+        assert_eq!(
+            format!("{:8.2}", stats),
+            "min=   0.001 max=   0.002 mean=  0.0015 stddev= 0.00071"
+        );
+        assert_eq!(GStats::new(&[5., 7.]).count, 2);
+    }
+}
@@ -0,0 +1,173 @@
+//! SVG path data
+
+use crate::GError;
+use std::fmt::Write;
+
+/// Builds the path data of SVG `d` attributes the way minifiers write it:
+/// with the shortest digits identifying each number, optionally rounded to
+/// some significant digits, without leading zeros nor separators that aren't
+/// needed, e.g. `M.5-1L1e5 2.5.5z`.
+///
+/// ```
+/// use gpoint::SvgPathWriter;
+///
+/// let mut path = SvgPathWriter::new().precision(3);
+/// path.command('M', &[0.5, -1.])?;
+/// path.command('l', &[100000., 1. / 3., 0.25, 0.5])?;
+/// path.command('z', &[])?;
+/// assert!(path.as_str() == "M.5-1l1e5 .333.25.5z");
+/// # Ok::<(), gpoint::GError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SvgPathWriter {
+    path: String,
+    precision: Option<usize>,
+    /// Whether the path ends with a number with a decimal point, which
+    /// another beginning with one can follow without separator.
+    after_point: bool,
+    /// Whether the path ends with a number.
+    after_number: bool,
+}
+
+impl SvgPathWriter {
+    /// An empty path, with numbers written with their shortest digits.
+    pub fn new() -> Self {
+        SvgPathWriter::default()
+    }
+
+    /// Rounds numbers to the given number of significant digits, like `%g`
+    /// does, before writing them with as few digits as possible.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision.max(1));
+        self
+    }
+
+    /// Appends a command, e.g. `M` or `c`, with its arguments. Non-finite
+    /// arguments are refused, SVG lacking them.
+    pub fn command(&mut self, command: char, args: &[f64]) -> Result<(), GError> {
+        if args.iter().any(|arg| !arg.is_finite()) {
+            return Err(GError::NonFinite);
+        }
+        self.path.push(command);
+        self.after_number = false;
+        self.after_point = false;
+        for &arg in args {
+            self.number(arg);
+        }
+        Ok(())
+    }
+
+    /// Returns the path data built so far.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the path data.
+    pub fn into_string(self) -> String {
+        self.path
+    }
+
+    fn number(&mut self, value: f64) {
+        let number = svg_number(value, self.precision);
+        let separated = number.starts_with('-') || number.starts_with('.') && self.after_point;
+        if self.after_number && !separated {
+            self.path.push(' ');
+        }
+        self.after_point = number.contains('.') && !number.contains('e');
+        self.after_number = true;
+        self.path.push_str(&number);
+    }
+}
+
+/// Writes a finite number with as few characters as possible.
+fn svg_number(value: f64, precision: Option<usize>) -> String {
+    // Rust's `{:e}` gives the shortest digits identifying the number
+    let scientific = match precision {
+        Some(precision) => format!("{:.*e}", precision - 1, value.abs()),
+        None => format!("{:e}", value.abs()),
+    };
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let digits: String = mantissa.chars().filter(|&c| c != '.').collect();
+    let digits = digits.trim_end_matches('0');
+    if digits.is_empty() {
+        return "0".to_owned();
+    }
+
+    let mut fixed = String::new();
+    if exponent < 0 {
+        fixed.push('.');
+        fixed.extend(std::iter::repeat_n('0', (-exponent - 1) as usize));
+        fixed.push_str(digits);
+    } else {
+        let integer = exponent as usize + 1;
+        fixed.push_str(&digits[..integer.min(digits.len())]);
+        fixed.extend(std::iter::repeat_n(
+            '0',
+            integer.saturating_sub(digits.len()),
+        ));
+        if digits.len() > integer {
+            fixed.push('.');
+            fixed.push_str(&digits[integer..]);
+        }
+    }
+    let mut scientific = digits[..1].to_owned();
+    if digits.len() > 1 {
+        scientific.push('.');
+        scientific.push_str(&digits[1..]);
+    }
+    let _ = write!(scientific, "e{}", exponent);
+
+    let shortest = if scientific.len() < fixed.len() {
+        scientific
+    } else {
+        fixed
+    };
+    if value.is_sign_negative() {
+        format!("-{}", shortest)
+    } else {
+        shortest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn numbers() {
+        for (num, precision, res) in [
+            (0., None, "0"),
+            (-0., None, "0"),
+            (0.5, None, ".5"),
+            (-0.5, None, "-.5"),
+            (0.1, None, ".1"),
+            (1.5, None, "1.5"),
+            (100., None, "100"),
+            (1000., None, "1e3"),
+            (123456., None, "123456"),
+            (0.001, None, ".001"),
+            (0.0001, None, "1e-4"),
+            (0.000123, None, ".000123"),
+            (0.0000123, None, "1.23e-5"),
+            (1. / 3., None, ".3333333333333333"),
+            (1. / 3., Some(3), ".333"),
+            (2. / 3., Some(1), ".7"),
+            (9.96, Some(2), "10"),
+            (123456., Some(2), "1.2e5"),
+            (-1e-300, Some(6), "-1e-300"),
+        ] {
+            assert_eq!(svg_number(num, precision), res, "{:e}", num);
+        }
+    }
+    #[test]
+    fn path() {
+        let mut path = SvgPathWriter::new();
+        path.command('M', &[1., 2.]).unwrap();
+        path.command('C', &[0.5, 0.5, 1.5, -0.5, 1e-5, 0.25])
+            .unwrap();
+        path.command('Z', &[]).unwrap();
+        assert_eq!(path.as_str(), "M1 2C.5.5 1.5-.5 1e-5 .25Z");
+        assert_eq!(path.command('L', &[f64::NAN]), Err(GError::NonFinite));
+        assert_eq!(path.into_string(), "M1 2C.5.5 1.5-.5 1e-5 .25Z");
+    }
+}
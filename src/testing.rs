@@ -0,0 +1,357 @@
+//! Helpers for testing float formatting, in this crate and downstream
+
+use crate::Spec;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Values known to trip float formatting code up.
+///
+/// Contains signed zeros, subnormals, the extreme exponents, exact powers of
+/// two and ten, halfway cases for rounding, values next to the `%g` switch to
+/// the scientific notation, infinities and NaNs with various payloads.
+///
+/// ```
+/// use gpoint::{testing, GPoint};
+///
+/// for &num in testing::corpus() {
+///     assert!(!format!("{}", GPoint(num)).is_empty());
+/// }
+/// ```
+pub fn corpus() -> &'static [f64] {
+    &CORPUS
+}
+
+static CORPUS: [f64; 48] = [
+    // zeros and subnormals
+    0.,
+    -0.,
+    5e-324,
+    -5e-324,
+    1e-323,
+    2.225073858507201e-308,
+    // extreme exponents
+    f64::MIN_POSITIVE,
+    f64::MAX,
+    f64::MIN,
+    f64::EPSILON,
+    1e-308,
+    1e308,
+    // exact powers of two and ten
+    1.,
+    2.,
+    0.5,
+    0.25,
+    1024.,
+    9007199254740992.,
+    18446744073709551616.,
+    1.8014398509481984e16,
+    10.,
+    0.1,
+    1e15,
+    1e16,
+    1e22,
+    1e23,
+    // halfway cases for rounding, ties go to even
+    0.125,
+    0.375,
+    2.5,
+    3.5,
+    1.5,
+    -2.5,
+    1.0000005,
+    0.15,
+    // around the switch to the scientific notation
+    999999.,
+    999999.5,
+    9999995.,
+    0.0001,
+    9.99995e-5,
+    9.999995e-5,
+    123456789.,
+    // others
+    1.23456,
+    -1.01,
+    // non-finite values
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::NAN,
+    f64::from_bits(0xfff8_0000_0000_0000),
+    f64::from_bits(0x7ff0_0000_0000_0001),
+];
+
+/// A line of a golden file the current platform disagrees with, as reported
+/// by [`verify_golden()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// The `printf()` conversion specification.
+    pub spec: String,
+    /// The formatted value.
+    pub value: f64,
+    /// The output recorded in the golden file.
+    pub expected: String,
+    /// The output on the current platform.
+    pub actual: String,
+}
+
+/// Formats every value with every `printf()` conversion specification, one
+/// line each: the specification, the bits of the value in hexadecimal and
+/// the output, separated by tabs.
+///
+/// Failures are recorded as the error between angle brackets.
+///
+/// ```
+/// use gpoint::testing;
+///
+/// let golden = testing::golden(&[1.5], &["%g", "%.3e"]).unwrap();
+/// assert!(golden == "%g\t0x3ff8000000000000\t1.5\n%.3e\t0x3ff8000000000000\t1.500e+00\n");
+/// ```
+pub fn golden(values: &[f64], specs: &[&str]) -> io::Result<String> {
+    let mut golden = String::new();
+    for spec in specs {
+        let parsed = parse(spec)?;
+        for &value in values {
+            let _ = writeln!(
+                golden,
+                "{}\t{:#018x}\t{}",
+                spec,
+                value.to_bits(),
+                output(&parsed, value)
+            );
+        }
+    }
+    Ok(golden)
+}
+
+/// Records the [`golden()`] output of the values and specifications into the
+/// file at `path`, to pin the expected output on a platform.
+pub fn write_golden(path: impl AsRef<Path>, values: &[f64], specs: &[&str]) -> io::Result<()> {
+    fs::write(path, golden(values, specs)?)
+}
+
+/// Formats again the values recorded in the golden file at `path`, returning
+/// every line the current platform disagrees with.
+///
+/// ```no_run
+/// use gpoint::testing;
+///
+/// let mismatches = testing::verify_golden("tests/linux-glibc.golden")?;
+/// assert!(mismatches.is_empty(), "{:?}", mismatches);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn verify_golden(path: impl AsRef<Path>) -> io::Result<Vec<Mismatch>> {
+    let mut mismatches = Vec::new();
+    for line in fs::read_to_string(path)?.lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (spec, bits, expected) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(spec), Some(bits), Some(expected)) => (spec, bits, expected),
+            _ => return Err(invalid(line)),
+        };
+        let bits = bits.strip_prefix("0x").ok_or_else(|| invalid(line))?;
+        let value = u64::from_str_radix(bits, 16).map_err(|_| invalid(line))?;
+        let value = f64::from_bits(value);
+        let actual = output(&parse(spec)?, value);
+        if actual != expected {
+            mismatches.push(Mismatch {
+                spec: spec.to_owned(),
+                value,
+                expected: expected.to_owned(),
+                actual,
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Normalizes the pieces of `%g` output that vary across platforms so that
+/// snapshots recorded on one platform pass on the others: NaN spellings and
+/// their sign become `nan`, infinity spellings become `inf` and exponents
+/// get two digits at least, as glibc prints them.
+///
+/// ```
+/// use gpoint::testing;
+///
+/// assert!(testing::normalize("x = -nan(ind), y = 1.#INF, z = 1e+005") == "x = nan, y = inf, z = 1e+05");
+/// ```
+pub fn normalize(output: &str) -> String {
+    let mut normalized = String::with_capacity(output.len());
+    let mut rest = output;
+    while let Some(c) = rest.chars().next() {
+        let after_word = normalized.ends_with(|c: char| c.is_alphanumeric() || c == '.');
+        if !after_word {
+            if let Some((spelling, len)) = non_finite(rest) {
+                if spelling == "nan" && normalized.ends_with(['-', '+']) {
+                    normalized.pop();
+                }
+                normalized.push_str(spelling);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        let after_digits = normalized.ends_with(|c: char| c.is_ascii_digit() || c == '.');
+        if after_digits && (c == 'e' || c == 'E') {
+            if let Some(len) = exponent(&rest[1..]) {
+                let (sign, digits) = rest[1..1 + len].split_at(1);
+                let zeros = digits.len() - digits.trim_start_matches('0').len();
+                normalized.push(c);
+                normalized.push_str(sign);
+                normalized.push_str(&digits[zeros.min(digits.len().saturating_sub(2))..]);
+                rest = &rest[1 + len..];
+                continue;
+            }
+        }
+        normalized.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    normalized
+}
+
+/// Recognizes a non-finite value spelled as by any C library at the start of
+/// `text`, returning its normalized spelling and its length.
+fn non_finite(text: &str) -> Option<(&'static str, usize)> {
+    for &(spelling, normalized) in &[
+        ("1.#qnan", "nan"),
+        ("1.#snan", "nan"),
+        ("1.#ind", "nan"),
+        ("1.#inf", "inf"),
+        ("infinity", "inf"),
+        ("inf", "inf"),
+        ("nan", "nan"),
+    ] {
+        let prefix = match text.get(..spelling.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(spelling) => prefix,
+            _ => continue,
+        };
+        let rest = &text[prefix.len()..];
+        let suffix = if spelling.starts_with('1') {
+            // MSVCRT pads these with zeros for larger precisions
+            rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len()
+        } else if spelling == "nan" && rest.starts_with('(') {
+            // payloads like `nan(ind)` or `nan(0x1)`
+            rest.find(')').map_or(0, |end| end + 1)
+        } else {
+            0
+        };
+        let len = prefix.len() + suffix;
+        if text[len..].starts_with(char::is_alphanumeric) {
+            continue;
+        }
+        return Some((normalized, len));
+    }
+    None
+}
+
+/// Returns the length of the signed exponent digits at the start of `text`.
+fn exponent(text: &str) -> Option<usize> {
+    let digits = text.strip_prefix(['+', '-'])?;
+    let len = digits.len()
+        - digits
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    if len == 0 {
+        return None;
+    }
+    Some(1 + len)
+}
+
+fn parse(spec: &str) -> io::Result<Spec> {
+    Spec::parse(spec).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+fn output(spec: &Spec, value: f64) -> String {
+    spec.try_format(value)
+        .unwrap_or_else(|e| format!("<{}>", e))
+}
+
+fn invalid(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid golden file line: {}", line),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GPoint, Options};
+
+    #[test]
+    fn pure_same_as_libc() {
+        let consistent = Options::new().consistent(true);
+        for &num in corpus() {
+            for (res, expected) in [
+                (
+                    format!("{}", GPoint(num).with(consistent)),
+                    format!("{}", GPoint(num)),
+                ),
+                (
+                    format!("{:#.17}", GPoint(num).with(consistent)),
+                    format!("{:#.17}", GPoint(num)),
+                ),
+                (
+                    format!("{:+.0}", GPoint(num).with(consistent)),
+                    format!("{:+.0}", GPoint(num)),
+                ),
+            ] {
+                assert_eq!(res, expected, "{:e}", num);
+            }
+        }
+    }
+
+    #[test]
+    fn normalized() {
+        for (output, res) in [
+            ("1e+05", "1e+05"),
+            ("1e+005", "1e+05"),
+            ("-1.5E-010", "-1.5E-10"),
+            ("1e+100", "1e+100"),
+            ("1e+000", "1e+00"),
+            ("2.e+005", "2.e+05"),
+            ("e+005 here+005", "e+005 here+005"),
+            ("nan", "nan"),
+            ("-nan", "nan"),
+            ("NaN", "nan"),
+            ("-NAN", "nan"),
+            ("-nan(ind)", "nan"),
+            ("nan(snan)", "nan"),
+            ("1.#QNAN", "nan"),
+            ("-1.#IND00", "nan"),
+            ("  -1.#INF", "  -inf"),
+            ("Infinity", "inf"),
+            ("+INF", "+inf"),
+            ("[nan, -inf]", "[nan, -inf]"),
+            ("information, banana", "information, banana"),
+            ("1 nanometer", "1 nanometer"),
+        ] {
+            assert_eq!(normalize(output), res, "{}", output);
+        }
+    }
+
+    #[test]
+    fn golden_files() {
+        let path = std::env::temp_dir().join(format!("gpoint-{}.golden", std::process::id()));
+        write_golden(&path, corpus(), &["%g", "%+#.17G", "%010.3e", "%.400f"]).unwrap();
+        assert_eq!(verify_golden(&path).unwrap(), []);
+        let recorded = fs::read_to_string(&path).unwrap();
+        fs::write(&path, recorded.replacen("\t1.5\n", "\t1.50\n", 1)).unwrap();
+        let mismatches = verify_golden(&path).unwrap();
+        fs::write(&path, "%g\t1.5\t1.5\n").unwrap();
+        let error = verify_golden(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            mismatches,
+            [Mismatch {
+                spec: "%g".to_owned(),
+                value: 1.5,
+                expected: "1.50".to_owned(),
+                actual: "1.5".to_owned(),
+            }]
+        );
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            golden(&[1.], &["%d"]).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
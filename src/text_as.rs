@@ -0,0 +1,80 @@
+//! Conversions of floats to and from `%g` text, for serialization adapters
+
+use crate::{GConfig, GError, GPoint};
+
+/// A conversion of floats to text and back, in the shape of `serde_with`'s
+/// `SerializeAs` and `DeserializeAs`: their implementations for a local
+/// marker type call [`GTextAs::to_text()`] and [`GTextAs::from_text()`], and
+/// `serde_with` applies them to the elements of containers such as `Vec` or
+/// `HashMap` values.
+///
+/// ```
+/// use gpoint::{GPointAs, GPointAsPrec, GTextAs};
+///
+/// assert!(GPointAs::to_text(0.1 + 0.2) == Ok("0.3".to_string()));
+/// assert!(GPointAsPrec::<17>::to_text(0.1 + 0.2) == Ok("0.30000000000000004".to_string()));
+/// assert!(GPointAs::from_text("1e-06") == Ok(1e-6));
+/// ```
+pub trait GTextAs {
+    /// Formats `value`.
+    fn to_text(value: f64) -> Result<String, GError>;
+
+    /// Parses `text` as written by [`GTextAs::to_text()`], or any other
+    /// number Rust parses, failing with [`GError::InvalidNumber`].
+    fn from_text(text: &str) -> Result<f64, GError> {
+        text.parse().map_err(|_| GError::InvalidNumber)
+    }
+}
+
+/// Floats as text formatted with `%g` and the default [`Options`](crate::Options),
+/// which must keep the output parsable, e.g. without digit grouping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GPointAs {}
+
+impl GTextAs for GPointAs {
+    fn to_text(value: f64) -> Result<String, GError> {
+        GPoint(value).try_to_string()
+    }
+}
+
+/// Floats as text formatted like [`GPointAs`], with `N` significant digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GPointAsPrec<const N: usize> {}
+
+impl<const N: usize> GTextAs for GPointAsPrec<N> {
+    fn to_text(value: f64) -> Result<String, GError> {
+        GPoint(value)
+            .with(GConfig::get().precision(N))
+            .try_to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn text_as() {
+        for (num, res, res_prec) in [
+            (0., "0", "0"),
+            (1. / 3., "0.333333", "0.333"),
+            (-1234.5, "-1234.5", "-1.23e+03"),
+            (1e-10, "1e-10", "1e-10"),
+            (f64::INFINITY, "inf", "inf"),
+            (-f64::NAN, "-nan", "-nan"),
+        ] {
+            assert_eq!(GPointAs::to_text(num).unwrap(), res);
+            assert_eq!(GPointAsPrec::<3>::to_text(num).unwrap(), res_prec);
+            // formatting what was parsed gives the same text back
+            let parsed = GPointAsPrec::<3>::from_text(res_prec).unwrap();
+            assert_eq!(GPointAsPrec::<3>::to_text(parsed).unwrap(), res_prec);
+        }
+        for &num in crate::testing::corpus() {
+            let text = GPointAsPrec::<17>::to_text(num).unwrap();
+            let parsed = GPointAsPrec::<17>::from_text(&text).unwrap();
+            assert!(parsed == num || num.is_nan() && parsed.is_nan(), "{}", text);
+        }
+        for text in ["", " 1", "1,5", "0x10", "one"] {
+            assert_eq!(GPointAs::from_text(text), Err(GError::InvalidNumber));
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! The exports of a WebAssembly module for JavaScript, called by `js/gpoint.js`
+
+use crate::{GConfig, GPoint, Spec};
+use std::convert::TryFrom;
+
+/// Allocates `size` bytes in the module's memory, for JavaScript to write
+/// specifications and read output in. Returns null if `size` is 0.
+#[no_mangle]
+pub extern "C" fn gpoint_alloc(size: usize) -> *mut u8 {
+    if size == 0 {
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(vec![0u8; size].into_boxed_slice()) as *mut u8
+}
+
+/// Frees bytes allocated by [`gpoint_alloc()`].
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`gpoint_alloc()`] called with `size`, and
+/// not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn gpoint_free(ptr: *mut u8, size: usize) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, size)));
+    }
+}
+
+/// Formats `value` with the `spec_len` bytes of the `printf()` specification
+/// at `spec`, with the pure Rust backend so that browsers display exactly
+/// what servers do, whatever their C library, on top of the options of
+/// [`GConfig`]. Writes at most `size` bytes of UTF-8 output at `buf`, without
+/// a final 0.
+///
+/// Returns the length of the whole output, which was truncated if it is
+/// above `size`, or -1 if `spec` is invalid or formatting fails.
+///
+/// It is exported with the `wasm` feature, in a module built with
+/// `cargo rustc --release --target wasm32-unknown-unknown --features wasm
+/// --crate-type cdylib`.
+///
+/// # Safety
+///
+/// `spec` must point to `spec_len` readable bytes if that isn't 0, and `buf` to `size`
+/// writable ones, e.g. from [`gpoint_alloc()`].
+#[no_mangle]
+pub unsafe extern "C" fn gpoint_format_js(
+    value: f64,
+    spec: *const u8,
+    spec_len: usize,
+    buf: *mut u8,
+    size: usize,
+) -> i32 {
+    let spec = match spec_len {
+        // as for empty strings, which `gpoint_alloc()` gives no bytes for
+        0 => &[],
+        _ => std::slice::from_raw_parts(spec, spec_len),
+    };
+    let spec = match std::str::from_utf8(spec).map(Spec::parse) {
+        Ok(Ok(spec)) => spec,
+        _ => return -1,
+    };
+    let options = GConfig::get().consistent(true);
+    let len = GPoint(value).with(options).with_formatted(&spec, |numstr| {
+        let copied = numstr.len().min(size);
+        std::ptr::copy_nonoverlapping(numstr.as_ptr(), buf, copied);
+        numstr.len()
+    });
+    match len.map(i32::try_from) {
+        Ok(Ok(len)) => len,
+        _ => -1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn format_js() {
+        let format = |value, spec: &str, size| {
+            let buf = gpoint_alloc(size);
+            let len = unsafe { gpoint_format_js(value, spec.as_ptr(), spec.len(), buf, size) };
+            let output = unsafe { std::slice::from_raw_parts(buf, size.min(len.max(0) as usize)) };
+            let output = String::from_utf8(output.to_vec()).unwrap();
+            unsafe { gpoint_free(buf, size) };
+            (len, output)
+        };
+        assert_eq!(format(1. / 3., "%g", 16), (8, "0.333333".into()));
+        assert_eq!(format(-1234.5, "%+.3e", 16), (10, "-1.234e+03".into()));
+        assert_eq!(format(1e100, "%g", 3), (6, "1e+".into()));
+        assert_eq!(format(1., "%5d", 16).0, -1);
+        assert_eq!(format(1., "", 16).0, -1);
+        assert!(gpoint_alloc(0).is_null());
+        // the same output as glibc
+        for &num in crate::testing::corpus() {
+            let mut c = [0 as libc::c_char; 64];
+            let c_len = unsafe {
+                libc::snprintf(
+                    c.as_mut_ptr(),
+                    c.len(),
+                    b"%.17g\0".as_ptr() as *const _,
+                    num,
+                )
+            };
+            let c = unsafe { std::ffi::CStr::from_ptr(c.as_ptr()) };
+            assert_eq!(
+                format(num, "%.17g", 64),
+                (c_len, c.to_str().unwrap().into())
+            );
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Well-Known Text geometries
+
+use crate::{formatted, Flags, GConfig, GError, Options};
+use std::fmt;
+
+/// Writes geometries as Well-Known Text, e.g. `POINT(1.5 2.25)`, with every
+/// coordinate formatted with `%g` like GEOS and other C tooling do.
+///
+/// Coordinates are slices of 2 numbers, or 3 for geometries tagged `Z`.
+///
+/// ```
+/// use gpoint::WktWriter;
+///
+/// let wkt = WktWriter::new().precision(4);
+/// let mut out = String::new();
+/// wkt.point(&mut out, &[1.5, 2.25]).unwrap();
+/// out.push('\n');
+/// wkt.linestring(&mut out, &[[0., 0.], [1. / 3., 1e7]]).unwrap();
+/// assert!(out == "POINT(1.5 2.25)\nLINESTRING(0 0,0.3333 1e+07)");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct WktWriter {
+    flags: Flags,
+    options: Options,
+}
+
+impl WktWriter {
+    /// Writes coordinates with the default [`Options`], and their precision
+    /// or else `%g`'s default one.
+    pub fn new() -> Self {
+        WktWriter {
+            flags: Flags::default(),
+            options: GConfig::get(),
+        }
+    }
+
+    /// Sets the number of significant digits of coordinates.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.flags.precision = Some(precision);
+        self
+    }
+
+    /// Sets the [`Options`] coordinates are formatted with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes a `POINT`, or `POINT EMPTY` without coordinates.
+    pub fn point(&self, out: &mut impl fmt::Write, coord: &[f64]) -> Result<(), GError> {
+        let coords: &[&[f64]] = if coord.is_empty() { &[] } else { &[coord] };
+        self.tagged(out, "POINT", coords)?;
+        self.sequence(out, coords)
+    }
+
+    /// Writes a `LINESTRING`.
+    pub fn linestring<C: AsRef<[f64]>>(
+        &self,
+        out: &mut impl fmt::Write,
+        coords: &[C],
+    ) -> Result<(), GError> {
+        self.tagged(out, "LINESTRING", coords)?;
+        self.sequence(out, coords)
+    }
+
+    /// Writes a `POLYGON` from its rings, the exterior one first.
+    pub fn polygon<R: AsRef<[C]>, C: AsRef<[f64]>>(
+        &self,
+        out: &mut impl fmt::Write,
+        rings: &[R],
+    ) -> Result<(), GError> {
+        let first = rings
+            .iter()
+            .map(AsRef::as_ref)
+            .find(|ring| !ring.is_empty());
+        self.tagged(out, "POLYGON", first.unwrap_or(&[]))?;
+        if rings.is_empty() {
+            return Ok(());
+        }
+        write(out, "(")?;
+        for (i, ring) in rings.iter().enumerate() {
+            if i > 0 {
+                write(out, ",")?;
+            }
+            self.sequence(out, ring.as_ref())?;
+        }
+        write(out, ")")
+    }
+
+    /// Writes the geometry tag, with `Z` for 3D coordinates, and `EMPTY`
+    /// without coordinates.
+    fn tagged<C: AsRef<[f64]>>(
+        &self,
+        out: &mut impl fmt::Write,
+        tag: &str,
+        coords: &[C],
+    ) -> Result<(), GError> {
+        write(out, tag)?;
+        match coords.first().map(|coord| coord.as_ref().len()) {
+            None => write(out, " EMPTY"),
+            Some(3) => write(out, " Z "),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Writes coordinates between parentheses, nothing if there are none.
+    fn sequence<C: AsRef<[f64]>>(
+        &self,
+        out: &mut impl fmt::Write,
+        coords: &[C],
+    ) -> Result<(), GError> {
+        if coords.is_empty() {
+            return Ok(());
+        }
+        let flags = Flags {
+            precision: self.flags.precision.or(self.options.precision),
+            ..self.flags
+        };
+        write(out, "(")?;
+        for (i, coord) in coords.iter().enumerate() {
+            if i > 0 {
+                write(out, ",")?;
+            }
+            for (j, &value) in coord.as_ref().iter().enumerate() {
+                if j > 0 {
+                    write(out, " ")?;
+                }
+                formatted(value, &flags, &self.options, |numstr| out.write_str(numstr))?
+                    .map_err(|_| GError::Write)?;
+            }
+        }
+        write(out, ")")
+    }
+}
+
+impl Default for WktWriter {
+    fn default() -> Self {
+        WktWriter::new()
+    }
+}
+
+fn write(out: &mut impl fmt::Write, text: &str) -> Result<(), GError> {
+    out.write_str(text).map_err(|_| GError::Write)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn wkt() {
+        let wkt = WktWriter::new();
+        let mut out = String::new();
+        wkt.point(&mut out, &[]).unwrap();
+        out.push('|');
+        wkt.point(&mut out, &[-1.5, 2., 1e-10]).unwrap();
+        out.push('|');
+        wkt.linestring::<[f64; 2]>(&mut out, &[]).unwrap();
+        out.push('|');
+        let square = [[0., 0.], [10., 0.], [10., 10.], [0., 0.]];
+        let hole = [[1., 1.], [2., 1.], [1., 1.]];
+        wkt.polygon(&mut out, &[&square[..], &hole[..]]).unwrap();
+        out.push('|');
+        wkt.polygon::<&[[f64; 2]], _>(&mut out, &[]).unwrap();
+        assert_eq!(
+            out,
+            "POINT EMPTY|POINT Z (-1.5 2 1e-10)|LINESTRING EMPTY|\
+             POLYGON((0 0,10 0,10 10,0 0),(1 1,2 1,1 1))|POLYGON EMPTY"
+        );
+        let mut out = String::new();
+        WktWriter::new()
+            .precision(17)
+            .point(&mut out, &[0.1, f64::MAX])
+            .unwrap();
+        assert_eq!(out, "POINT(0.10000000000000001 1.7976931348623157e+308)");
+        // the precision of the options, unless one is set
+        let _guard = crate::ConfigGuard::new(Options::new().precision(3));
+        let mut out = String::new();
+        WktWriter::new().point(&mut out, &[1. / 3., 2.]).unwrap();
+        WktWriter::new()
+            .precision(5)
+            .point(&mut out, &[1. / 3., 2.])
+            .unwrap();
+        assert_eq!(out, "POINT(0.333 2)POINT(0.33333 2)");
+    }
+}
@@ -0,0 +1,114 @@
+//! Streaming formatted numbers into `io::Write` sinks
+
+use crate::{formatted, Flags, GConfig, Options, Spec};
+use std::io::{self, BufWriter, Write};
+
+/// A buffered writer streaming formatted numbers into any `io::Write`,
+/// without allocating a `String` per number.
+///
+/// ```
+/// use gpoint::{GWriter, Spec};
+///
+/// let mut writer = GWriter::new(Vec::new());
+/// writer.write_g(42.)?;
+/// writer.write_sep(", ")?;
+/// writer.write_g_spec(1.23456, &Spec::parse("%.3f").unwrap())?;
+/// assert!(writer.into_inner()? == b"42, 1.235");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct GWriter<W: Write> {
+    inner: BufWriter<W>,
+    options: Options,
+}
+
+impl<W: Write> GWriter<W> {
+    /// Wraps `inner`, formatting numbers with the default [`Options`].
+    pub fn new(inner: W) -> Self {
+        GWriter {
+            inner: BufWriter::new(inner),
+            options: GConfig::get(),
+        }
+    }
+
+    /// Sets the [`Options`] numbers are formatted with.
+    pub fn with(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes `value` as `printf("%g")` would.
+    pub fn write_g(&mut self, value: impl Into<f64>) -> io::Result<()> {
+        let options = self.options;
+        let flags = Flags {
+            precision: options.precision,
+            ..Flags::default()
+        };
+        self.write_flags(value.into(), &flags, &options)
+    }
+
+    /// Writes `value` as `printf()` would with the given specification.
+    pub fn write_g_spec(&mut self, value: impl Into<f64>, spec: &Spec) -> io::Result<()> {
+        let options = spec.apply(self.options);
+        self.write_flags(value.into(), &spec.flags_with(&options), &options)
+    }
+
+    /// Writes a separator, e.g. between numbers.
+    pub fn write_sep(&mut self, separator: &str) -> io::Result<()> {
+        self.inner.write_all(separator.as_bytes())
+    }
+
+    /// Flushes the buffered output into the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Flushes the buffered output and returns the underlying writer.
+    pub fn into_inner(self) -> io::Result<W> {
+        self.inner.into_inner().map_err(|e| e.into_error())
+    }
+
+    fn write_flags(&mut self, value: f64, flags: &Flags, options: &Options) -> io::Result<()> {
+        let inner = &mut self.inner;
+        formatted(value, flags, options, |numstr| {
+            inner.write_all(numstr.as_bytes())
+        })
+        .map_err(io::Error::other)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn stream() {
+        let mut writer = GWriter::new(Vec::new()).with(Options::new().unsigned_nan(true));
+        let spec = Spec::parse("%8.3G").unwrap();
+        for num in [42., -1.01, 1e-10, -f64::NAN] {
+            writer.write_g(num).unwrap();
+            writer.write_sep("|").unwrap();
+            writer.write_g_spec(num as f32, &spec).unwrap();
+            writer.write_sep("\n").unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(writer.into_inner().unwrap()).unwrap(),
+            "42|      42\n-1.01|   -1.01\n1e-10|   1E-10\nnan|     NAN\n"
+        );
+        // the same output as `GPoint::with()`, with the precision of the options
+        let options = Options::new().precision(3);
+        let mut writer = GWriter::new(Vec::new()).with(options);
+        writer.write_g(1. / 3.).unwrap();
+        let _guard = crate::ConfigGuard::new(options);
+        let mut guarded = GWriter::new(Vec::new());
+        guarded.write_g(1. / 3.).unwrap();
+        let expected = crate::GPoint(1. / 3.).with(options).to_string();
+        assert_eq!(
+            String::from_utf8(writer.into_inner().unwrap()).unwrap(),
+            expected
+        );
+        assert_eq!(
+            String::from_utf8(guarded.into_inner().unwrap()).unwrap(),
+            expected
+        );
+    }
+}